@@ -1,3 +1,4 @@
 # $ANDROID_SDK_ROOT/build-tools/$version/aidl
 aidl --lang=rust aidl/IRemoteService.aidl -o src/framework/scheduler/binder
+aidl --lang=rust aidl/IFasControl.aidl -o src/framework/scheduler/binder
 aidl --lang=rust aidl/IRemoteService.aidl -o zygisk/rust/src
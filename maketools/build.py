@@ -34,7 +34,9 @@ python3 ./make.py build:
     --nightly:
         Introducing more optimizations using rust nightly
     --verbose:
-        print details of build\
+        print details of build
+    --allocator=<mimalloc|jemalloc>:
+        replace the system allocator of fas-rs\
 """
 CFLAGS = (
     "-Ofast -flto -fmerge-all-constants -fno-exceptions -fomit-frame-pointer -fshort-enums \
@@ -51,6 +53,7 @@ def __parse_args(args):
     verbose = False
     clean = False
     nightly = False
+    allocator = None
 
     for arg in args:
         match arg:
@@ -69,6 +72,8 @@ def __parse_args(args):
             case "--check":
                 check = True
                 build = False
+            case "--allocator=mimalloc" | "--allocator=jemalloc":
+                allocator = arg.removeprefix("--allocator=")
             case "-h" | "--help":
                 print(build_help_text)
             case _:
@@ -81,7 +86,7 @@ def __parse_args(args):
     elif (release and debug) or (build and clean) or (check and clean):
         raise Exception("Conflicting build arguments")
 
-    return (check, clean, release, nightly, verbose)
+    return (check, clean, release, nightly, verbose, allocator)
 
 
 def __clean():
@@ -183,7 +188,7 @@ def __task_zygisk(args):
         exit(-1)
 
     try:
-        (check, clean, release, nightly, verbose) = __parse_args(args)
+        (check, clean, release, nightly, verbose, allocator) = __parse_args(args)
     except Exception as err:
         eprint(err)
         exit(-1)
@@ -228,6 +233,8 @@ def __task_zygisk(args):
 
     cargo.rust_flag("-C default-linker-libraries")
     cargo.arg("--features use_binder")
+    if allocator:
+        cargo.arg("--features use_{}".format(allocator))
     cargo.arg("--no-default-features")
     cargo.build()
 
@@ -269,7 +276,7 @@ def __task_ebpf(args):
         exit(-1)
 
     try:
-        (check, clean, release, nightly, verbose) = __parse_args(args)
+        (check, clean, release, nightly, verbose, allocator) = __parse_args(args)
     except Exception as err:
         eprint(err)
         exit(-1)
@@ -312,6 +319,8 @@ def __task_ebpf(args):
 
     cargo.rust_flag("-C default-linker-libraries")
     cargo.arg("--features use_ebpf")
+    if allocator:
+        cargo.arg("--features use_{}".format(allocator))
     cargo.build()
 
     if check:
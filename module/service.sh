@@ -44,9 +44,7 @@ for i in $(seq 0 2); do
 done
 
 if [ -f $MERGE_FLAG ]; then
-	$MODDIR/fas-rs merge $MODDIR/games.toml >$DIR/.update_games.toml
-	rm $MERGE_FLAG
-	mv $DIR/.update_games.toml $DIR/games.toml
+	$MODDIR/fas-rs merge $MODDIR/games.toml --in-place 2>$DIR/merge_log.txt && rm $MERGE_FLAG
 fi
 
 killall fas-rs
@@ -0,0 +1,97 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process,
+};
+
+use anyhow::{anyhow, bail, Result};
+
+use crate::framework::{Config, Format, Severity};
+
+const HELP: &str = "\
+fas-rs check [config] [options]:
+    --std <path>        standard config to check conflicts against\
+";
+
+struct Options {
+    config: Option<String>,
+    std: Option<String>,
+}
+
+impl Options {
+    fn parse(args: &[String]) -> Result<Self> {
+        let mut args = args.iter();
+        let mut options = Self {
+            config: None,
+            std: None,
+        };
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--std" => {
+                    options.std = Some(
+                        args.next()
+                            .cloned()
+                            .ok_or_else(|| anyhow!("Missing value of {arg}\n{HELP}"))?,
+                    );
+                }
+                _ if arg.starts_with("--") => bail!("Unknown argument {arg}\n{HELP}"),
+                _ if options.config.is_none() => options.config = Some(arg.clone()),
+                _ => bail!("Unexpected argument {arg}\n{HELP}"),
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+pub fn check(args: &[String], default_config: &Path) -> Result<()> {
+    let options = Options::parse(args)?;
+    let config_path = options.config.as_deref().map_or(default_config, Path::new);
+    let std_path = options.std.map(PathBuf::from).or_else(|| {
+        let std_path = env::current_exe().ok()?.parent()?.join("games.toml");
+        std_path.exists().then_some(std_path)
+    });
+
+    let local = fs::read_to_string(config_path)?;
+    let std = std_path.map(fs::read_to_string).transpose()?;
+
+    let diagnostics = Config::check(
+        local.as_str(),
+        std.as_deref(),
+        Format::from_path(config_path),
+    );
+    for diagnostic in &diagnostics {
+        println!("{diagnostic}");
+    }
+
+    let errors = diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.severity == Severity::Error)
+        .count();
+    let warnings = diagnostics.len() - errors;
+    println!(
+        "{}: {errors} error(s), {warnings} warning(s)",
+        config_path.display()
+    );
+
+    if errors > 0 {
+        process::exit(1);
+    }
+
+    Ok(())
+}
@@ -12,11 +12,26 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::{fs, path::PathBuf, sync::atomic::Ordering};
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicIsize, Ordering},
+        Arc,
+    },
+};
 
-use anyhow::Result;
+use super::{
+    file_handler::SysFs,
+    throttle::{self, Clamp},
+    writer::{Job, Writer},
+};
+use crate::framework::{Error, FreqBackend, FreqControl, Result};
 
-use super::{file_handler::FileHandler, OFFSET_MAP};
+const MSM_PERFORMANCE: &str = "/sys/module/msm_performance/parameters";
+
+pub fn msm_performance() -> bool {
+    Path::new(MSM_PERFORMANCE).join("cpu_max_freq").exists()
+}
 
 #[derive(Debug)]
 pub struct Info {
@@ -24,68 +39,213 @@ pub struct Info {
     pub cpus: Vec<i32>,
     path: PathBuf,
     pub freqs: Vec<isize>,
+    pub last_freq: Option<isize>,
+    pub backend: FreqBackend,
+    pub control: FreqControl,
+    /// Extension offset of this policy, shared with the entry in `OFFSET_MAP`
+    pub offset: Arc<AtomicIsize>,
+    /// Writes failed in a way that won't recover by itself, skipped until the next game
+    pub disabled: bool,
+    /// `cur_state` of the cpufreq cooling device of this policy
+    pub cooling: Option<PathBuf>,
+    pub clamp: Option<Clamp>,
+    /// Ceiling from the per-cluster thermal rules
+    pub thermal_cap: Option<isize>,
+    fs: Arc<dyn SysFs>,
 }
 
 impl Info {
-    pub fn new(path: PathBuf) -> Result<Self> {
-        let policy = path.file_name().unwrap().to_str().unwrap()[6..].parse()?;
+    pub fn new(path: PathBuf, fs: Arc<dyn SysFs>) -> Result<Self> {
+        let policy = path.file_name().unwrap().to_str().unwrap()[6..]
+            .parse()
+            .map_err(|_| Error::ParseNode)?;
 
-        let cpus: Vec<i32> = fs::read_to_string(path.join("affected_cpus"))?
+        let cpus: Vec<i32> = fs
+            .read_to_string(&path.join("affected_cpus"))
+            .map_err(|source| Error::Cpufreq { policy, source })?
             .split_whitespace()
             .map(|c| c.parse::<i32>().unwrap())
             .collect();
 
-        let mut freqs: Vec<_> = fs::read_to_string(path.join("scaling_available_frequencies"))?
-            .split_whitespace()
-            .map(|f| f.parse().unwrap())
-            .collect();
-
-        freqs.sort_unstable();
+        let freqs = Self::read_freqs(&path, fs.as_ref())?;
 
         Ok(Self {
             policy,
             cpus,
             path,
             freqs,
+            last_freq: None,
+            backend: FreqBackend::Cpufreq,
+            control: FreqControl::Pin,
+            offset: Arc::new(AtomicIsize::new(0)),
+            disabled: false,
+            cooling: None,
+            clamp: None,
+            thermal_cap: None,
+            fs,
         })
     }
 
-    pub fn write_freq(
-        &self,
-        freq: isize,
-        file_handler: &mut FileHandler,
-        weight: f64,
-    ) -> Result<()> {
+    /// Boost OPPs are only listed in `scaling_boost_frequencies`, and some vendors hide
+    /// the table entirely, then fall back to `time_in_state` or the cpuinfo bounds
+    fn read_freqs(path: &Path, fs: &dyn SysFs) -> Result<Vec<isize>> {
+        let parse = |file: &str| -> Vec<isize> {
+            fs.read_to_string(&path.join(file))
+                .map(|s| {
+                    s.split_whitespace()
+                        .filter_map(|f| f.parse().ok())
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+
+        let mut freqs = parse("scaling_available_frequencies");
+        freqs.extend(parse("scaling_boost_frequencies"));
+
+        if freqs.is_empty() {
+            freqs = fs
+                .read_to_string(&path.join("stats/time_in_state"))
+                .map(|s| {
+                    s.lines()
+                        .filter_map(|l| l.split_whitespace().next()?.parse().ok())
+                        .collect()
+                })
+                .unwrap_or_default();
+        }
+
+        if freqs.is_empty() {
+            freqs = parse("cpuinfo_min_freq");
+            freqs.extend(parse("cpuinfo_max_freq"));
+        }
+
+        if freqs.is_empty() {
+            return Err(Error::NoFreqTable(path.to_path_buf()));
+        }
+
+        freqs.sort_unstable();
+        freqs.dedup();
+
+        Ok(freqs)
+    }
+
+    pub fn write_freq(&mut self, freq: isize, weight: f64) -> Job {
+        let offset = self.offset.load(Ordering::Acquire);
         let freq = freq
-            .saturating_add(
-                OFFSET_MAP
-                    .get()
-                    .unwrap()
-                    .get(&self.policy)
-                    .unwrap()
-                    .load(Ordering::Acquire),
-            )
+            .saturating_add(offset)
             .max(self.freqs.first().copied().unwrap());
 
-        let max_freq_path = self.max_freq_path();
-        let min_freq_path = self.min_freq_path();
+        let freq = (freq as f64 * weight).round() as isize;
+        // Asking for more than the thermal limits only wastes writes
+        let ceiling = self.freqs.last().copied().unwrap();
+        let ceiling = self.clamp.map_or(ceiling, |clamp| ceiling.min(clamp.freq));
+        let ceiling = self.thermal_cap.map_or(ceiling, |cap| ceiling.min(cap));
+        let freq = freq.min(ceiling);
+        self.last_freq = Some(freq);
+
+        match self.control {
+            FreqControl::Pin => self.write_limits(freq, freq),
+            FreqControl::Limit => self.write_limits(freq, self.freqs.first().copied().unwrap()),
+            FreqControl::Boost => self.write_limits(ceiling, freq),
+        }
+    }
+
+    /// Static limits of the fixed frequency mode, kept inside the frequency table
+    pub fn write_fixed(&mut self, min_freq: isize, max_freq: isize) -> Job {
+        let lowest = self.freqs.first().copied().unwrap();
+        let highest = self.freqs.last().copied().unwrap();
+        let max_freq = max_freq.clamp(lowest, highest);
+        let min_freq = min_freq.clamp(lowest, max_freq);
+        self.last_freq = Some(max_freq);
+
+        self.write_limits(max_freq, min_freq)
+    }
+
+    pub fn reset_freq(&mut self) -> Job {
+        self.last_freq = None;
+
+        let max_freq = self.freqs.last().copied().unwrap();
+        let min_freq = self.freqs.first().copied().unwrap();
 
-        let freq = format!("{:.0}", freq as f64 * weight);
-        file_handler.write_with_workround(max_freq_path, &freq)?;
-        file_handler.write_with_workround(min_freq_path, &freq)?;
+        self.write_limits(max_freq, min_freq)
+    }
+
+    fn write_limits(&self, max_freq: isize, min_freq: isize) -> Job {
+        let writes = match self.backend {
+            FreqBackend::Cpufreq => vec![
+                (self.max_freq_path(), max_freq.to_string()),
+                (self.min_freq_path(), min_freq.to_string()),
+            ],
+            FreqBackend::MsmPerformance => {
+                let path = Path::new(MSM_PERFORMANCE);
+                vec![
+                    (path.join("cpu_max_freq"), self.msm_value(max_freq)),
+                    (path.join("cpu_min_freq"), self.msm_value(min_freq)),
+                ]
+            }
+        };
+
+        Job::new(self.policy, writes)
+    }
 
-        Ok(())
+    fn msm_value(&self, freq: isize) -> String {
+        self.cpus
+            .iter()
+            .map(|cpu| format!("{cpu}:{freq}"))
+            .collect::<Vec<_>>()
+            .join(" ")
     }
 
-    pub fn reset_freq(&self, file_handler: &mut FileHandler) -> Result<()> {
-        let max_freq_path = self.max_freq_path();
-        let min_freq_path = self.min_freq_path();
+    pub fn verify_freq(&self) -> Option<isize> {
+        let freq = self.last_freq?;
+        // The limit fas-rs moves, the other one stays at stock
+        let path = match self.control {
+            FreqControl::Boost => self.min_freq_path(),
+            FreqControl::Pin | FreqControl::Limit => self.max_freq_path(),
+        };
+        let node_freq: isize = self.fs.read_to_string(&path).ok()?.trim().parse().ok()?;
+        let (lower, upper) = self.freq_band(freq);
+
+        (!(lower..=upper).contains(&node_freq)).then_some(node_freq)
+    }
+
+    /// 0 when the policy isn't thermally throttled or has no cooling device
+    pub fn cooling_state(&self) -> u64 {
+        throttle::cooling_state(self.fs.as_ref(), self.cooling.as_deref())
+    }
+
+    pub fn reopen(&self, writer: &Writer) {
+        writer.close(vec![self.max_freq_path(), self.min_freq_path()]);
+    }
+
+    pub fn cur_freq(&self) -> Result<isize> {
+        let freq = self
+            .fs
+            .read_to_string(&self.path.join("scaling_cur_freq"))
+            .map_err(|source| Error::Cpufreq {
+                policy: self.policy,
+                source,
+            })?;
+        freq.trim().parse().map_err(|_| Error::ParseNode)
+    }
 
-        file_handler.write_with_workround(max_freq_path, self.freqs.last().unwrap().to_string())?;
-        file_handler
-            .write_with_workround(min_freq_path, self.freqs.first().unwrap().to_string())?;
+    pub fn freq_band(&self, freq: isize) -> (isize, isize) {
+        let lower = self
+            .freqs
+            .iter()
+            .rev()
+            .find(|f| **f <= freq)
+            .or_else(|| self.freqs.first())
+            .copied()
+            .unwrap_or(freq);
+        let upper = self
+            .freqs
+            .iter()
+            .find(|f| **f >= freq)
+            .or_else(|| self.freqs.last())
+            .copied()
+            .unwrap_or(freq);
 
-        Ok(())
+        (lower, upper)
     }
 
     fn max_freq_path(&self) -> PathBuf {
@@ -0,0 +1,36 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::{Duration, SystemTime};
+
+use smallvec::SmallVec;
+
+/// One `fas_update_freq` call, kept so a state dump can explain what the controller did
+#[derive(Debug)]
+pub struct Decision {
+    pub time: SystemTime,
+    /// Last frametime of the game when the decision was made
+    pub frametime: Duration,
+    pub factor: f64,
+    pub policy_freq: isize,
+    pub policies: SmallVec<[PolicyDecision; 4]>,
+}
+
+#[derive(Debug)]
+pub struct PolicyDecision {
+    pub policy: i32,
+    pub weight: f64,
+    /// Frequency queued for the policy, `None` when its writes are disabled
+    pub freq: Option<isize>,
+}
@@ -14,78 +14,207 @@
 
 use std::{
     collections::{hash_map::Entry, HashMap},
-    fs::{set_permissions, File},
-    io::{self, prelude::*, ErrorKind},
-    os::unix::fs::PermissionsExt,
+    fmt::Debug,
+    fs::{self, set_permissions, File, OpenOptions},
+    io::{self, ErrorKind},
+    os::unix::fs::{FileExt, PermissionsExt},
     path::{Path, PathBuf},
+    sync::{Arc, Mutex},
 };
 
-use anyhow::Result;
+use log::info;
 use sys_mount::{unmount, UnmountFlags};
 
+use crate::journal;
+
+pub trait SysFs: Debug + Send + Sync {
+    fn read_to_string(&self, path: &Path) -> io::Result<String>;
+    fn write(&self, path: &Path, content: &[u8]) -> io::Result<()>;
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>>;
+}
+
+#[derive(Debug)]
+pub struct RealFs;
+
+impl SysFs for RealFs {
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        fs::read_to_string(path)
+    }
+
+    fn write(&self, path: &Path, content: &[u8]) -> io::Result<()> {
+        fs::write(path, content)
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        fs::read_dir(path)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct MemoryFs {
+    files: Mutex<HashMap<PathBuf, String>>,
+}
+
+impl MemoryFs {
+    pub fn insert(&self, path: impl AsRef<Path>, content: impl Into<String>) {
+        self.files
+            .lock()
+            .unwrap()
+            .insert(path.as_ref().to_path_buf(), content.into());
+    }
+
+    pub fn get(&self, path: impl AsRef<Path>) -> Option<String> {
+        self.files.lock().unwrap().get(path.as_ref()).cloned()
+    }
+}
+
+impl SysFs for MemoryFs {
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        self.get(path).ok_or_else(|| ErrorKind::NotFound.into())
+    }
+
+    fn write(&self, path: &Path, content: &[u8]) -> io::Result<()> {
+        let content = String::from_utf8_lossy(content).into_owned();
+        self.insert(path, content);
+        Ok(())
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        let mut entries: Vec<_> = self
+            .files
+            .lock()
+            .unwrap()
+            .keys()
+            .filter_map(|file| {
+                let relative = file.strip_prefix(path).ok()?;
+                let name = relative.components().next()?;
+                Some(path.join(name))
+            })
+            .collect();
+        entries.sort_unstable();
+        entries.dedup();
+
+        if entries.is_empty() {
+            Err(ErrorKind::NotFound.into())
+        } else {
+            Ok(entries)
+        }
+    }
+}
+
+/// A node kept open for writing
+#[derive(Debug)]
+struct Node {
+    file: File,
+    /// Last value written through this fd, writing it again is skipped
+    last: Option<Vec<u8>>,
+}
+
 #[derive(Debug)]
 pub struct FileHandler {
-    files: HashMap<PathBuf, File>,
+    files: HashMap<PathBuf, Node>,
+    fs: Option<Arc<dyn SysFs>>,
+    dry_run: bool,
 }
 
 impl FileHandler {
     pub fn new() -> Self {
         Self {
             files: HashMap::new(),
+            fs: None,
+            dry_run: false,
         }
     }
 
-    pub fn read_to_string(&mut self, path: impl AsRef<Path>) -> Result<String> {
-        let mut string = String::new();
-        match self.files.entry(path.as_ref().to_path_buf()) {
-            Entry::Occupied(entry) => {
-                let mut string = String::new();
-                entry.get().read_to_string(&mut string)?;
-            }
-            Entry::Vacant(entry) => {
-                let mut file = File::open(path.as_ref())?;
-                file.read_to_string(&mut string)?;
-                entry.insert(file);
-            }
+    pub fn with_fs(fs: Arc<dyn SysFs>) -> Self {
+        Self {
+            files: HashMap::new(),
+            fs: Some(fs),
+            dry_run: false,
+        }
+    }
+
+    pub const fn dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    pub fn set_dry_run(&mut self, dry_run: bool) {
+        if self.dry_run != dry_run {
+            info!("Dry run: {dry_run}");
+            self.dry_run = dry_run;
+        }
+    }
+
+    pub fn read_to_string(&self, path: impl AsRef<Path>) -> io::Result<String> {
+        if let Some(fs) = &self.fs {
+            return fs.read_to_string(path.as_ref());
         }
 
-        Ok(string)
+        fs::read_to_string(path)
     }
 
     pub fn write_with_workround(
         &mut self,
         path: impl AsRef<Path>,
         content: impl AsRef<[u8]>,
-    ) -> Result<()> {
+    ) -> io::Result<()> {
+        if self.dry_run {
+            info!(
+                "[dry run] {} <- {}",
+                path.as_ref().display(),
+                String::from_utf8_lossy(content.as_ref())
+            );
+            return Ok(());
+        }
+
+        if let Some(fs) = &self.fs {
+            return fs.write(path.as_ref(), content.as_ref());
+        }
+
         if let Err(e) = self.write(path.as_ref(), content.as_ref()) {
             match e.kind() {
                 ErrorKind::PermissionDenied => {
                     set_permissions(path.as_ref(), PermissionsExt::from_mode(0o644))?;
-                    self.write(path, content)?;
-                    Ok(())
+                    self.write(path, content)
                 }
                 ErrorKind::InvalidInput => Ok(()),
-                _ => Err(e.into()),
+                _ => Err(e),
             }
         } else {
             Ok(())
         }
     }
 
+    /// Drop the cached fd and last value, the next write reopens the node and always goes through
+    pub fn close(&mut self, path: impl AsRef<Path>) {
+        self.files.remove(path.as_ref());
+    }
+
     fn write(&mut self, path: impl AsRef<Path>, content: impl AsRef<[u8]>) -> io::Result<()> {
-        match self.files.entry(path.as_ref().to_path_buf()) {
-            Entry::Occupied(mut entry) => {
-                entry.get_mut().write_all(content.as_ref())?;
-            }
+        let path = path.as_ref();
+        let content = content.as_ref();
+
+        let node = match self.files.entry(path.to_path_buf()) {
+            Entry::Occupied(entry) => entry.into_mut(),
             Entry::Vacant(entry) => {
-                let _ = unmount(path.as_ref(), UnmountFlags::DETACH);
-                set_permissions(path.as_ref(), PermissionsExt::from_mode(0o644))?;
-                let mut file = File::create(path)?;
-                file.write_all(content.as_ref())?;
-                entry.insert(file);
+                let _ = unmount(path, UnmountFlags::DETACH);
+                journal::record(path);
+                set_permissions(path, PermissionsExt::from_mode(0o644))?;
+                let file = OpenOptions::new().write(true).open(path)?;
+                entry.insert(Node { file, last: None })
             }
+        };
+
+        if node.last.as_deref() == Some(content) {
+            return Ok(());
         }
 
+        node.last = None;
+        node.file.write_all_at(content, 0)?;
+        node.last = Some(content.to_vec());
+
         Ok(())
     }
 }
@@ -0,0 +1,107 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use log::{info, warn};
+
+use super::cpu_info::Info;
+
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+const SUSTAINED_SAMPLES: u32 = 3;
+
+#[derive(Debug, Default, Clone, Copy)]
+struct PolicyStats {
+    samples: u64,
+    mismatches: u64,
+    conflicts: u64,
+    streak: u32,
+}
+
+#[derive(Debug)]
+pub struct IntegrityChecker {
+    stats: HashMap<i32, PolicyStats>,
+    timer: Instant,
+}
+
+impl IntegrityChecker {
+    pub fn new() -> Self {
+        Self {
+            stats: HashMap::new(),
+            timer: Instant::now(),
+        }
+    }
+
+    pub fn check(&mut self, cpu_infos: &[Info]) {
+        if self.timer.elapsed() < SAMPLE_INTERVAL {
+            return;
+        }
+
+        self.timer = Instant::now();
+
+        for cpu in cpu_infos {
+            let Some(freq) = cpu.last_freq else {
+                continue;
+            };
+            let Ok(cur_freq) = cpu.cur_freq() else {
+                continue;
+            };
+
+            let (lower, upper) = cpu.freq_band(freq);
+            let stats = self.stats.entry(cpu.policy).or_default();
+            stats.samples += 1;
+
+            if (lower..=upper).contains(&cur_freq) {
+                stats.streak = 0;
+                continue;
+            }
+
+            stats.mismatches += 1;
+            stats.streak += 1;
+
+            if stats.streak == SUSTAINED_SAMPLES {
+                warn!(
+                    "policy{}: current freq {cur_freq} KHz keeps out of intended band {lower}-{upper} KHz, governor or thermal may be interfering",
+                    cpu.policy
+                );
+            }
+        }
+    }
+
+    pub fn record_conflict(&mut self, policy: i32, freq: isize) {
+        let stats = self.stats.entry(policy).or_default();
+        stats.conflicts += 1;
+
+        if stats.conflicts == 1 {
+            warn!(
+                "policy{policy}: max freq was overwritten to {freq} KHz by another process, re-asserting it"
+            );
+        }
+    }
+
+    pub fn summary(&mut self) {
+        let mut stats: Vec<_> = self.stats.drain().collect();
+        stats.sort_unstable_by_key(|(policy, _)| *policy);
+
+        for (policy, stats) in stats.into_iter().filter(|(_, s)| s.samples > 0) {
+            info!(
+                "Session summary: policy{policy} freq mismatches {}/{} samples, write conflicts {}",
+                stats.mismatches, stats.samples, stats.conflicts
+            );
+        }
+    }
+}
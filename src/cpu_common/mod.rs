@@ -13,152 +13,693 @@
 // limitations under the License.
 
 mod cpu_info;
+mod decision;
 mod file_handler;
+mod integrity;
+mod throttle;
 mod weighting;
+mod writer;
 
 use std::{
-    collections::HashMap,
-    fs,
-    sync::{atomic::AtomicIsize, OnceLock},
-    time::Duration,
+    collections::{HashMap, VecDeque},
+    path::Path,
+    sync::{
+        atomic::{AtomicIsize, Ordering},
+        Arc, OnceLock, RwLock,
+    },
+    time::{Duration, Instant, SystemTime},
 };
 
-use anyhow::Result;
-use cpu_info::Info;
+pub use cpu_info::Info;
+use decision::{Decision, PolicyDecision};
 use file_handler::FileHandler;
+pub use file_handler::{MemoryFs, RealFs, SysFs};
+use integrity::IntegrityChecker;
 use libc::pid_t;
-#[cfg(debug_assertions)]
-use log::debug;
-use log::error;
+use log::{debug, error, info, warn};
+use throttle::Clamp;
+use writer::{Job, Writer};
 
 use crate::{
     api::{v1::ApiV1, v2::ApiV2, ApiV0},
-    Extension,
+    framework::{Error, FreqBackend, FreqControl, Recovery, Result},
+    trace, Extension,
 };
 use weighting::WeightedCalculator;
 
-const BASE_FREQ: isize = 600_000;
+static BASE_FREQ: AtomicIsize = AtomicIsize::new(600_000);
+/// Frequency writes kept for state dumps
+const WRITE_HISTORY: usize = 100;
+/// Controller decisions kept for state dumps, around 10 seconds at 120 fps
+const DECISION_HISTORY: usize = 1200;
+/// GPU busy percentage above which the frame is GPU bound and raising CPU frequency is wasted
+const GPU_BOUND_BUSY: u32 = 95;
+/// Reading back the limits and cooling states of every policy is too slow for the frame path
+const VERIFY_INTERVAL: Duration = Duration::from_secs(1);
 
-pub static OFFSET_MAP: OnceLock<HashMap<i32, AtomicIsize>> = OnceLock::new();
+/// Offsets by policy for the extension api, the frame path reads them through `Info::offset`
+pub static OFFSET_MAP: OnceLock<RwLock<HashMap<i32, Arc<AtomicIsize>>>> = OnceLock::new();
+
+pub fn set_base_freq(freq: isize) {
+    BASE_FREQ.store(freq, Ordering::Release);
+}
+
+pub fn policies() -> Result<Vec<Info>> {
+    policies_in(&(Arc::new(RealFs) as Arc<dyn SysFs>))
+}
+
+pub fn policies_in(fs: &Arc<dyn SysFs>) -> Result<Vec<Info>> {
+    let mut cpu_infos: Vec<_> = fs
+        .read_dir(Path::new("/sys/devices/system/cpu/cpufreq"))?
+        .into_iter()
+        .filter(|path| {
+            path.file_name()
+                .unwrap()
+                .to_str()
+                .unwrap()
+                .starts_with("policy")
+        })
+        .filter_map(|path| Info::new(path, fs.clone()).ok())
+        .collect();
+
+    cpu_infos.sort_by_key(|info| info.policy);
+    throttle::attach_cooling_devices(&mut cpu_infos, fs);
+
+    Ok(cpu_infos)
+}
+
+/// Offsets are never removed, so one set by an extension survives the policy going offline
+fn register_offsets(cpu_infos: &mut [Info]) {
+    let mut offsets = OFFSET_MAP
+        .get_or_init(|| RwLock::new(HashMap::new()))
+        .write()
+        .unwrap();
+
+    for cpu in cpu_infos {
+        cpu.offset = offsets
+            .entry(cpu.policy)
+            .or_insert_with(|| cpu.offset.clone())
+            .clone();
+    }
+}
+
+fn freq_range(cpu_infos: &[Info]) -> (isize, isize) {
+    let freqs = cpu_infos.iter().flat_map(|info| info.freqs.iter());
+    let max_freq = freqs.clone().max().copied().unwrap();
+    let min_freq = freqs.min().copied().unwrap();
+
+    (max_freq, min_freq)
+}
 
 #[derive(Debug)]
 pub struct Controller {
     max_freq: isize,
     min_freq: isize,
     policy_freq: isize,
+    freq_ceiling: isize,
+    write_conflicts: u64,
+    write_history: VecDeque<(SystemTime, i32, Option<isize>)>,
+    decisions: VecDeque<Decision>,
+    gpu_busy: Option<u32>,
+    backend: FreqBackend,
     cpu_infos: Vec<Info>,
-    file_handler: FileHandler,
+    fs: Arc<dyn SysFs>,
+    writer: Writer,
     weighted_calculator: WeightedCalculator,
+    integrity_checker: IntegrityChecker,
+    verify_timer: Instant,
+    relax_timer: Instant,
 }
 
 impl Controller {
-    pub fn new() -> Result<Self> {
-        let cpu_infos: Vec<_> = fs::read_dir("/sys/devices/system/cpu/cpufreq")?
-            .map(|entry| entry.unwrap().path())
-            .filter(|path| {
-                path.is_dir()
-                    && path
-                        .file_name()
-                        .unwrap()
-                        .to_str()
-                        .unwrap()
-                        .starts_with("policy")
-            })
-            .map(|path| Info::new(path).unwrap())
-            .collect();
-
-        OFFSET_MAP.get_or_init(|| {
-            cpu_infos
-                .iter()
-                .map(|cpu| (cpu.policy, AtomicIsize::new(0)))
-                .collect()
-        });
+    pub fn new(backend: FreqBackend) -> Result<Self> {
+        // Real nodes go through the cached fds of the file handler, not plain `fs::write`
+        Self::with_handler(backend, Arc::new(RealFs), FileHandler::new())
+    }
 
-        #[cfg(debug_assertions)]
-        debug!("cpu infos: {cpu_infos:?}");
+    pub fn with_fs(backend: FreqBackend, fs: Arc<dyn SysFs>) -> Result<Self> {
+        Self::with_handler(backend, fs.clone(), FileHandler::with_fs(fs))
+    }
 
-        let max_freq = cpu_infos
-            .iter()
-            .flat_map(|info| info.freqs.iter())
-            .max()
-            .copied()
-            .unwrap();
+    fn with_handler(
+        backend: FreqBackend,
+        fs: Arc<dyn SysFs>,
+        file_handler: FileHandler,
+    ) -> Result<Self> {
+        let backend = if backend == FreqBackend::MsmPerformance && !cpu_info::msm_performance() {
+            warn!("msm_performance is not available, fallback to cpufreq");
+            FreqBackend::Cpufreq
+        } else {
+            backend
+        };
 
-        let min_freq = cpu_infos
-            .iter()
-            .flat_map(|info| info.freqs.iter())
-            .min()
-            .copied()
-            .unwrap();
+        let mut cpu_infos = policies_in(&fs)?;
+        for cpu in &mut cpu_infos {
+            cpu.backend = backend;
+        }
+
+        register_offsets(&mut cpu_infos);
+
+        debug!("cpu infos: {cpu_infos:?}");
+
+        let (max_freq, min_freq) = freq_range(&cpu_infos);
+        let clusters = cpu_infos.iter().map(|info| info.cpus.clone()).collect();
 
         Ok(Self {
             max_freq,
             min_freq,
             policy_freq: max_freq,
+            freq_ceiling: max_freq,
+            write_conflicts: 0,
+            write_history: VecDeque::with_capacity(WRITE_HISTORY),
+            decisions: VecDeque::with_capacity(DECISION_HISTORY),
+            gpu_busy: None,
+            backend,
             cpu_infos,
-            file_handler: FileHandler::new(),
-            weighted_calculator: WeightedCalculator::new(),
+            writer: Writer::new(file_handler)?,
+            weighted_calculator: WeightedCalculator::new(fs.clone(), clusters),
+            fs,
+            integrity_checker: IntegrityChecker::new(),
+            verify_timer: Instant::now(),
+            relax_timer: Instant::now(),
         })
     }
 
     pub fn init_game(&mut self, extension: &Extension) {
-        self.policy_freq = self.max_freq;
+        self.rescan();
+        for cpu in &mut self.cpu_infos {
+            cpu.disabled = false;
+        }
+        self.policy_freq = self.freq_ceiling;
         extension.tigger_extentions(ApiV0::InitCpuFreq);
         extension.tigger_extentions(ApiV1::InitCpuFreq);
         extension.tigger_extentions(ApiV2::InitCpuFreq);
 
-        for cpu in &self.cpu_infos {
-            cpu.write_freq(self.max_freq, &mut self.file_handler, 1.0)
-                .unwrap_or_else(|e| error!("{e:?}"));
-        }
+        let freq = self.policy_freq;
+        self.write_policies(true, |_, cpu| cpu.write_freq(freq, 1.0));
     }
 
     pub fn init_default(&mut self, extension: &Extension) {
+        self.integrity_checker.summary();
         self.weighted_calculator.clear();
         self.policy_freq = self.max_freq;
+        self.freq_ceiling = self.max_freq;
         extension.tigger_extentions(ApiV0::ResetCpuFreq);
         extension.tigger_extentions(ApiV1::ResetCpuFreq);
         extension.tigger_extentions(ApiV2::ResetCpuFreq);
 
-        for cpu in &self.cpu_infos {
-            cpu.reset_freq(&mut self.file_handler)
-                .unwrap_or_else(|e| error!("{e:?}"));
+        self.write_policies(true, |_, cpu| cpu.reset_freq());
+    }
+
+    pub fn launch_boost(&mut self) {
+        let freq = self.max_freq;
+        self.write_policies(true, |_, cpu| cpu.write_freq(freq, 1.0));
+    }
+
+    pub fn end_launch_boost(&mut self) {
+        self.write_policies(true, |_, cpu| cpu.reset_freq());
+    }
+
+    /// Pin the listed policies to static (min, max) limits, the others are released
+    pub fn fix_freqs(&mut self, limits: &HashMap<i32, (isize, isize)>) {
+        self.write_policies(true, |_, cpu| match limits.get(&cpu.policy) {
+            Some(&(min_freq, max_freq)) => cpu.write_fixed(min_freq, max_freq),
+            None => cpu.reset_freq(),
+        });
+    }
+
+    pub const fn policy_freq(&self) -> isize {
+        self.policy_freq
+    }
+
+    pub fn set_policy_freq(&mut self, freq: isize) {
+        self.policy_freq = freq.clamp(self.min_freq, self.freq_ceiling);
+    }
+
+    /// Hold every policy at `ratio` of its own max frequency without FAS control,
+    /// FAS resumes from the same ratio afterwards
+    pub fn hold_freq(&mut self, ratio: f64) {
+        self.set_policy_freq((self.max_freq as f64 * ratio) as isize);
+        self.write_policies(true, |_, cpu| {
+            let max_freq = cpu.freqs.last().copied().unwrap();
+            cpu.write_freq((max_freq as f64 * ratio) as isize, 1.0)
+        });
+    }
+
+    pub fn freqs(&self) -> impl Iterator<Item = Option<isize>> + '_ {
+        self.cpu_infos.iter().map(|cpu| cpu.last_freq)
+    }
+
+    /// Current frequency of each policy, the last written one if it can't be read
+    pub fn cur_freqs(&self) -> Vec<(i32, isize)> {
+        self.cpu_infos
+            .iter()
+            .filter_map(|cpu| Some((cpu.policy, cpu.cur_freq().ok().or(cpu.last_freq)?)))
+            .collect()
+    }
+
+    /// Policies held below the requested frequency by a kernel thermal limit, as (policy, limit)
+    pub fn thermal_clamps(&self) -> Vec<(i32, isize)> {
+        self.cpu_infos
+            .iter()
+            .filter_map(|cpu| Some((cpu.policy, cpu.clamp?.freq)))
+            .collect()
+    }
+
+    /// Drop the thermal limits whose cooling device has backed off, checked about once a second
+    pub fn relax_clamps(&mut self) {
+        if self.relax_timer.elapsed() < VERIFY_INTERVAL {
+            return;
+        }
+        self.relax_timer = Instant::now();
+
+        for policy in &mut self.cpu_infos {
+            if policy
+                .clamp
+                .is_some_and(|clamp| policy.cooling_state() < clamp.state)
+            {
+                info!("policy{}: thermal limit relaxed", policy.policy);
+                policy.clamp = None;
+            }
         }
     }
 
-    pub fn fas_update_freq(&mut self, process: pid_t, factor: f64) {
-        self.policy_freq = self
-            .policy_freq
-            .saturating_add((BASE_FREQ as f64 * factor) as isize)
-            .clamp(self.min_freq, self.max_freq);
+    /// Whether a kernel thermal limit holds any policy below `ratio` of its max frequency
+    pub fn heavily_throttled(&self, ratio: f64) -> bool {
+        self.cpu_infos.iter().any(|cpu| {
+            let max_freq = cpu.freqs.last().copied().unwrap();
+            cpu.clamp
+                .is_some_and(|clamp| (clamp.freq as f64) < max_freq as f64 * ratio)
+        })
+    }
 
-        #[cfg(debug_assertions)]
-        {
-            debug!("change freq: {}", (BASE_FREQ as f64 * factor) as isize);
-            debug!("policy freq: {}", self.policy_freq);
+    /// Cap each policy to a fraction of its max frequency, policies missing from `caps` are released
+    pub fn set_thermal_caps(&mut self, caps: &HashMap<i32, f64>) {
+        for cpu in &mut self.cpu_infos {
+            cpu.thermal_cap = caps.get(&cpu.policy).map(|ratio| {
+                let min_freq = cpu.freqs.first().copied().unwrap();
+                let max_freq = cpu.freqs.last().copied().unwrap();
+                ((max_freq as f64 * ratio) as isize).max(min_freq)
+            });
         }
+    }
 
-        let weights = self.weighted_calculator.update(process).unwrap();
+    /// Policies capped by the per-cluster thermal rules, as (policy, cap)
+    pub fn thermal_caps(&self) -> Vec<(i32, isize)> {
+        self.cpu_infos
+            .iter()
+            .filter_map(|cpu| Some((cpu.policy, cpu.thermal_cap?)))
+            .collect()
+    }
+
+    pub const fn write_conflicts(&self) -> u64 {
+        self.write_conflicts
+    }
+
+    /// Last frequency writes as (time, policy, freq), oldest first, `None` is a reset
+    pub const fn write_history(&self) -> &VecDeque<(SystemTime, i32, Option<isize>)> {
+        &self.write_history
+    }
+
+    /// Last controller decisions, oldest first
+    pub const fn decisions(&self) -> &VecDeque<Decision> {
+        &self.decisions
+    }
+
+    pub const fn freq_ceiling(&self) -> isize {
+        self.freq_ceiling
+    }
+
+    pub fn set_dry_run(&mut self, dry_run: bool) {
+        self.writer.set_dry_run(dry_run);
+    }
 
-        for policy in &self.cpu_infos {
-            let weight = weights.weight(&policy.cpus).unwrap_or(1.0);
-            #[cfg(debug_assertions)]
+    pub fn set_freq_control(&mut self, control: FreqControl) {
+        for cpu in &mut self.cpu_infos {
+            cpu.control = control;
+        }
+    }
+
+    pub const fn set_weighting(&mut self, interval: Duration, window: Duration, decay: f64) {
+        self.weighted_calculator.set_params(interval, window, decay);
+    }
+
+    pub const fn set_critical_threads(&mut self, threads: Option<&'static [&'static str]>) {
+        self.weighted_calculator.set_critical_threads(threads);
+    }
+
+    pub fn set_freq_ceiling(&mut self, freq: isize) {
+        self.freq_ceiling = freq.clamp(self.min_freq, self.max_freq);
+        self.policy_freq = self.policy_freq.min(self.freq_ceiling);
+    }
+
+    pub const fn set_gpu_busy(&mut self, busy: Option<u32>) {
+        self.gpu_busy = busy;
+    }
+
+    /// A GPU bound frame only holds the CPU frequency. Splitting the factor between CPU and GPU
+    /// has to wait for a GPU devfreq write path, fas-rs only reads GPU busy% so far
+    pub fn fas_update_freq(&mut self, process: pid_t, frametime: Duration, factor: f64) {
+        let factor = if factor > 0.0 && self.gpu_busy.is_some_and(|busy| busy > GPU_BOUND_BUSY) {
+            debug!("gpu bound, hold cpu freq");
+            0.0
+        } else {
+            factor
+        };
+
+        self.policy_freq =
+            Self::step_freq(self.policy_freq, factor).clamp(self.min_freq, self.freq_ceiling);
+
+        debug!(
+            "change freq: {}",
+            (BASE_FREQ.load(Ordering::Acquire) as f64 * factor) as isize
+        );
+        debug!("policy freq: {}", self.policy_freq);
+
+        // The process may exit between frames, plain policy weights are fine until the next one
+        let weights = self
+            .weighted_calculator
+            .update(process)
+            .inspect_err(|e| debug!("{e:?}"))
+            .unwrap_or_default();
+
+        let freq = self.policy_freq;
+        self.write_policies(false, |index, policy| {
+            let weight = weights.weight(index).unwrap_or(1.0);
             debug!("policy{}: weight {:.2}", policy.policy, weight);
-            policy
-                .write_freq(self.policy_freq, &mut self.file_handler, weight)
-                .unwrap_or_else(|e| error!("{e:?}"));
+            let job = policy.write_freq(freq, weight);
+            if let Some(freq) = policy.last_freq {
+                trace::counter(format_args!("fas-rs policy{}_freq", policy.policy), freq);
+            }
+            job
+        });
+
+        if self.decisions.len() >= DECISION_HISTORY {
+            self.decisions.pop_front();
+        }
+        self.decisions.push_back(Decision {
+            time: SystemTime::now(),
+            frametime,
+            factor,
+            policy_freq: self.policy_freq,
+            policies: self
+                .cpu_infos
+                .iter()
+                .enumerate()
+                .map(|(index, policy)| PolicyDecision {
+                    policy: policy.policy,
+                    weight: weights.weight(index).unwrap_or(1.0),
+                    freq: policy.last_freq.filter(|_| !policy.disabled),
+                })
+                .collect(),
+        });
+    }
+
+    /// Catch limits overwritten by other processes or lowered by the kernel, called from housekeeping
+    pub fn verify_writes(&mut self) {
+        // Nodes only show what fas-rs wrote once the writer has caught up
+        if self.verify_timer.elapsed() < VERIFY_INTERVAL
+            || self.writer.dry_run()
+            || !self.writer.is_idle()
+        {
+            return;
+        }
+        self.verify_timer = Instant::now();
+
+        for policy in &mut self.cpu_infos {
+            let Some(freq) = policy.verify_freq() else {
+                continue;
+            };
+
+            // The kernel lowered the limit under us, that's throttling rather than a conflict
+            let state = policy.cooling_state();
+            if state > 0 && policy.last_freq.is_some_and(|last| freq < last) {
+                if policy.clamp.map(|clamp| clamp.freq) != Some(freq) {
+                    info!("policy{}: thermally limited to {freq}", policy.policy);
+                }
+                policy.clamp = Some(Clamp { freq, state });
+            } else {
+                self.write_conflicts += 1;
+                self.integrity_checker.record_conflict(policy.policy, freq);
+                policy.reopen(&self.writer);
+            }
+        }
+
+        self.integrity_checker.check(&self.cpu_infos);
+    }
+
+    /// Hand the writes to the writer thread, blocking ones wait until they reached the nodes
+    fn write_policies(&mut self, blocking: bool, mut write: impl FnMut(usize, &mut Info) -> Job) {
+        for (index, cpu) in self.cpu_infos.iter_mut().enumerate() {
+            if cpu.disabled {
+                continue;
+            }
+
+            self.writer.write(write(index, cpu), blocking);
+
+            if self.write_history.len() >= WRITE_HISTORY {
+                self.write_history.pop_front();
+            }
+            self.write_history
+                .push_back((SystemTime::now(), cpu.policy, cpu.last_freq));
+        }
+
+        if blocking {
+            self.writer.flush();
+        }
+
+        // Failures of earlier non-blocking writes are picked up here too
+        let mut rescan = false;
+        for e in self.writer.take_errors() {
+            match e.recovery() {
+                Recovery::Retry => debug!("{e}, written again on the next update"),
+                Recovery::Rescan => {
+                    warn!("{e}, rescanning cpu policies");
+                    rescan = true;
+                }
+                Recovery::Disable => {
+                    error!("{e}, stop writing it until the next game");
+                    if let Error::Cpufreq { policy, .. } = e {
+                        for cpu in self.cpu_infos.iter_mut().filter(|cpu| cpu.policy == policy) {
+                            cpu.disabled = true;
+                        }
+                    }
+                }
+            }
+        }
+
+        if rescan {
+            self.rescan();
+        }
+    }
+
+    /// Policies, their cores and frequency tables can change at runtime (hotplug, core isolation,
+    /// boost OPPs unlocked late), re-enumerate them instead of failing on a stale list forever
+    fn rescan(&mut self) {
+        let mut cpu_infos = match policies_in(&self.fs) {
+            Ok(cpu_infos) if !cpu_infos.is_empty() => cpu_infos,
+            Ok(_) => return,
+            Err(e) => {
+                error!("Failed to rescan cpu policies: {e:?}");
+                return;
+            }
+        };
+
+        let layout = |infos: &[Info]| -> Vec<(i32, Vec<i32>, Vec<isize>)> {
+            infos
+                .iter()
+                .map(|info| (info.policy, info.cpus.clone(), info.freqs.clone()))
+                .collect()
+        };
+        let old_layout = layout(&self.cpu_infos);
+        let new_layout = layout(&cpu_infos);
+        if old_layout == new_layout {
+            return;
+        }
+
+        info!("cpu policies changed: {old_layout:?} -> {new_layout:?}");
+
+        for cpu in &mut cpu_infos {
+            cpu.last_freq = self
+                .cpu_infos
+                .iter()
+                .find(|old| old.policy == cpu.policy)
+                .and_then(|old| old.last_freq);
+        }
+
+        for cpu in &self.cpu_infos {
+            cpu.reopen(&self.writer);
+        }
+
+        let control = self.cpu_infos.first().map(|cpu| cpu.control);
+        for cpu in &mut cpu_infos {
+            cpu.backend = self.backend;
+            cpu.control = control.unwrap_or(FreqControl::Pin);
         }
+
+        register_offsets(&mut cpu_infos);
+        self.weighted_calculator
+            .set_clusters(cpu_infos.iter().map(|info| info.cpus.clone()).collect());
+
+        (self.max_freq, self.min_freq) = freq_range(&cpu_infos);
+        self.freq_ceiling = self.freq_ceiling.clamp(self.min_freq, self.max_freq);
+        self.policy_freq = self.policy_freq.clamp(self.min_freq, self.freq_ceiling);
+        self.cpu_infos = cpu_infos;
     }
 
-    pub fn scale_factor(target_fps: u32, frame: Duration, target: Duration) -> f64 {
+    pub fn step_freq(freq: isize, factor: f64) -> isize {
+        freq.saturating_add((BASE_FREQ.load(Ordering::Acquire) as f64 * factor) as isize)
+    }
+
+    /// The policy runs once a frame, so slower targets take bigger steps to keep up per second.
+    /// Below 60fps a frame already carries more error, so the step only grows with the square root
+    fn rate_factor(target_fps: f64) -> f64 {
+        let target_fps = target_fps.max(1.0);
+        if target_fps >= 60.0 {
+            120.0 / target_fps
+        } else {
+            2.0 * (60.0 / target_fps).sqrt()
+        }
+    }
+
+    pub fn scale_factor(target_fps: f64, frame: Duration, target: Duration) -> f64 {
         if frame > target {
             let factor_a = (frame - target).as_nanos() as f64 / target.as_nanos() as f64;
-            let factor_b = 120.0 / f64::from(target_fps);
+            let factor_b = Self::rate_factor(target_fps);
             factor_a * factor_b
         } else {
             let factor_a = (target - frame).as_nanos() as f64 / target.as_nanos() as f64;
-            let factor_b = 120.0 / f64::from(target_fps);
+            let factor_b = Self::rate_factor(target_fps);
             factor_a * factor_b * -1.0
         }
     }
+
+    pub fn jitter_factor(target_fps: f64, factor: f64, jitter: Duration, threshold: f64) -> f64 {
+        let jitter = jitter.as_secs_f64();
+
+        if threshold > 0.0 && jitter > threshold {
+            let boost = (jitter - threshold) * Self::rate_factor(target_fps);
+            factor.max(boost)
+        } else {
+            factor
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LITTLE: &str = "/sys/devices/system/cpu/cpufreq/policy0";
+    const BIG: &str = "/sys/devices/system/cpu/cpufreq/policy4";
+
+    fn policy_tree() -> Arc<MemoryFs> {
+        let fs = MemoryFs::default();
+        fs.insert(format!("{LITTLE}/affected_cpus"), "0 1 2 3");
+        fs.insert(
+            format!("{LITTLE}/scaling_available_frequencies"),
+            "300000 1000000 1800000",
+        );
+        fs.insert(format!("{BIG}/affected_cpus"), "4 5 6 7");
+        fs.insert(
+            format!("{BIG}/scaling_available_frequencies"),
+            "500000 1500000 2800000",
+        );
+        Arc::new(fs)
+    }
+
+    /// (`scaling_min_freq`, `scaling_max_freq`) of a policy once the writer caught up
+    fn limits(controller: &Controller, fs: &MemoryFs, policy: &str) -> (isize, isize) {
+        controller.writer.flush();
+        let read = |node: &str| {
+            fs.get(format!("{policy}/{node}"))
+                .and_then(|freq| freq.parse().ok())
+                .unwrap_or_default()
+        };
+        (read("scaling_min_freq"), read("scaling_max_freq"))
+    }
+
+    #[test]
+    fn init_game_pins_the_highest_frequency() {
+        let fs = policy_tree();
+        let mut controller = Controller::with_fs(FreqBackend::Cpufreq, fs.clone()).unwrap();
+        controller.init_game(&Extension::noop());
+
+        assert_eq!(limits(&controller, &fs, LITTLE), (1_800_000, 1_800_000));
+        assert_eq!(limits(&controller, &fs, BIG), (2_800_000, 2_800_000));
+    }
+
+    #[test]
+    fn update_steps_within_each_table() {
+        let fs = policy_tree();
+        let mut controller = Controller::with_fs(FreqBackend::Cpufreq, fs.clone()).unwrap();
+        controller.init_game(&Extension::noop());
+        let step = BASE_FREQ.load(Ordering::Acquire);
+
+        controller.set_policy_freq(1_000_000);
+        controller.fas_update_freq(0, Duration::from_millis(8), 0.5);
+        let freq = 1_000_000 + step / 2;
+        assert_eq!(limits(&controller, &fs, LITTLE), (freq, freq));
+        assert_eq!(limits(&controller, &fs, BIG), (freq, freq));
+
+        controller.set_policy_freq(400_000);
+        controller.fas_update_freq(0, Duration::from_millis(8), -1.0);
+        assert_eq!(limits(&controller, &fs, LITTLE), (300_000, 300_000));
+        assert_eq!(limits(&controller, &fs, BIG), (500_000, 500_000));
+    }
+
+    #[test]
+    fn limit_control_keeps_the_lowest_min_freq() {
+        let fs = policy_tree();
+        let mut controller = Controller::with_fs(FreqBackend::Cpufreq, fs.clone()).unwrap();
+        controller.set_freq_control(FreqControl::Limit);
+        controller.init_game(&Extension::noop());
+
+        assert_eq!(limits(&controller, &fs, LITTLE), (300_000, 1_800_000));
+        assert_eq!(limits(&controller, &fs, BIG), (500_000, 2_800_000));
+    }
+
+    #[test]
+    fn init_default_releases_the_limits() {
+        let fs = policy_tree();
+        let mut controller = Controller::with_fs(FreqBackend::Cpufreq, fs.clone()).unwrap();
+        let extension = Extension::noop();
+        controller.init_game(&extension);
+        controller.set_policy_freq(1_000_000);
+        controller.fas_update_freq(0, Duration::from_millis(8), 0.0);
+        controller.init_default(&extension);
+
+        assert_eq!(limits(&controller, &fs, LITTLE), (300_000, 1_800_000));
+        assert_eq!(limits(&controller, &fs, BIG), (500_000, 2_800_000));
+    }
+
+    #[test]
+    fn scale_factor_is_zero_on_target() {
+        for target_fps in [60.0, 144.0, 165.0] {
+            let target = Duration::from_secs(1);
+            assert!(Controller::scale_factor(target_fps, target, target).abs() < f64::EPSILON);
+        }
+    }
+
+    #[test]
+    fn scale_factor_keeps_the_per_second_step_above_60fps() {
+        let target = Duration::from_secs(1);
+        let late = Duration::from_millis(1100);
+        let early = Duration::from_millis(900);
+
+        let per_second = |target_fps: f64, frame| {
+            Controller::scale_factor(target_fps, frame, target) * target_fps
+        };
+
+        for target_fps in [144.0, 165.0] {
+            assert!(Controller::scale_factor(target_fps, late, target) > 0.0);
+            assert!(Controller::scale_factor(target_fps, early, target) < 0.0);
+            assert!((per_second(target_fps, late) - per_second(120.0, late)).abs() < 1e-6);
+            assert!((per_second(target_fps, early) - per_second(120.0, early)).abs() < 1e-6);
+        }
+
+        let step_165 = Controller::scale_factor(165.0, late, target);
+        let step_144 = Controller::scale_factor(144.0, late, target);
+        assert!(step_165 < step_144);
+    }
 }
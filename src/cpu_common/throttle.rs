@@ -0,0 +1,70 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{path::Path, sync::Arc};
+
+use super::{file_handler::SysFs, Info};
+
+const COOLING_DEVICES: &str = "/sys/class/thermal";
+
+/// A kernel thermal limit below the frequency fas-rs asked for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Clamp {
+    pub freq: isize,
+    /// Cooling device state the limit was seen at, a lower state means the limit was relaxed
+    pub state: u64,
+}
+
+/// Give each policy the `cur_state` node of the cpufreq cooling device limiting it.
+/// `thermal-cpufreq-<n>` devices are numbered in policy order, `cpufreq-cpu<n>` name a cpu
+pub fn attach_cooling_devices(cpu_infos: &mut [Info], fs: &Arc<dyn SysFs>) {
+    let Ok(devices) = fs.read_dir(Path::new(COOLING_DEVICES)) else {
+        return;
+    };
+
+    for device in devices.into_iter().filter(|device| {
+        device
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with("cooling_device"))
+    }) {
+        let Ok(kind) = fs.read_to_string(&device.join("type")) else {
+            continue;
+        };
+        let kind = kind.trim();
+
+        let policy = if let Some(index) = kind.strip_prefix("thermal-cpufreq-") {
+            index
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| cpu_infos.get_mut(index))
+        } else if let Some(cpu) = kind.strip_prefix("cpufreq-cpu") {
+            cpu.parse::<i32>()
+                .ok()
+                .and_then(|cpu| cpu_infos.iter_mut().find(|info| info.cpus.contains(&cpu)))
+        } else {
+            None
+        };
+
+        if let Some(policy) = policy {
+            policy.cooling = Some(device.join("cur_state"));
+        }
+    }
+}
+
+pub fn cooling_state(fs: &dyn SysFs, path: Option<&Path>) -> u64 {
+    path.and_then(|path| fs.read_to_string(path).ok())
+        .and_then(|state| state.trim().parse().ok())
+        .unwrap_or(0)
+}
@@ -0,0 +1,37 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Name prefixes (lowercase) of threads on the rendering critical path,
+/// `comm` is truncated to 15 bytes so only prefixes are matched
+pub const CRITICAL_THREADS: &[&str] = &[
+    "renderthread",
+    "glthread",
+    "gl-",
+    "unitymain",
+    "unitygfx",
+    "gamethread",
+    "rhithread",
+    "renderer",
+    "thread-render",
+    "vkqueue",
+    "vulkan",
+    "mainthread-ue",
+    "ue4render",
+    "cocos",
+];
+
+pub fn is_critical(comm: &str, prefixes: &[&str]) -> bool {
+    let comm = comm.trim().to_lowercase();
+    prefixes.iter().any(|prefix| comm.starts_with(prefix))
+}
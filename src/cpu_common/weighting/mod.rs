@@ -12,39 +12,87 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod critical;
 mod task;
 mod weights;
 
 use std::{
-    collections::{hash_map, HashMap},
-    fs,
+    collections::HashMap,
     path::Path,
+    sync::Arc,
     time::{Duration, Instant},
 };
 
 use anyhow::Result;
 use cpu_cycles_reader::Cycles;
 use libc::pid_t;
-#[cfg(debug_assertions)]
 use log::debug;
 use task::TaskMeta;
 use weights::Weights;
 
+use super::file_handler::SysFs;
+
 #[derive(Debug)]
 pub struct WeightedCalculator {
     map: HashMap<i32, TaskMeta>,
     cpu_times_long: HashMap<i32, u64>,
     cpu_times_short: HashMap<i32, u64>,
     timer: Instant,
+    sample_timer: Instant,
+    interval: Duration,
+    window: Duration,
+    decay: f64,
+    clusters: Vec<Vec<i32>>,
+    num_cpus: usize,
+    critical_threads: Option<&'static [&'static str]>,
+    fs: Arc<dyn SysFs>,
 }
 
 impl WeightedCalculator {
-    pub fn new() -> Self {
+    pub fn new(fs: Arc<dyn SysFs>, clusters: Vec<Vec<i32>>) -> Self {
         Self {
             map: HashMap::new(),
             cpu_times_long: HashMap::new(),
             cpu_times_short: HashMap::new(),
             timer: Instant::now(),
+            sample_timer: Instant::now(),
+            interval: Duration::ZERO,
+            window: Duration::from_secs(3),
+            decay: 0.0,
+            num_cpus: num_cpus_in(&clusters),
+            clusters,
+            critical_threads: None,
+            fs,
+        }
+    }
+
+    /// `interval` limits how often thread runtimes are sampled, `window` is how long
+    /// the selected threads are kept before being re-picked, and `decay` is how much of
+    /// the previous weight each new sample keeps (`0.0` uses the latest sample only)
+    pub const fn set_params(&mut self, interval: Duration, window: Duration, decay: f64) {
+        self.interval = interval;
+        self.window = window;
+        self.decay = decay.clamp(0.0, 0.99);
+    }
+
+    pub fn set_clusters(&mut self, clusters: Vec<Vec<i32>>) {
+        self.num_cpus = num_cpus_in(&clusters);
+        self.clusters = clusters;
+        for meta in self.map.values_mut() {
+            meta.cluster = None;
+        }
+    }
+
+    /// Thread name prefixes of the game's render path, `None` for the generic ones
+    pub const fn set_critical_threads(&mut self, threads: Option<&'static [&'static str]>) {
+        self.critical_threads = threads;
+    }
+
+    fn blend(&self, last: f64, sample: f64) -> f64 {
+        if sample.is_nan() {
+            last
+        } else {
+            self.decay.mul_add(last, (1.0 - self.decay) * sample)
         }
     }
 
@@ -53,6 +101,7 @@ impl WeightedCalculator {
         self.cpu_times_short.clear();
         self.cpu_times_long.clear();
         self.timer = Instant::now();
+        self.sample_timer = Instant::now();
     }
 
     pub fn update(&mut self, process: pid_t) -> Result<Weights> {
@@ -62,12 +111,14 @@ impl WeightedCalculator {
     }
 
     fn calculate_weights(&self) -> Result<Weights> {
-        let mut weights = Weights {
-            map: HashMap::new(),
-        };
+        if self.map.is_empty() {
+            return Ok(Weights::default());
+        }
+
+        let num_cpus = self.num_cpus;
+        let mut cpu_weights = vec![0.0; num_cpus];
 
         for meta in self.map.values() {
-            let num_cpus = num_cpus::get();
             let mut cycles_instants = Vec::new();
 
             for cpu in 0..num_cpus {
@@ -81,35 +132,34 @@ impl WeightedCalculator {
                 .collect();
             let cycles_sum: Cycles = cycles.iter().copied().sum();
 
-            for (cpu, cycles) in cycles.iter().enumerate() {
-                let cpu_weight = cycles.as_hz() as f64 / cycles_sum.as_hz() as f64;
-                let final_weight = cpu_weight * meta.weight;
-                match weights.map.entry(cpu as i32) {
-                    hash_map::Entry::Occupied(mut o) => {
-                        *o.get_mut() += final_weight;
-                    }
-                    hash_map::Entry::Vacant(v) => {
-                        v.insert(final_weight);
-                    }
-                }
+            for (cpu_weight, cycles) in cpu_weights.iter_mut().zip(&cycles) {
+                *cpu_weight += cycles.as_hz() as f64 / cycles_sum.as_hz() as f64 * meta.weight;
             }
         }
 
-        Ok(weights)
+        Ok(Weights::new(&cpu_weights, &self.clusters))
     }
 
     fn update_cpu_times(&mut self, process: pid_t) {
+        let fs = &self.fs;
         self.map.retain(|task, _| {
-            Path::new(&format!("/proc/{process}/task/{task}/schedstat")).exists()
+            fs.read_to_string(Path::new(&format!("/proc/{process}/task/{task}/schedstat")))
+                .is_ok()
         });
 
+        self.track_migrations(process);
+
         let new_cpu_times: HashMap<_, _> = self
             .map
             .keys()
             .filter_map(|task| {
                 Some((
                     task,
-                    fs::read_to_string(format!("/proc/{process}/task/{task}/schedstat")).ok()?,
+                    self.fs
+                        .read_to_string(Path::new(&format!(
+                            "/proc/{process}/task/{task}/schedstat"
+                        )))
+                        .ok()?,
                 ))
             })
             .map(|(task, stat)| {
@@ -135,28 +185,34 @@ impl WeightedCalculator {
             })
             .collect();
 
-        #[cfg(debug_assertions)]
         debug!("cpu_slices: {cpu_slices:?}");
 
         self.cpu_times_short = new_cpu_times;
 
         let total_time: u64 = cpu_slices.values().sum();
         for (task, time) in cpu_slices {
-            let weight = time as f64 / total_time as f64;
+            let sample = time as f64 / total_time as f64;
+            let weight = self.blend(self.map[&task].weight, sample);
             self.map.get_mut(&task).unwrap().weight = weight;
         }
     }
 
     fn update_top_tasks(&mut self, process: pid_t) -> Result<()> {
-        if self.timer.elapsed() <= Duration::from_secs(3) {
-            self.update_cpu_times(process);
+        if self.timer.elapsed() <= self.window {
+            if self.sample_timer.elapsed() >= self.interval {
+                self.sample_timer = Instant::now();
+                self.update_cpu_times(process);
+            }
+
             return Ok(());
         }
 
         self.timer = Instant::now();
 
-        let cpu_times: HashMap<_, _> = fs::read_dir(format!("/proc/{process}/task"))?
-            .map(|e| e.unwrap().path())
+        let cpu_times: HashMap<_, _> = self
+            .fs
+            .read_dir(Path::new(&format!("/proc/{process}/task")))?
+            .into_iter()
             .filter_map(|p| {
                 Some((
                     p.file_name()
@@ -165,7 +221,7 @@ impl WeightedCalculator {
                         .unwrap()
                         .parse::<pid_t>()
                         .ok()?,
-                    fs::read_to_string(p.join("schedstat")).ok()?,
+                    self.fs.read_to_string(&p.join("schedstat")).ok()?,
                 ))
             })
             .map(|(tid, stat)| {
@@ -179,7 +235,7 @@ impl WeightedCalculator {
             })
             .collect();
 
-        let mut cpu_slices: Vec<_> = cpu_times
+        let cpu_slices: Vec<_> = cpu_times
             .iter()
             .map(|(tid, cputime)| {
                 (
@@ -188,6 +244,19 @@ impl WeightedCalculator {
                 )
             })
             .collect();
+
+        let critical: Vec<_> = cpu_slices
+            .iter()
+            .filter(|(tid, slice)| {
+                *slice > 0 && (*tid == process || self.is_critical(process, *tid))
+            })
+            .copied()
+            .collect();
+        let mut cpu_slices = if critical.is_empty() {
+            cpu_slices
+        } else {
+            critical
+        };
         cpu_slices.sort_by_key(|(_, slice)| *slice);
         cpu_slices.reverse();
         cpu_slices.truncate(5);
@@ -196,15 +265,18 @@ impl WeightedCalculator {
 
         let cpu_slices: HashMap<_, _> = cpu_slices.into_iter().collect();
 
-        #[cfg(debug_assertions)]
         debug!("cpu_slices: {cpu_slices:?}");
 
         self.map.retain(|t, _| cpu_slices.contains_key(t));
 
-        let num_cpus = num_cpus::get();
+        let num_cpus = self.num_cpus;
         let total_time: u64 = cpu_slices.values().sum();
         for (task, time) in cpu_slices {
-            let weight = time as f64 / total_time as f64;
+            let sample = time as f64 / total_time as f64;
+            let weight = self
+                .map
+                .get(&task)
+                .map_or(sample, |meta| self.blend(meta.weight, sample));
             self.map
                 .entry(task)
                 .or_insert(TaskMeta::new(task, num_cpus)?)
@@ -213,4 +285,57 @@ impl WeightedCalculator {
 
         Ok(())
     }
+
+    fn is_critical(&self, process: pid_t, tid: pid_t) -> bool {
+        self.fs
+            .read_to_string(Path::new(&format!("/proc/{process}/task/{tid}/comm")))
+            .is_ok_and(|comm| {
+                critical::is_critical(
+                    &comm,
+                    self.critical_threads.unwrap_or(critical::CRITICAL_THREADS),
+                )
+            })
+    }
+    /// Reset the cycle trace of tasks that moved to another cluster since the last sample,
+    /// so weights follow them right away instead of after the next window
+    fn track_migrations(&mut self, process: pid_t) {
+        let num_cpus = self.num_cpus;
+
+        for (task, meta) in &mut self.map {
+            let Some(cpu) = self
+                .fs
+                .read_to_string(Path::new(&format!("/proc/{process}/task/{task}/stat")))
+                .ok()
+                .and_then(|stat| last_cpu(&stat))
+            else {
+                continue;
+            };
+
+            let cluster = self.clusters.iter().position(|cpus| cpus.contains(&cpu));
+            if meta.cluster.is_some() && cluster.is_some() && meta.cluster != cluster {
+                debug!("task {task} migrated to cpu{cpu}");
+                meta.reset_trace(num_cpus)
+                    .unwrap_or_else(|e| debug!("{e:?}"));
+            }
+
+            meta.cluster = cluster;
+        }
+    }
+}
+
+/// Cpus covered by the policies, `num_cpus::get` follows the affinity of fas-rs which is
+/// pinned to the little cluster
+fn num_cpus_in(clusters: &[Vec<i32>]) -> usize {
+    clusters
+        .iter()
+        .flatten()
+        .max()
+        .map_or_else(num_cpus::get, |cpu| *cpu as usize + 1)
+}
+
+/// The `processor` field (39th) of `/proc/<pid>/task/<tid>/stat`, `comm` may contain spaces
+/// so counting starts after its closing parenthesis
+fn last_cpu(stat: &str) -> Option<i32> {
+    let (_, rest) = stat.rsplit_once(')')?;
+    rest.split_whitespace().nth(36)?.parse().ok()
 }
@@ -21,6 +21,7 @@ pub struct TaskMeta {
     pub weight: f64,
     pub cycles_trace: Vec<CyclesInstant>,
     pub cycles_reader: CyclesReader,
+    pub cluster: Option<usize>,
 }
 
 impl TaskMeta {
@@ -36,6 +37,19 @@ impl TaskMeta {
             weight: 0.0,
             cycles_reader,
             cycles_trace,
+            cluster: None,
         })
     }
+
+    /// Restart cycle counting from now, so the per-cpu weights only reflect where the task runs after a migration
+    pub fn reset_trace(&mut self, num_cpus: usize) -> Result<()> {
+        self.cycles_trace.clear();
+
+        for cpu in 0..num_cpus {
+            self.cycles_trace
+                .push(self.cycles_reader.instant(cpu as i32)?);
+        }
+
+        Ok(())
+    }
 }
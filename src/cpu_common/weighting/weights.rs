@@ -12,28 +12,33 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::collections::HashMap;
-
+/// Weight of each policy, indexed by its position in the policy list
+#[derive(Default)]
 pub struct Weights {
-    pub map: HashMap<i32, f64>,
+    policies: Vec<Option<f64>>,
 }
 
 impl Weights {
-    pub fn weight(&self, cpus: &Vec<i32>) -> Option<f64> {
-        if self.map.is_empty() {
-            return None;
-        }
+    pub fn new(cpu_weights: &[f64], clusters: &[Vec<i32>]) -> Self {
+        let policies = clusters
+            .iter()
+            .map(|cpus| {
+                let mut weight = 1.0;
+                for cpu in cpus {
+                    let partial_weight = *cpu_weights.get(*cpu as usize)?;
+                    if partial_weight.is_normal() {
+                        weight += partial_weight;
+                    }
+                }
 
-        let mut weight = 1.0;
-        for cpu in cpus {
-            let partial_weight = *self.map.get(cpu)?;
-            if partial_weight.is_normal() {
-                weight += partial_weight;
-            }
-        }
+                Some(f64::min(weight, 1.5))
+            })
+            .collect();
 
-        let weight = weight.min(1.5);
+        Self { policies }
+    }
 
-        Some(weight)
+    pub fn weight(&self, policy: usize) -> Option<f64> {
+        self.policies.get(policy).copied().flatten()
     }
 }
@@ -0,0 +1,209 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    iter, mem,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, Receiver, SyncSender, TrySendError},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+use log::{debug, error};
+
+use super::file_handler::FileHandler;
+use crate::framework::{Error, Result};
+
+/// Jobs the frame path can queue before it starts dropping them
+const QUEUE_SIZE: usize = 64;
+
+/// The node writes of one policy, a newer job of the same policy replaces a queued one
+#[derive(Debug)]
+pub struct Job {
+    policy: i32,
+    writes: Vec<(PathBuf, String)>,
+}
+
+impl Job {
+    pub const fn new(policy: i32, writes: Vec<(PathBuf, String)>) -> Self {
+        Self { policy, writes }
+    }
+}
+
+#[derive(Debug)]
+enum Message {
+    Write(Job),
+    Close(Vec<PathBuf>),
+    DryRun(bool),
+    Flush(SyncSender<()>),
+}
+
+/// Owns the file handler on its own thread, so a slow sysfs node can't hold up frame processing
+#[derive(Debug)]
+pub struct Writer {
+    sx: SyncSender<Message>,
+    errors: Arc<Mutex<Vec<Error>>>,
+    pending: Arc<AtomicUsize>,
+    dry_run: bool,
+}
+
+impl Writer {
+    pub fn new(file_handler: FileHandler) -> Result<Self> {
+        let (sx, rx) = mpsc::sync_channel(QUEUE_SIZE);
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let pending = Arc::new(AtomicUsize::new(0));
+        let dry_run = file_handler.dry_run();
+
+        {
+            let errors = errors.clone();
+            let pending = pending.clone();
+            thread::Builder::new()
+                .name("FreqWriter".into())
+                .spawn(move || writer_thread(file_handler, &rx, &errors, &pending))?;
+        }
+
+        Ok(Self {
+            sx,
+            errors,
+            pending,
+            dry_run,
+        })
+    }
+
+    /// Queue a job, a blocking one waits for room instead of being dropped on a full queue
+    pub fn write(&self, job: Job, blocking: bool) {
+        let policy = job.policy;
+        self.pending.fetch_add(1, Ordering::AcqRel);
+
+        let result = if blocking {
+            self.sx
+                .send(Message::Write(job))
+                .map_err(|e| TrySendError::Disconnected(e.0))
+        } else {
+            self.sx.try_send(Message::Write(job))
+        };
+
+        if let Err(e) = result {
+            self.pending.fetch_sub(1, Ordering::AcqRel);
+            match e {
+                TrySendError::Full(_) => {
+                    debug!("Frequency writer is behind, dropped a write of policy{policy}");
+                }
+                TrySendError::Disconnected(_) => error!("Frequency writer thread exited"),
+            }
+        }
+    }
+
+    pub fn close(&self, paths: Vec<PathBuf>) {
+        let _ = self.sx.send(Message::Close(paths));
+    }
+
+    pub const fn dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    pub fn set_dry_run(&mut self, dry_run: bool) {
+        if self.dry_run != dry_run {
+            self.dry_run = dry_run;
+            let _ = self.sx.send(Message::DryRun(dry_run));
+        }
+    }
+
+    /// Wait until everything queued so far has been written
+    pub fn flush(&self) {
+        let (ack, done) = mpsc::sync_channel(1);
+        if self.sx.send(Message::Flush(ack)).is_ok() {
+            let _ = done.recv();
+        }
+    }
+
+    /// Write errors since the last call
+    pub fn take_errors(&self) -> Vec<Error> {
+        mem::take(&mut *self.errors.lock().unwrap())
+    }
+
+    /// No write is queued or in flight, so reading the nodes back shows what fas-rs wrote
+    pub fn is_idle(&self) -> bool {
+        self.pending.load(Ordering::Acquire) == 0
+    }
+}
+
+fn writer_thread(
+    mut file_handler: FileHandler,
+    rx: &Receiver<Message>,
+    errors: &Mutex<Vec<Error>>,
+    pending: &AtomicUsize,
+) {
+    let mut jobs: Vec<Job> = Vec::new();
+
+    while let Ok(message) = rx.recv() {
+        for message in iter::once(message).chain(rx.try_iter()) {
+            let message = match message {
+                Message::Write(job) => {
+                    if let Some(queued) = jobs.iter_mut().find(|queued| queued.policy == job.policy)
+                    {
+                        *queued = job;
+                        pending.fetch_sub(1, Ordering::AcqRel);
+                    } else {
+                        jobs.push(job);
+                    }
+                    continue;
+                }
+                message => message,
+            };
+
+            // Anything else is ordered against the writes queued before it
+            run_jobs(&mut file_handler, &mut jobs, errors, pending);
+
+            match message {
+                Message::Close(paths) => {
+                    for path in paths {
+                        file_handler.close(path);
+                    }
+                }
+                Message::DryRun(dry_run) => file_handler.set_dry_run(dry_run),
+                Message::Flush(ack) => {
+                    let _ = ack.send(());
+                }
+                Message::Write(_) => (),
+            }
+        }
+
+        run_jobs(&mut file_handler, &mut jobs, errors, pending);
+    }
+}
+
+fn run_jobs(
+    file_handler: &mut FileHandler,
+    jobs: &mut Vec<Job>,
+    errors: &Mutex<Vec<Error>>,
+    pending: &AtomicUsize,
+) {
+    for job in jobs.drain(..) {
+        for (path, value) in &job.writes {
+            if let Err(source) = file_handler.write_with_workround(path, value) {
+                errors.lock().unwrap().push(Error::Cpufreq {
+                    policy: job.policy,
+                    source,
+                });
+                break;
+            }
+        }
+
+        pending.fetch_sub(1, Ordering::AcqRel);
+    }
+}
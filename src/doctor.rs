@@ -0,0 +1,304 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    env,
+    fmt::Display,
+    fs::{self, OpenOptions},
+    path::Path,
+    process,
+};
+
+use anyhow::{bail, Result};
+
+use crate::{
+    framework::{detected_daemons, Config, Format, Severity},
+    misc,
+};
+
+const HELP: &str = "\
+fas-rs doctor:
+    check the environment fas-rs needs and print what to fix\
+";
+
+const CPUFREQ: &str = "/sys/devices/system/cpu/cpufreq";
+const SELINUX_ENFORCE: &str = "/sys/fs/selinux/enforce";
+/// Other performance tuners that fight fas-rs over cpufreq, by process name
+const TUNERS: &[&str] = &["uperf", "sfanalysis", "AsoulOpt"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Ok,
+    Warn,
+    Fail,
+}
+
+struct Report {
+    failures: usize,
+}
+
+impl Report {
+    fn item(&mut self, status: Status, name: &str, message: impl Display, hint: Option<&str>) {
+        let tag = match status {
+            Status::Ok => " OK ",
+            Status::Warn => "WARN",
+            Status::Fail => {
+                self.failures += 1;
+                "FAIL"
+            }
+        };
+
+        println!("[{tag}] {name}: {message}");
+        if let Some(hint) = hint.filter(|_| status != Status::Ok) {
+            println!("       -> {hint}");
+        }
+    }
+}
+
+pub fn doctor(args: &[String], user_config: &Path) -> Result<()> {
+    if let Some(arg) = args.first() {
+        bail!("Unknown argument {arg}\n{HELP}");
+    }
+
+    let mut report = Report { failures: 0 };
+    check_root(&mut report);
+    check_selinux(&mut report);
+    check_daemon(&mut report);
+    check_bpf(&mut report);
+    check_cpufreq(&mut report);
+    check_interference(&mut report);
+    check_config(&mut report, user_config);
+
+    if report.failures > 0 {
+        println!("{} problem(s) found", report.failures);
+        process::exit(1);
+    }
+
+    Ok(())
+}
+
+fn check_root(report: &mut Report) {
+    let uid = unsafe { libc::geteuid() };
+    if uid == 0 {
+        report.item(Status::Ok, "root", "running as uid 0", None);
+    } else {
+        report.item(
+            Status::Fail,
+            "root",
+            format_args!("running as uid {uid}"),
+            Some("run through su, fas-rs needs root to write cpufreq nodes"),
+        );
+    }
+}
+
+fn check_selinux(report: &mut Report) {
+    match fs::read_to_string(SELINUX_ENFORCE).map(|mode| mode.trim().to_string()) {
+        Ok(mode) => {
+            let mode = if mode == "1" {
+                "enforcing, denied nodes are patched into the policy at startup"
+            } else {
+                "permissive"
+            };
+            report.item(Status::Ok, "selinux", mode, None);
+        }
+        Err(e) => report.item(
+            Status::Warn,
+            "selinux",
+            format_args!("unknown, {e}"),
+            Some("check that selinuxfs is mounted at /sys/fs/selinux"),
+        ),
+    }
+}
+
+fn check_daemon(report: &mut Report) {
+    match misc::running_instance() {
+        Some(pid) => report.item(
+            Status::Ok,
+            "daemon",
+            format_args!("running, pid {pid}"),
+            None,
+        ),
+        None => report.item(
+            Status::Warn,
+            "daemon",
+            "not running",
+            Some("check /sdcard/Android/fas-rs/fas_log.txt for why it exited"),
+        ),
+    }
+}
+
+#[cfg(feature = "use_ebpf")]
+fn check_bpf(report: &mut Report) {
+    if misc::running_instance().is_some() {
+        report.item(Status::Ok, "bpf", "in use by the running daemon", None);
+        return;
+    }
+
+    match frame_analyzer::Analyzer::new() {
+        Ok(_) => report.item(Status::Ok, "bpf", "frame analyzer loaded", None),
+        Err(e) => report.item(
+            Status::Fail,
+            "bpf",
+            format_args!("failed to load the frame analyzer, {e}"),
+            Some("the kernel needs eBPF with uprobe support, or build fas-rs with the use_binder backend"),
+        ),
+    }
+}
+
+#[cfg(feature = "use_binder")]
+fn check_bpf(report: &mut Report) {
+    report.item(Status::Ok, "bpf", "not needed by the binder backend", None);
+}
+
+fn check_cpufreq(report: &mut Report) {
+    let policies = match fs::read_dir(CPUFREQ) {
+        Ok(entries) => {
+            let mut policies: Vec<_> = entries
+                .filter_map(std::result::Result::ok)
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    path.file_name()
+                        .and_then(|name| name.to_str())
+                        .is_some_and(|name| name.starts_with("policy"))
+                })
+                .collect();
+            policies.sort();
+            policies
+        }
+        Err(e) => {
+            report.item(
+                Status::Fail,
+                "cpufreq",
+                format_args!("can't read {CPUFREQ}, {e}"),
+                Some("this kernel does not expose cpufreq, fas-rs can't control it"),
+            );
+            return;
+        }
+    };
+
+    let mountinfo = fs::read_to_string("/proc/self/mountinfo").unwrap_or_default();
+
+    for policy in policies {
+        let name = policy.file_name().unwrap().to_string_lossy().to_string();
+
+        for node in ["scaling_max_freq", "scaling_min_freq"] {
+            let path = policy.join(node);
+            let shown = path.display();
+
+            if mountinfo
+                .lines()
+                .filter_map(|line| line.split_whitespace().nth(4))
+                .any(|mount_point| Path::new(mount_point) == path)
+            {
+                report.item(
+                    Status::Fail,
+                    "cpufreq",
+                    format_args!("{shown} is locked by a bind mount"),
+                    Some("another module pins this node, disable it or its frequency lock"),
+                );
+            } else if let Err(e) = OpenOptions::new().write(true).open(&path) {
+                report.item(
+                    Status::Fail,
+                    "cpufreq",
+                    format_args!("{shown} is not writable, {e}"),
+                    Some("check permissions, and selinux denials with `dmesg | grep avc`"),
+                );
+            } else {
+                report.item(
+                    Status::Ok,
+                    "cpufreq",
+                    format_args!("{name}/{node} writable"),
+                    None,
+                );
+            }
+        }
+    }
+}
+
+fn check_interference(report: &mut Report) {
+    for daemon in detected_daemons() {
+        report.item(
+            Status::Ok,
+            "vendor",
+            format_args!("{daemon} present, fas-rs neutralizes it while working"),
+            None,
+        );
+    }
+
+    let Ok(entries) = fs::read_dir("/proc") else {
+        return;
+    };
+
+    for entry in entries.filter_map(std::result::Result::ok) {
+        let Ok(comm) = fs::read_to_string(entry.path().join("comm")) else {
+            continue;
+        };
+        let comm = comm.trim();
+
+        if TUNERS.contains(&comm) {
+            report.item(
+                Status::Fail,
+                "interference",
+                format_args!("{comm} is running (pid {})", entry.file_name().to_string_lossy()),
+                Some("uninstall or disable other performance tuners, they overwrite fas-rs's frequencies"),
+            );
+        }
+    }
+}
+
+fn check_config(report: &mut Report, user_config: &Path) {
+    let shown = user_config.display();
+    let local = match fs::read_to_string(user_config) {
+        Ok(local) => local,
+        Err(e) => {
+            report.item(
+                Status::Fail,
+                "config",
+                format_args!("can't read {shown}, {e}"),
+                Some("reinstall the module to recreate the config, or run `fas-rs init`"),
+            );
+            return;
+        }
+    };
+
+    let std = env::current_exe()
+        .ok()
+        .and_then(|exe| Some(exe.parent()?.join("games.toml")))
+        .and_then(|std| fs::read_to_string(std).ok());
+    let diagnostics = Config::check(
+        local.as_str(),
+        std.as_deref(),
+        Format::from_path(user_config),
+    );
+    let errors = diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.severity == Severity::Error)
+        .count();
+
+    if errors == 0 {
+        report.item(
+            Status::Ok,
+            "config",
+            format_args!("{shown}, {} warning(s)", diagnostics.len()),
+            None,
+        );
+    } else {
+        report.item(
+            Status::Fail,
+            "config",
+            format_args!("{shown}, {errors} error(s)"),
+            Some("run `fas-rs check` for details"),
+        );
+    }
+}
@@ -0,0 +1,70 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::{
+    data::{AppType, ConfigData},
+    pattern::PackagePattern,
+};
+
+const VIDEO_PLAYERS: &[&str] = &[
+    "com.google.android.youtube",
+    "com.netflix.mediaclient",
+    "tv.danmaku.bili",
+    "com.bilibili.app.in",
+    "com.ss.android.ugc.aweme",
+    "com.zhiliaoapp.musically",
+    "com.ss.android.ugc.trill",
+    "com.smile.gifmaker",
+    "com.kuaishou.nebula",
+    "com.tencent.qqlive",
+    "com.qiyi.video",
+    "com.youku.phone",
+    "com.mxtech.videoplayer.ad",
+    "com.mxtech.videoplayer.pro",
+    "org.videolan.vlc",
+    "is.xyz.mpv",
+    "com.amazon.avod.thirdpartyclient",
+    "com.disney.disneyplus",
+];
+
+impl ConfigData {
+    pub fn load_blacklist(&mut self) {
+        self.blacklist.patterns = self
+            .blacklist
+            .packages
+            .iter()
+            .filter_map(|key| PackagePattern::parse(key))
+            .collect();
+    }
+
+    pub fn blacklisted(&self, pkg: &str) -> bool {
+        let blacklist = &self.blacklist;
+        let pkg = pkg.split(':').next().unwrap_or(pkg);
+
+        blacklist.packages.iter().any(|key| key == pkg)
+            || blacklist
+                .patterns
+                .iter()
+                .any(|pattern| pattern.is_match(pkg))
+            || (blacklist.video_players && VIDEO_PLAYERS.contains(&pkg))
+    }
+
+    /// Streaming apps are video as far as frames go, like the video players above, but can
+    /// still be held at a static clamp through `[fixed_freq]`
+    pub fn skips_fas(&self, pkg: &str) -> bool {
+        let pkg = pkg.split(':').next().unwrap_or(pkg);
+
+        self.app_type.get(pkg) == Some(&AppType::Streaming) && !self.fixed_freq.contains_key(pkg)
+    }
+}
@@ -0,0 +1,345 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    fmt::{self, Display, Formatter},
+    iter,
+    path::Path,
+};
+
+use toml::{Table, Value};
+
+use super::{
+    data::{Config as ConfigConfig, ConfigData},
+    format::Format,
+    migrate,
+    pattern::PackagePattern,
+    Config,
+};
+
+const SECTIONS: &[&str] = &[
+    "version",
+    "include",
+    "config",
+    "game_list",
+    "powersave",
+    "balance",
+    "performance",
+    "fast",
+    "sensor",
+    "battery",
+    "power_budget",
+    "refresh_rate",
+    "fixed_freq",
+    "freq_control",
+    "app_type",
+    "isolate_core",
+    "extensions",
+    "thermal",
+    "irq",
+    "session",
+    "blacklist",
+    "control",
+];
+const MODES: &[&str] = &["powersave", "balance", "performance", "fast"];
+const SECTION_KEYS: &[(&str, &[&str])] = &[
+    ("powersave", &["margin"]),
+    ("balance", &["margin"]),
+    ("performance", &["margin"]),
+    ("fast", &["margin"]),
+    ("sensor", &["priority", "game"]),
+    ("battery", &["rules", "charging_mode", "charging_max_temp"]),
+    (
+        "thermal",
+        &["zone", "hysteresis", "rules", "policies", "policy_rules"],
+    ),
+    ("irq", &["policy", "keywords"]),
+    ("session", &["milestones"]),
+    ("blacklist", &["packages", "video_players"]),
+    ("control", &["uids"]),
+];
+const MAX_TARGET_FPS: f64 = 240.0;
+const PACKAGES_DIR: &str = "/data/data";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+
+        write!(f, "{severity}: {}", self.message)
+    }
+}
+
+#[derive(Default)]
+struct Diagnostics(Vec<Diagnostic>);
+
+impl Diagnostics {
+    fn warning(&mut self, message: String) {
+        self.0.push(Diagnostic {
+            severity: Severity::Warning,
+            message,
+        });
+    }
+
+    fn error(&mut self, message: String) {
+        self.0.push(Diagnostic {
+            severity: Severity::Error,
+            message,
+        });
+    }
+}
+
+impl Config {
+    /// The local config is in `format`, the standard one is always toml
+    pub fn check<S: AsRef<str>>(l: S, s: Option<S>, format: Format) -> Vec<Diagnostic> {
+        let mut diagnostics = Diagnostics::default();
+
+        let local: Table = match format.parse(l.as_ref()) {
+            Ok(local) => local,
+            Err(e) => {
+                diagnostics.error(format!("Config is not valid {} at {e}", format.extension()));
+                return diagnostics.0;
+            }
+        };
+
+        if let Err(e) = format.parse::<ConfigData>(l.as_ref()) {
+            diagnostics.error(format!(
+                "Config does not match the expected format at {e}, fas-rs will fall back to the standard config"
+            ));
+        }
+
+        let version = migrate::version(&local);
+        if version < migrate::VERSION {
+            diagnostics.warning(format!(
+                "Config version {version} is older than {}, it is migrated when fas-rs starts",
+                migrate::VERSION
+            ));
+        }
+
+        check_keys(&local, &mut diagnostics);
+        check_game_list(&local, &mut diagnostics);
+        check_rules(&local, &mut diagnostics);
+        check_thermal_policies(&local, &mut diagnostics);
+
+        if let Some(std) = s {
+            match toml::from_str::<Table>(std.as_ref()) {
+                Ok(std) => check_std(&local, &std, &mut diagnostics),
+                Err(e) => diagnostics.error(format!("Standard config is not valid toml: {e}")),
+            }
+        }
+
+        diagnostics.0
+    }
+}
+
+fn check_keys(local: &Table, diagnostics: &mut Diagnostics) {
+    for key in local.keys() {
+        if !SECTIONS.contains(&key.as_str()) {
+            diagnostics.error(format!("Unknown section [{key}]"));
+        }
+    }
+
+    if let Some(config) = local.get("config").and_then(Value::as_table) {
+        let known = toml::from_str::<ConfigConfig>("")
+            .ok()
+            .and_then(|config| Value::try_from(config).ok())
+            .and_then(|config| config.as_table().cloned())
+            .unwrap_or_default();
+
+        for key in config.keys().filter(|key| !known.contains_key(*key)) {
+            diagnostics.error(format!("Unknown key '{key}' in [config]"));
+        }
+    }
+
+    for (section, keys) in SECTION_KEYS {
+        let Some(table) = local.get(*section).and_then(Value::as_table) else {
+            continue;
+        };
+
+        for key in table.keys().filter(|key| !keys.contains(&key.as_str())) {
+            diagnostics.error(format!("Unknown key '{key}' in [{section}]"));
+        }
+    }
+
+    if let Some(extensions) = local.get("extensions").and_then(Value::as_table) {
+        for (name, _) in extensions.iter().filter(|(_, value)| !value.is_table()) {
+            diagnostics.error(format!("[extensions] '{name}' should be a table"));
+        }
+    }
+}
+
+fn check_game_list(local: &Table, diagnostics: &mut Diagnostics) {
+    let Some(game_list) = local.get("game_list").and_then(Value::as_table) else {
+        diagnostics.error("Missing section [game_list]".into());
+        return;
+    };
+
+    let installed = Path::new(PACKAGES_DIR).read_dir().is_ok();
+
+    for (key, value) in game_list {
+        check_target_fps(key, value, diagnostics);
+
+        let is_pattern = (key.starts_with('/') && key.ends_with('/') && key.len() > 1)
+            || key.contains(['*', '?']);
+        if is_pattern {
+            if PackagePattern::parse(key).is_none() {
+                diagnostics.error(format!("Illegal package pattern '{key}' never matches"));
+            }
+            continue;
+        }
+
+        let pkg = key.split_once('/').map_or(key.as_str(), |(pkg, _)| pkg);
+        if !valid_package(pkg) {
+            diagnostics.error(format!(
+                "'{key}' is not a valid package name and never matches"
+            ));
+        } else if installed && !Path::new(PACKAGES_DIR).join(pkg).exists() {
+            diagnostics.warning(format!("Package '{pkg}' is not installed"));
+        }
+    }
+}
+
+fn check_target_fps(key: &str, value: &Value, diagnostics: &mut Diagnostics) {
+    let valid = |fps: &Value| {
+        Config::fps_value(fps).is_some_and(|fps| (1.0..=MAX_TARGET_FPS).contains(&fps))
+    };
+
+    let ok = match value {
+        Value::Integer(_) | Value::Float(_) => valid(value),
+        Value::Array(arr) => !arr.is_empty() && arr.iter().all(valid),
+        Value::String(s) => s == "auto",
+        _ => false,
+    };
+
+    if !ok {
+        diagnostics.error(format!(
+            "Invalid target fps of '{key}': {value}, expected \"auto\", a number in 1..={MAX_TARGET_FPS} or a non-empty array of them"
+        ));
+    }
+}
+
+fn check_rules(local: &Table, diagnostics: &mut Diagnostics) {
+    for section in ["battery", "thermal"] {
+        let rules = local
+            .get(section)
+            .and_then(|table| table.get("rules"))
+            .and_then(Value::as_array);
+
+        for rule in rules.into_iter().flatten() {
+            if let Some(fps) = rule.get("target_fps") {
+                check_target_fps(&format!("[{section}] rule"), fps, diagnostics);
+            }
+        }
+    }
+}
+
+fn check_thermal_policies(local: &Table, diagnostics: &mut Diagnostics) {
+    let Some(thermal) = local.get("thermal") else {
+        return;
+    };
+
+    let policies = thermal.get("policies").and_then(Value::as_table);
+    for key in policies.into_iter().flat_map(Table::keys) {
+        if key
+            .strip_prefix("policy")
+            .and_then(|n| n.parse::<i32>().ok())
+            .is_none()
+        {
+            diagnostics.error(format!(
+                "Invalid key '{key}' in [thermal.policies], expected 'policy<n>'"
+            ));
+        }
+    }
+
+    let rules = thermal.get("policy_rules").and_then(Value::as_array);
+    for rule in rules.into_iter().flatten() {
+        let max_freq = rule.get("max_freq").and_then(Value::as_float);
+        if !max_freq.is_some_and(|f| f > 0.0 && f <= 1.0) {
+            diagnostics.error(format!(
+                "Invalid max_freq of [thermal] policy rule: {:?}, expected a float in (0, 1]",
+                rule.get("max_freq")
+            ));
+        }
+    }
+}
+
+fn check_std(local: &Table, std: &Table, diagnostics: &mut Diagnostics) {
+    let keep_std = local
+        .get("config")
+        .and_then(|config| config.get("keep_std"))
+        .and_then(Value::as_bool);
+
+    let Some(keep_std) = keep_std else {
+        diagnostics
+            .error("Missing or invalid 'keep_std' in [config], config merge will fail".into());
+        return;
+    };
+
+    for section in iter::once("config").chain(MODES.iter().copied()) {
+        let (Some(local), Some(std)) = (
+            local.get(section).and_then(Value::as_table),
+            std.get(section).and_then(Value::as_table),
+        ) else {
+            continue;
+        };
+
+        for (key, value) in local {
+            if key == "keep_std" {
+                continue;
+            }
+
+            match std.get(key) {
+                None => diagnostics.warning(format!(
+                    "'{key}' in [{section}] is not in the standard config and will be dropped on merge"
+                )),
+                Some(std_value) if keep_std && std_value != value => {
+                    diagnostics.warning(format!(
+                        "'{key}' in [{section}] is overridden by the standard value {std_value} because keep_std = true"
+                    ));
+                }
+                Some(_) => (),
+            }
+        }
+    }
+}
+
+fn valid_package(pkg: &str) -> bool {
+    let mut segments = 0;
+
+    for segment in pkg.split('.') {
+        let mut chars = segment.chars();
+        if !chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+            || !chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            return false;
+        }
+        segments += 1;
+    }
+
+    segments >= 2
+}
@@ -12,7 +12,12 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use super::Config;
+use std::collections::HashMap;
+
+use super::{
+    Config, FreqBackend, FreqControl, IrqConfig, LogLevel, SensorConfig, SensorKind, SessionConfig,
+    Smoothing, ThermalConfig,
+};
 
 impl Config {
     pub const fn default_value_keep_std() -> bool {
@@ -26,4 +31,244 @@ impl Config {
     pub const fn default_value_scene_game_list() -> bool {
         true
     }
+
+    pub const fn default_value_file_game_list() -> bool {
+        false
+    }
+
+    pub const fn default_value_game_turbo_list() -> bool {
+        false
+    }
+
+    pub const fn default_value_auto_detect() -> bool {
+        false
+    }
+
+    pub const fn default_value_mode_sync() -> bool {
+        false
+    }
+
+    pub const fn default_value_refresh_rate_cap() -> bool {
+        true
+    }
+
+    pub const fn default_value_outlier_ratio() -> f64 {
+        0.0
+    }
+
+    pub const fn default_value_smoothing() -> Smoothing {
+        Smoothing::Raw
+    }
+
+    pub const fn default_value_smoothing_alpha() -> f64 {
+        0.3
+    }
+
+    pub const fn default_value_smoothing_window() -> usize {
+        5
+    }
+
+    pub const fn default_value_jitter_threshold() -> f64 {
+        0.0
+    }
+
+    pub const fn default_value_vsync_align() -> bool {
+        false
+    }
+
+    pub const fn default_value_weighting_interval() -> u64 {
+        0
+    }
+
+    pub const fn default_value_weighting_window() -> u64 {
+        3000
+    }
+
+    pub const fn default_value_weighting_decay() -> f64 {
+        0.0
+    }
+
+    pub const fn default_value_launch_boost_time() -> u64 {
+        0
+    }
+
+    pub const fn default_value_start_delay() -> u64 {
+        3000
+    }
+
+    pub const fn default_value_start_frames() -> u32 {
+        0
+    }
+
+    pub const fn default_value_multi_window() -> bool {
+        false
+    }
+
+    pub const fn default_value_deep_relax_time() -> u64 {
+        0
+    }
+
+    pub const fn default_value_cooldown_time() -> u64 {
+        0
+    }
+
+    pub const fn default_value_cooldown_ceiling() -> f64 {
+        0.7
+    }
+
+    pub const fn default_value_ladder_time() -> u64 {
+        0
+    }
+
+    pub const fn default_value_frame_pacing() -> bool {
+        false
+    }
+
+    pub const fn default_value_video_detect() -> bool {
+        false
+    }
+
+    pub const fn default_value_stop_vendor_daemons() -> bool {
+        false
+    }
+
+    pub const fn default_value_throttle_background() -> bool {
+        false
+    }
+
+    pub const fn default_value_fpsgo_coexist() -> bool {
+        false
+    }
+
+    pub const fn default_value_freq_backend() -> FreqBackend {
+        FreqBackend::Cpufreq
+    }
+
+    pub const fn default_value_freq_control() -> FreqControl {
+        FreqControl::Pin
+    }
+
+    pub const fn default_value_devfreq_boost() -> bool {
+        false
+    }
+
+    pub const fn default_value_io_boost() -> bool {
+        false
+    }
+
+    pub const fn default_value_restrict_cpuidle() -> bool {
+        false
+    }
+
+    pub const fn default_value_log_level() -> LogLevel {
+        if cfg!(debug_assertions) {
+            LogLevel::Debug
+        } else {
+            LogLevel::Info
+        }
+    }
+
+    pub const fn default_value_trace_marker() -> bool {
+        false
+    }
+
+    pub const fn default_value_overlay_interval() -> u64 {
+        0
+    }
+
+    pub const fn default_value_webui_port() -> u16 {
+        0
+    }
+
+    pub const fn default_value_binder_control() -> bool {
+        false
+    }
+
+    pub const fn default_value_record() -> bool {
+        false
+    }
+
+    pub const fn default_value_dry_run() -> bool {
+        false
+    }
+}
+
+impl SensorConfig {
+    pub fn default_value_priority() -> Vec<SensorKind> {
+        vec![
+            SensorKind::Ebpf,
+            SensorKind::Zygisk,
+            SensorKind::SurfaceFlinger,
+            SensorKind::Gfxinfo,
+        ]
+    }
+}
+
+impl Default for SensorConfig {
+    fn default() -> Self {
+        Self {
+            priority: Self::default_value_priority(),
+            game: HashMap::new(),
+        }
+    }
+}
+
+impl ThermalConfig {
+    pub const fn default_value_hysteresis() -> u32 {
+        2
+    }
+}
+
+impl Default for ThermalConfig {
+    fn default() -> Self {
+        Self {
+            zone: None,
+            hysteresis: Self::default_value_hysteresis(),
+            rules: Vec::new(),
+            policies: HashMap::new(),
+            policy_rules: Vec::new(),
+        }
+    }
+}
+
+impl IrqConfig {
+    pub fn default_value_keywords() -> Vec<String> {
+        [
+            "mdss",
+            "dsi",
+            "kgsl",
+            "mali",
+            "gpu",
+            "touch",
+            "fts",
+            "goodix",
+            "synaptics",
+        ]
+        .into_iter()
+        .map(String::from)
+        .collect()
+    }
+}
+
+impl Default for IrqConfig {
+    fn default() -> Self {
+        Self {
+            policy: None,
+            keywords: Self::default_value_keywords(),
+        }
+    }
+}
+
+impl SessionConfig {
+    pub fn default_value_milestones() -> Vec<u64> {
+        vec![30, 60, 120]
+    }
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            milestones: Self::default_value_milestones(),
+        }
+    }
 }
@@ -14,21 +14,62 @@
 
 mod default;
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use serde::{Deserialize, Serialize};
-use toml::Table;
+use toml::{Table, Value};
+
+use super::pattern::PackagePattern;
+use crate::framework::node::Mode;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ConfigData {
+    /// Schema version, older configs are migrated on startup
+    #[serde(default)]
+    pub version: u32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    #[serde(skip)]
+    pub included: HashSet<String>,
     pub config: Config,
     pub game_list: Table,
     #[serde(skip)]
-    pub scene_game_list: HashSet<String>,
+    pub game_patterns: Vec<(PackagePattern, Value)>,
+    #[serde(skip)]
+    pub imported_games: HashSet<String>,
     pub powersave: ModeConfig,
     pub balance: ModeConfig,
     pub performance: ModeConfig,
     pub fast: ModeConfig,
+    #[serde(default)]
+    pub sensor: SensorConfig,
+    #[serde(default)]
+    pub battery: BatteryConfig,
+    #[serde(default)]
+    pub power_budget: HashMap<String, u32>,
+    #[serde(default)]
+    pub refresh_rate: HashMap<String, u32>,
+    /// Static `[min, max]` by `policy<n>` for games that skip FAS
+    #[serde(default)]
+    pub fixed_freq: HashMap<String, HashMap<String, [isize; 2]>>,
+    #[serde(default)]
+    pub freq_control: HashMap<String, FreqControl>,
+    #[serde(default)]
+    pub app_type: HashMap<String, AppType>,
+    #[serde(default)]
+    pub isolate_core: HashMap<String, bool>,
+    #[serde(default)]
+    pub thermal: ThermalConfig,
+    #[serde(default)]
+    pub irq: IrqConfig,
+    #[serde(default)]
+    pub session: SessionConfig,
+    #[serde(default)]
+    pub blacklist: BlacklistConfig,
+    #[serde(default)]
+    pub control: ControlConfig,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub extensions: HashMap<String, Table>,
 }
 
 #[allow(clippy::struct_excessive_bools)]
@@ -40,6 +81,221 @@ pub struct Config {
     pub userspace_governor: bool,
     #[serde(default = "Config::default_value_scene_game_list")]
     pub scene_game_list: bool,
+    #[serde(default = "Config::default_value_file_game_list")]
+    pub file_game_list: bool,
+    #[serde(default = "Config::default_value_game_turbo_list")]
+    pub game_turbo_list: bool,
+    #[serde(default = "Config::default_value_auto_detect")]
+    pub auto_detect: bool,
+    #[serde(default = "Config::default_value_mode_sync")]
+    pub mode_sync: bool,
+    #[serde(default = "Config::default_value_refresh_rate_cap")]
+    pub refresh_rate_cap: bool,
+    #[serde(default = "Config::default_value_outlier_ratio")]
+    pub outlier_ratio: f64,
+    #[serde(default = "Config::default_value_smoothing")]
+    pub smoothing: Smoothing,
+    #[serde(default = "Config::default_value_smoothing_alpha")]
+    pub smoothing_alpha: f64,
+    #[serde(default = "Config::default_value_smoothing_window")]
+    pub smoothing_window: usize,
+    #[serde(default = "Config::default_value_jitter_threshold")]
+    pub jitter_threshold: f64,
+    #[serde(default = "Config::default_value_vsync_align")]
+    pub vsync_align: bool,
+    #[serde(default = "Config::default_value_weighting_interval")]
+    pub weighting_interval: u64,
+    #[serde(default = "Config::default_value_weighting_window")]
+    pub weighting_window: u64,
+    #[serde(default = "Config::default_value_weighting_decay")]
+    pub weighting_decay: f64,
+    #[serde(default = "Config::default_value_launch_boost_time")]
+    pub launch_boost_time: u64,
+    #[serde(default = "Config::default_value_start_delay")]
+    pub start_delay: u64,
+    #[serde(default = "Config::default_value_start_frames")]
+    pub start_frames: u32,
+    #[serde(default = "Config::default_value_multi_window")]
+    pub multi_window: bool,
+    #[serde(default = "Config::default_value_deep_relax_time")]
+    pub deep_relax_time: u64,
+    #[serde(default = "Config::default_value_cooldown_time")]
+    pub cooldown_time: u64,
+    #[serde(default = "Config::default_value_cooldown_ceiling")]
+    pub cooldown_ceiling: f64,
+    #[serde(default = "Config::default_value_ladder_time")]
+    pub ladder_time: u64,
+    #[serde(default = "Config::default_value_frame_pacing")]
+    pub frame_pacing: bool,
+    #[serde(default = "Config::default_value_video_detect")]
+    pub video_detect: bool,
+    #[serde(default = "Config::default_value_stop_vendor_daemons")]
+    pub stop_vendor_daemons: bool,
+    #[serde(default = "Config::default_value_throttle_background")]
+    pub throttle_background: bool,
+    #[serde(default = "Config::default_value_fpsgo_coexist")]
+    pub fpsgo_coexist: bool,
+    #[serde(default = "Config::default_value_freq_backend")]
+    pub freq_backend: FreqBackend,
+    #[serde(default = "Config::default_value_freq_control")]
+    pub freq_control: FreqControl,
+    #[serde(default = "Config::default_value_devfreq_boost")]
+    pub devfreq_boost: bool,
+    #[serde(default = "Config::default_value_io_boost")]
+    pub io_boost: bool,
+    #[serde(default = "Config::default_value_restrict_cpuidle")]
+    pub restrict_cpuidle: bool,
+    #[serde(default = "Config::default_value_log_level")]
+    pub log_level: LogLevel,
+    #[serde(default = "Config::default_value_trace_marker")]
+    pub trace_marker: bool,
+    #[serde(default = "Config::default_value_overlay_interval")]
+    pub overlay_interval: u64,
+    #[serde(default = "Config::default_value_webui_port")]
+    pub webui_port: u16,
+    #[serde(default = "Config::default_value_binder_control")]
+    pub binder_control: bool,
+    #[serde(default = "Config::default_value_record")]
+    pub record: bool,
+    #[serde(default = "Config::default_value_dry_run")]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Smoothing {
+    Raw,
+    Ema,
+    Median,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Error,
+    Info,
+    Debug,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FreqBackend {
+    Cpufreq,
+    MsmPerformance,
+}
+
+/// Which limits the frequency is written to
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FreqControl {
+    /// Both limits, the frequency is pinned
+    Pin,
+    /// Only the max limit, fas-rs never raises frequencies above stock
+    Limit,
+    /// Only the min limit, fas-rs never lowers frequencies below stock
+    Boost,
+}
+
+/// What kind of app a game list entry is, changes how its frames are measured
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AppType {
+    Game,
+    /// Presents at the panel rate, the guest renderer is followed through its GPU fences
+    Emulator,
+    /// Renders video, skipped unless `[fixed_freq]` gives it a static clamp
+    Streaming,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SensorConfig {
+    #[serde(default = "SensorConfig::default_value_priority")]
+    pub priority: Vec<SensorKind>,
+    #[serde(default)]
+    pub game: HashMap<String, Vec<SensorKind>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SensorKind {
+    Ebpf,
+    Zygisk,
+    SurfaceFlinger,
+    Gfxinfo,
+    Fence,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct BatteryConfig {
+    #[serde(default)]
+    pub rules: Vec<BatteryRule>,
+    pub charging_mode: Option<Mode>,
+    pub charging_max_temp: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct BatteryRule {
+    pub below: u32,
+    pub mode: Option<Mode>,
+    pub target_fps: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ThermalConfig {
+    pub zone: Option<String>,
+    #[serde(default = "ThermalConfig::default_value_hysteresis")]
+    pub hysteresis: u32,
+    #[serde(default)]
+    pub rules: Vec<ThermalRule>,
+    /// Thermal zone types of each cluster, keyed by `policy<n>`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub policies: HashMap<String, Vec<String>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub policy_rules: Vec<PolicyThermalRule>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct ThermalRule {
+    pub above: u32,
+    pub target_fps: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct PolicyThermalRule {
+    pub above: u32,
+    /// Fraction of the cluster's max frequency it is capped to
+    pub max_freq: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct IrqConfig {
+    pub policy: Option<i32>,
+    #[serde(default = "IrqConfig::default_value_keywords")]
+    pub keywords: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SessionConfig {
+    /// Minutes of play after which extensions get `session_milestone`
+    #[serde(default = "SessionConfig::default_value_milestones")]
+    pub milestones: Vec<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct BlacklistConfig {
+    #[serde(default)]
+    pub packages: Vec<String>,
+    #[serde(default)]
+    pub video_players: bool,
+    #[serde(skip)]
+    pub patterns: Vec<PackagePattern>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ControlConfig {
+    /// Apps besides root and shell that may change the mode or config
+    #[serde(default)]
+    pub uids: Vec<u32>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Copy)]
@@ -0,0 +1,74 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::path::Path;
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use super::read::ParseError;
+use crate::framework::error::Result;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Toml,
+    Json,
+    Yaml,
+}
+
+impl Format {
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("json") => Self::Json,
+            Some("yaml" | "yml") => Self::Yaml,
+            _ => Self::Toml,
+        }
+    }
+
+    pub const fn extension(self) -> &'static str {
+        match self {
+            Self::Toml => "toml",
+            Self::Json => "json",
+            Self::Yaml => "yaml",
+        }
+    }
+
+    pub fn parse<T: DeserializeOwned>(self, source: &str) -> std::result::Result<T, ParseError> {
+        match self {
+            Self::Toml => toml::from_str(source).map_err(|e| ParseError::new(&e, source)),
+            Self::Json => serde_json::from_str(source)
+                .map_err(|e| ParseError::at(source, e.line(), e.column(), &strip_location(&e))),
+            Self::Yaml => serde_yaml::from_str(source).map_err(|e| {
+                let (line, column) = e
+                    .location()
+                    .map_or((0, 0), |location| (location.line(), location.column()));
+                ParseError::at(source, line, column, &strip_location(&e))
+            }),
+        }
+    }
+
+    pub fn serialize<T: Serialize>(self, value: &T) -> Result<String> {
+        Ok(match self {
+            Self::Toml => toml::to_string(value)?,
+            Self::Json => serde_json::to_string_pretty(value)?,
+            Self::Yaml => serde_yaml::to_string(value)?,
+        })
+    }
+}
+
+fn strip_location(e: &impl ToString) -> String {
+    let message = e.to_string();
+    message
+        .split_once(" at line ")
+        .map_or_else(|| message.clone(), |(message, _)| message.to_string())
+}
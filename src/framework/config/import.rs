@@ -0,0 +1,135 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use super::data::{Config, SceneAppList};
+use crate::framework::error::Result;
+
+const SCENE_PROFILE: &str = "/data/data/com.omarea.vtools/shared_prefs/games.xml";
+const GAME_LIST_FILE: &str = "/sdcard/Android/fas-rs/game_list.txt";
+const GAME_TURBO_SETTING: &str = "gb_boosting_game";
+
+pub trait GameListImporter {
+    fn name(&self) -> &'static str;
+
+    /// files to watch for changes, empty if the source can not be watched
+    fn paths(&self) -> Vec<PathBuf>;
+
+    fn import(&self) -> Result<HashSet<String>>;
+}
+
+pub struct Scene;
+
+impl GameListImporter for Scene {
+    fn name(&self) -> &'static str {
+        "scene"
+    }
+
+    fn paths(&self) -> Vec<PathBuf> {
+        vec![SCENE_PROFILE.into()]
+    }
+
+    fn import(&self) -> Result<HashSet<String>> {
+        if !Path::new(SCENE_PROFILE).exists() {
+            return Ok(HashSet::new());
+        }
+
+        let scene_apps = fs::read_to_string(SCENE_PROFILE)?;
+        let scene_apps: SceneAppList = quick_xml::de::from_str(&scene_apps)?;
+
+        Ok(scene_apps
+            .apps
+            .into_iter()
+            .filter(|app| app.is_game)
+            .map(|game| game.pkg)
+            .collect())
+    }
+}
+
+/// One package per line, `#` starts a comment
+pub struct PackageListFile;
+
+impl GameListImporter for PackageListFile {
+    fn name(&self) -> &'static str {
+        "file"
+    }
+
+    fn paths(&self) -> Vec<PathBuf> {
+        vec![GAME_LIST_FILE.into()]
+    }
+
+    fn import(&self) -> Result<HashSet<String>> {
+        if !Path::new(GAME_LIST_FILE).exists() {
+            return Ok(HashSet::new());
+        }
+
+        Ok(fs::read_to_string(GAME_LIST_FILE)?
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or_default().trim())
+            .filter(|pkg| !pkg.is_empty())
+            .map(ToString::to_string)
+            .collect())
+    }
+}
+
+/// Games added to Xiaomi Game Turbo (MIUI / `HyperOS`)
+pub struct GameTurbo;
+
+impl GameListImporter for GameTurbo {
+    fn name(&self) -> &'static str {
+        "game_turbo"
+    }
+
+    fn paths(&self) -> Vec<PathBuf> {
+        Vec::new()
+    }
+
+    fn import(&self) -> Result<HashSet<String>> {
+        let output = Command::new("settings")
+            .args(["get", "secure", GAME_TURBO_SETTING])
+            .output()?;
+        let value = String::from_utf8_lossy(&output.stdout);
+
+        Ok(value
+            .split([',', ';', ' ', '\n'])
+            .map(str::trim)
+            .filter(|pkg| !pkg.is_empty() && *pkg != "null")
+            .map(ToString::to_string)
+            .collect())
+    }
+}
+
+pub fn importers(config: &Config) -> Vec<Box<dyn GameListImporter>> {
+    let mut importers: Vec<Box<dyn GameListImporter>> = Vec::new();
+
+    if config.scene_game_list {
+        importers.push(Box::new(Scene));
+    }
+
+    if config.file_game_list {
+        importers.push(Box::new(PackageListFile));
+    }
+
+    if config.game_turbo_list {
+        importers.push(Box::new(GameTurbo));
+    }
+
+    importers
+}
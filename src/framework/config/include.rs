@@ -0,0 +1,125 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use log::{error, info};
+use serde::Deserialize;
+use toml::Table;
+
+use super::{
+    data::{AppType, ConfigData, FreqControl, SensorKind},
+    format::Format,
+};
+
+#[derive(Debug, Default, Deserialize)]
+struct IncludeData {
+    #[serde(default)]
+    game_list: Table,
+    #[serde(default)]
+    power_budget: HashMap<String, u32>,
+    #[serde(default)]
+    refresh_rate: HashMap<String, u32>,
+    #[serde(default)]
+    fixed_freq: HashMap<String, HashMap<String, [isize; 2]>>,
+    #[serde(default)]
+    freq_control: HashMap<String, FreqControl>,
+    #[serde(default)]
+    app_type: HashMap<String, AppType>,
+    #[serde(default)]
+    isolate_core: HashMap<String, bool>,
+    #[serde(default)]
+    sensor: IncludeSensor,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct IncludeSensor {
+    #[serde(default)]
+    game: HashMap<String, Vec<SensorKind>>,
+}
+
+impl ConfigData {
+    pub fn include_paths(&self, dir: &Path) -> Vec<PathBuf> {
+        self.include.iter().map(|path| dir.join(path)).collect()
+    }
+
+    pub fn load_includes(&mut self, dir: &Path) {
+        for path in self.include_paths(dir) {
+            let data = match fs::read_to_string(&path) {
+                Ok(source) => Format::from_path(&path).parse::<IncludeData>(&source),
+                Err(e) => {
+                    error!("Failed to read included config {path:?}, reason: {e}");
+                    continue;
+                }
+            };
+
+            let data = match data {
+                Ok(data) => data,
+                Err(e) => {
+                    error!("Failed to parse included config {path:?} at {e}");
+                    continue;
+                }
+            };
+
+            let mut count = 0;
+            for (pkg, value) in data.game_list {
+                if !self.game_list.contains_key(&pkg) {
+                    self.included.insert(pkg.clone());
+                    self.game_list.insert(pkg, value);
+                    count += 1;
+                }
+            }
+
+            for (pkg, budget) in data.power_budget {
+                self.power_budget.entry(pkg).or_insert(budget);
+            }
+
+            for (pkg, rate) in data.refresh_rate {
+                self.refresh_rate.entry(pkg).or_insert(rate);
+            }
+
+            for (pkg, limits) in data.fixed_freq {
+                self.fixed_freq.entry(pkg).or_insert(limits);
+            }
+
+            for (pkg, control) in data.freq_control {
+                self.freq_control.entry(pkg).or_insert(control);
+            }
+
+            for (pkg, app_type) in data.app_type {
+                self.app_type.entry(pkg).or_insert(app_type);
+            }
+
+            for (pkg, isolate) in data.isolate_core {
+                self.isolate_core.entry(pkg).or_insert(isolate);
+            }
+
+            for (pkg, priority) in data.sensor.game {
+                self.sensor.game.entry(pkg).or_insert(priority);
+            }
+
+            info!("Included {count} games from {path:?}");
+        }
+    }
+
+    pub fn without_includes(&self) -> Self {
+        let mut data = self.clone();
+        data.game_list.retain(|pkg, _| !self.included.contains(pkg));
+        data
+    }
+}
@@ -16,26 +16,97 @@ use likely_stable::LikelyOption;
 use serde::{Deserialize, Serialize};
 use toml::{Table, Value};
 
-use super::Config;
+use super::{format::Format, migrate, Config};
 use crate::framework::error::{Error, Result};
 
 #[derive(Deserialize, Serialize)]
 struct ConfigData {
+    #[serde(default)]
+    pub version: u32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<Value>,
     pub config: Table,
     pub game_list: Table,
     pub powersave: Table,
     pub balance: Table,
     pub performance: Table,
     pub fast: Table,
+    #[serde(default)]
+    pub sensor: Table,
+    #[serde(default)]
+    pub battery: Table,
+    #[serde(default)]
+    pub power_budget: Table,
+    #[serde(default)]
+    pub refresh_rate: Table,
+    #[serde(default, skip_serializing_if = "Table::is_empty")]
+    pub fixed_freq: Table,
+    #[serde(default, skip_serializing_if = "Table::is_empty")]
+    pub freq_control: Table,
+    #[serde(default, skip_serializing_if = "Table::is_empty")]
+    pub app_type: Table,
+    #[serde(default, skip_serializing_if = "Table::is_empty")]
+    pub isolate_core: Table,
+    #[serde(default)]
+    pub thermal: Table,
+    #[serde(default)]
+    pub irq: Table,
+    #[serde(default)]
+    pub session: Table,
+    #[serde(default)]
+    pub blacklist: Table,
+    #[serde(default, skip_serializing_if = "Table::is_empty")]
+    pub control: Table,
+    #[serde(default, skip_serializing_if = "Table::is_empty")]
+    pub extensions: Table,
 }
 
 impl Config {
-    pub fn merge<S: AsRef<str>>(l: S, s: S) -> Result<String> {
+    /// The standard config is always toml, the local one and the result are in `format`
+    pub fn merge<S: AsRef<str>>(l: S, s: S, format: Format) -> Result<String> {
         let local_conf = l.as_ref();
         let std_conf = s.as_ref();
 
         let std_conf: ConfigData = toml::from_str(std_conf)?;
-        let local_conf: ConfigData = toml::from_str(local_conf)?;
+        let mut local_conf: Table = format.parse(local_conf).map_err(Error::Config)?;
+        migrate::migrate(&mut local_conf);
+        let local_conf: ConfigData = Value::Table(local_conf).try_into()?;
+
+        let sensor = if local_conf.sensor.is_empty() {
+            std_conf.sensor
+        } else {
+            local_conf.sensor
+        };
+
+        let battery = if local_conf.battery.is_empty() {
+            std_conf.battery
+        } else {
+            local_conf.battery
+        };
+
+        let thermal = if local_conf.thermal.is_empty() {
+            std_conf.thermal
+        } else {
+            local_conf.thermal
+        };
+
+        let irq = if local_conf.irq.is_empty() {
+            std_conf.irq
+        } else {
+            local_conf.irq
+        };
+
+        let session = if local_conf.session.is_empty() {
+            std_conf.session
+        } else {
+            local_conf.session
+        };
+
+        let blacklist = if local_conf.blacklist.is_empty() {
+            std_conf.blacklist
+        } else {
+            local_conf.blacklist
+        };
 
         if local_conf
             .config
@@ -44,14 +115,30 @@ impl Config {
             .ok_or(Error::ParseConfig)?
         {
             let new_conf = ConfigData {
+                version: std_conf.version,
+                include: local_conf.include,
                 config: std_conf.config,
                 game_list: local_conf.game_list,
                 powersave: std_conf.powersave,
                 balance: std_conf.balance,
                 performance: std_conf.performance,
                 fast: std_conf.fast,
+                sensor,
+                battery,
+                power_budget: local_conf.power_budget,
+                refresh_rate: local_conf.refresh_rate,
+                fixed_freq: local_conf.fixed_freq,
+                freq_control: local_conf.freq_control,
+                app_type: local_conf.app_type,
+                isolate_core: local_conf.isolate_core,
+                thermal,
+                irq,
+                session,
+                blacklist,
+                control: local_conf.control,
+                extensions: local_conf.extensions,
             };
-            return Ok(toml::to_string(&new_conf)?);
+            return format.serialize(&new_conf);
         }
 
         let config = Self::table_merge(std_conf.config, local_conf.config);
@@ -61,15 +148,73 @@ impl Config {
         let fast = Self::table_merge(std_conf.fast, local_conf.fast);
 
         let new_conf = ConfigData {
+            version: std_conf.version,
+            include: local_conf.include,
             config,
             game_list: local_conf.game_list,
             powersave,
             balance,
             performance,
             fast,
+            sensor,
+            battery,
+            power_budget: local_conf.power_budget,
+            refresh_rate: local_conf.refresh_rate,
+            fixed_freq: local_conf.fixed_freq,
+            freq_control: local_conf.freq_control,
+            app_type: local_conf.app_type,
+            isolate_core: local_conf.isolate_core,
+            thermal,
+            irq,
+            session,
+            blacklist,
+            control: local_conf.control,
+            extensions: local_conf.extensions,
         };
 
-        Ok(toml::to_string(&new_conf)?)
+        format.serialize(&new_conf)
+    }
+
+    pub fn diff<S: AsRef<str>>(o: S, n: S, format: Format) -> Result<Vec<String>> {
+        let old: Table = format.parse(o.as_ref()).map_err(Error::Config)?;
+        let new: Table = format.parse(n.as_ref()).map_err(Error::Config)?;
+
+        let mut diff = Vec::new();
+        let mut sections: Vec<_> = old.keys().chain(new.keys()).collect();
+        sections.sort_unstable();
+        sections.dedup();
+
+        for section in sections {
+            match (old.get(section), new.get(section)) {
+                (Some(Value::Table(old)), Some(Value::Table(new))) => {
+                    Self::table_diff(section, old, new, &mut diff);
+                }
+                (Some(_), None) => diff.push(format!("- [{section}]")),
+                (None, Some(_)) => diff.push(format!("+ [{section}]")),
+                (Some(old), Some(new)) if old != new => {
+                    diff.push(format!("~ {section}: {old} -> {new}"));
+                }
+                _ => (),
+            }
+        }
+
+        Ok(diff)
+    }
+
+    fn table_diff(section: &str, old: &Table, new: &Table, diff: &mut Vec<String>) {
+        for (key, value) in old {
+            match new.get(key) {
+                None => diff.push(format!("- [{section}] {key} = {value}")),
+                Some(new) if new != value => {
+                    diff.push(format!("~ [{section}] {key}: {value} -> {new}"));
+                }
+                Some(_) => (),
+            }
+        }
+
+        for (key, value) in new.iter().filter(|(key, _)| !old.contains_key(*key)) {
+            diff.push(format!("+ [{section}] {key} = {value}"));
+        }
     }
 
     fn table_merge(mut s: Table, l: Table) -> Table {
@@ -0,0 +1,119 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{fs, path::Path};
+
+use log::{info, warn};
+use toml::{Table, Value};
+
+use super::format::Format;
+
+/// Schema version written by this build, bump it together with a new entry in `STEPS`
+pub const VERSION: u32 = 1;
+
+/// `STEPS[n]` migrates a config of version `n` to `n + 1`, a config without `version` is version 0
+const STEPS: [fn(&mut Table); VERSION as usize] = [flat_game_list];
+
+pub fn version(table: &Table) -> u32 {
+    table
+        .get("version")
+        .and_then(Value::as_integer)
+        .and_then(|version| u32::try_from(version).ok())
+        .unwrap_or(0)
+}
+
+/// Bring `table` up to `VERSION`, returns whether anything changed
+pub fn migrate(table: &mut Table) -> bool {
+    let version = version(table);
+    if version >= VERSION {
+        return false;
+    }
+
+    for step in &STEPS[version as usize..] {
+        step(table);
+    }
+    table.insert("version".into(), Value::Integer(VERSION.into()));
+
+    true
+}
+
+/// Migrate the config file at `path` in place, the old file is kept as `<name>.<ext>.v<n>.bak`.
+/// Returns the source to load, the original one if it can't be migrated
+pub fn migrate_file(path: &Path, source: String) -> String {
+    let format = Format::from_path(path);
+    let Ok(mut table) = format.parse::<Table>(&source) else {
+        return source;
+    };
+
+    let from = version(&table);
+    let mut migrated = table.clone();
+    if !migrate(&mut migrated) {
+        return source;
+    }
+
+    // When no step changed anything, the comments and layout of the file are kept
+    let unchanged = {
+        let mut migrated = migrated.clone();
+        migrated.remove("version");
+        table.remove("version");
+        migrated == table
+    };
+    let Some(migrated) = unchanged
+        .then(|| insert_version(format, &source))
+        .filter(|text| {
+            format
+                .parse::<Table>(text)
+                .is_ok_and(|table| table == migrated)
+        })
+        .or_else(|| format.serialize(&migrated).ok())
+    else {
+        return source;
+    };
+
+    let backup = path.with_extension(format!("{}.v{from}.bak", format.extension()));
+    match fs::copy(path, &backup).and_then(|_| fs::write(path, &migrated)) {
+        Ok(()) => info!(
+            "Config migrated from version {from} to {VERSION}, the old one is kept at {backup:?}"
+        ),
+        Err(e) => warn!(
+            "Config migrated from version {from} to {VERSION} but failed to write it back: {e}"
+        ),
+    }
+
+    migrated
+}
+
+/// `source` with a `version` line added as text, the caller checks that it still parses
+fn insert_version(format: Format, source: &str) -> String {
+    match format {
+        // Top level keys have to come before the first table
+        Format::Toml => format!("version = {VERSION}\n{source}"),
+        Format::Yaml => format!("version: {VERSION}\n{source}"),
+        Format::Json => source.replacen('{', &format!("{{\n  \"version\": {VERSION},"), 1),
+    }
+}
+
+/// `game_list` used to be a plain list of packages, each of them now maps to its target fps
+fn flat_game_list(table: &mut Table) {
+    let Some(Value::Array(pkgs)) = table.get("game_list") else {
+        return;
+    };
+
+    let games: Table = pkgs
+        .iter()
+        .filter_map(Value::as_str)
+        .map(|pkg| (pkg.to_string(), Value::String("auto".into())))
+        .collect();
+    table.insert("game_list".into(), Value::Table(games));
+}
@@ -12,29 +12,66 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod blacklist;
+mod check;
 mod data;
+mod format;
+mod import;
+mod include;
 mod merge;
+mod migrate;
+mod pattern;
 mod read;
 
-use std::{fs, path::Path, sync::Arc, thread};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::Path,
+    sync::Arc,
+    thread,
+};
 
 use log::{error, info};
 use parking_lot::RwLock;
-use toml::Value;
+use toml::{Table, Value};
 
-use crate::framework::{error::Result, node::Mode};
-use data::{Config as ConfigConfig, ConfigData, ModeConfig};
+use crate::{
+    framework::{
+        error::{Error, Result},
+        node::Mode,
+    },
+    misc,
+};
+use data::{AppType, Config as ConfigConfig, ConfigData, ModeConfig};
 use read::wait_and_read;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+pub use format::Format;
+pub use read::ParseError;
+
+pub use check::{Diagnostic, Severity};
+pub use data::{
+    BatteryRule, FreqBackend, FreqControl, IrqConfig, LogLevel, PolicyThermalRule, SensorKind,
+    Smoothing, ThermalConfig, ThermalRule,
+};
+
+/// `AID_SHELL`, commands run over adb
+const SHELL_UID: u32 = 2000;
+
+/// Targets of `"auto"` and imported games, up to 165Hz panels
+pub const AUTO_TARGET_FPS: [f64; 7] = [30.0, 45.0, 60.0, 90.0, 120.0, 144.0, 165.0];
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum TargetFps {
-    Value(u32),
-    Array(Vec<u32>),
+    Value(f64),
+    Array(Vec<f64>),
 }
 
 #[derive(Debug, Clone)]
 pub struct Config {
+    path: Arc<Path>,
     toml: Arc<RwLock<ConfigData>>,
+    parse_error: Arc<RwLock<Option<ParseError>>>,
+    auto_games: Arc<RwLock<HashSet<String>>>,
 }
 
 impl Config {
@@ -42,75 +79,299 @@ impl Config {
         let path = p.as_ref();
         let std_path = sp.as_ref();
 
-        let ori = fs::read_to_string(path)?;
+        let ori = migrate::migrate_file(path, fs::read_to_string(path)?);
 
-        let toml: ConfigData = toml::from_str(&ori)?;
+        let mut toml: ConfigData = Format::from_path(path).parse(&ori).map_err(|e| {
+            error!("Failed to parse config {path:?} at {e}");
+            Error::Config(e)
+        })?;
+        toml.load_includes(path.parent().unwrap_or_else(|| Path::new(".")));
+        toml.load_patterns();
+        toml.load_blacklist();
         let toml = Arc::new(RwLock::new(toml));
+        let parse_error = Arc::new(RwLock::new(None));
 
         {
             let path = path.to_owned();
             let std_path = std_path.to_owned();
             let toml = toml.clone();
+            let parse_error = parse_error.clone();
 
             thread::Builder::new()
                 .name("ConfigThread".into())
                 .spawn(move || {
-                    wait_and_read(&path, &std_path, &toml).unwrap_or_else(|e| error!("{e:#?}"));
+                    misc::set_idle_priority();
+                    wait_and_read(&path, &std_path, &toml, &parse_error)
+                        .unwrap_or_else(|e| error!("{e:#?}"));
                     panic!("An unrecoverable error occurred!");
                 })?;
         }
 
         info!("Config watcher started");
 
-        Ok(Self { toml })
+        Ok(Self {
+            path: Arc::from(path),
+            toml,
+            parse_error,
+            auto_games: Arc::new(RwLock::new(HashSet::new())),
+        })
+    }
+
+    /// The user config file being watched
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Check that `source` parses as a config in the format of `path`, without applying it
+    pub fn validate(path: &Path, source: &str) -> std::result::Result<(), ParseError> {
+        Format::from_path(path)
+            .parse::<ConfigData>(source)
+            .map(|_| ())
+    }
+
+    /// Replace the config file at `path` with `source` if it parses, the config thread picks it up by itself
+    pub fn write_checked(path: &Path, source: &str) -> Result<()> {
+        Self::validate(path, source).map_err(Error::Config)?;
+
+        let temp = path.with_extension("tmp");
+        fs::write(&temp, source)?;
+        fs::rename(&temp, path)?;
+
+        Ok(())
+    }
+
+    /// The merged config currently in effect, serialized as toml
+    pub fn dump(&self) -> Result<String> {
+        Format::Toml.serialize(&*self.toml.read())
+    }
+
+    pub fn parse_error(&self) -> Option<String> {
+        self.parse_error.read().as_ref().map(ToString::to_string)
     }
 
     pub fn need_fas<S: AsRef<str>>(&self, pkg: S) -> bool {
         let toml = self.toml.read();
         let pkg = pkg.as_ref();
 
-        toml.game_list.contains_key(pkg) || toml.scene_game_list.contains(pkg)
+        if toml.blacklisted(pkg) || toml.skips_fas(pkg) {
+            return false;
+        }
+
+        toml.game_list.contains_key(pkg)
+            || toml
+                .game_list
+                .keys()
+                .any(|key| key.split_once('/').is_some_and(|(p, _)| p == pkg))
+            || toml
+                .game_patterns
+                .iter()
+                .any(|(pattern, _)| pattern.is_match(pkg))
+            || toml.imported_games.contains(pkg)
+            || (toml.config.auto_detect && self.auto_games.read().contains(pkg))
     }
 
     pub fn target_fps<S: AsRef<str>>(&self, pkg: S) -> Option<TargetFps> {
         let pkg = pkg.as_ref();
         let pkg = pkg.split(':').next()?;
 
-        self.toml.read().game_list.get(pkg).map_or_else(
+        let value = {
+            let toml = self.toml.read();
+            toml.game_list.get(pkg).cloned().or_else(|| {
+                toml.game_patterns
+                    .iter()
+                    .find(|(pattern, _)| pattern.is_match(pkg))
+                    .map(|(_, value)| value.clone())
+            })
+        };
+
+        value.as_ref().map_or_else(
             || {
-                if self.toml.read().scene_game_list.contains(pkg) {
-                    Some(TargetFps::Array(vec![30, 45, 60, 90, 120, 144]))
+                if self.toml.read().imported_games.contains(pkg)
+                    || self.auto_games.read().contains(pkg)
+                {
+                    Some(TargetFps::Array(AUTO_TARGET_FPS.to_vec()))
                 } else {
                     None
                 }
             },
-            |value| match value {
-                Value::Array(arr) => {
-                    let mut arr: Vec<_> = arr
-                        .iter()
-                        .filter_map(toml::Value::as_integer)
-                        .map(|i| i as u32)
-                        .collect();
-                    arr.sort_unstable();
-                    Some(TargetFps::Array(arr))
-                }
-                Value::Integer(i) => Some(TargetFps::Value(*i as u32)),
-                Value::String(s) => {
-                    if s == "auto" {
-                        Some(TargetFps::Array(vec![30, 45, 60, 90, 120, 144]))
-                    } else {
-                        error!("Find target game {pkg} in config, but meet illegal data type");
-                        error!("Sugg: try \'{pkg} = \"auto\"\'");
-                        None
-                    }
-                }
-                _ => {
+            |value| Self::parse_target_fps(pkg, value),
+        )
+    }
+
+    pub fn activity_target_fps<S: AsRef<str>>(&self, pkg: S, activity: S) -> Option<TargetFps> {
+        let pkg = pkg.as_ref();
+        let activity = activity.as_ref();
+
+        let mut keys = vec![format!("{pkg}/{activity}")];
+        if let Some(short) = activity.strip_prefix(pkg) {
+            keys.push(format!("{pkg}/{short}"));
+        } else if activity.starts_with('.') {
+            keys.push(format!("{pkg}/{pkg}{activity}"));
+        }
+
+        let value = {
+            let toml = self.toml.read();
+            keys.iter().find_map(|key| toml.game_list.get(key).cloned())
+        };
+
+        value.map_or_else(
+            || self.target_fps(pkg),
+            |value| Self::parse_target_fps(pkg, &value),
+        )
+    }
+
+    fn parse_target_fps(pkg: &str, value: &Value) -> Option<TargetFps> {
+        match value {
+            Value::Array(arr) => {
+                let mut arr: Vec<_> = arr.iter().filter_map(Self::fps_value).collect();
+                arr.sort_unstable_by(f64::total_cmp);
+                Some(TargetFps::Array(arr))
+            }
+            Value::Integer(_) | Value::Float(_) => Self::fps_value(value).map(TargetFps::Value),
+            Value::String(s) => {
+                if s == "auto" {
+                    Some(TargetFps::Array(AUTO_TARGET_FPS.to_vec()))
+                } else {
                     error!("Find target game {pkg} in config, but meet illegal data type");
                     error!("Sugg: try \'{pkg} = \"auto\"\'");
                     None
                 }
-            },
-        )
+            }
+            _ => {
+                error!("Find target game {pkg} in config, but meet illegal data type");
+                error!("Sugg: try \'{pkg} = \"auto\"\'");
+                None
+            }
+        }
+    }
+
+    /// Target fps may be fractional, such as 29.97 for emulators
+    fn fps_value(value: &Value) -> Option<f64> {
+        let fps = match value {
+            Value::Integer(i) => *i as f64,
+            Value::Float(f) => *f,
+            _ => return None,
+        };
+
+        (fps.is_finite() && fps > 0.0).then_some(fps)
+    }
+
+    pub fn add_auto_game(&self, pkg: String) {
+        self.auto_games.write().insert(pkg);
+    }
+
+    pub fn blacklisted(&self, pkg: &str) -> bool {
+        self.toml.read().blacklisted(pkg)
+    }
+
+    /// Whether `uid` may change the mode or config at runtime: root, shell or `[control] uids`
+    pub fn control_allowed(&self, uid: u32) -> bool {
+        uid == 0 || uid == SHELL_UID || self.toml.read().control.uids.contains(&uid)
+    }
+
+    pub fn sensor_priority<S: AsRef<str>>(&self, pkg: S) -> Vec<SensorKind> {
+        let toml = self.toml.read();
+        let pkg = pkg.as_ref();
+
+        if let Some(priority) = toml.sensor.game.get(pkg) {
+            return priority.clone();
+        }
+
+        let mut priority = toml.sensor.priority.clone();
+        let emulator = toml.app_type.get(pkg) == Some(&AppType::Emulator);
+        drop(toml);
+
+        // Emulators present at the panel rate and repeat guest frames, their GPU fences
+        // follow the guest renderer instead
+        if emulator {
+            priority.retain(|kind| *kind != SensorKind::Fence);
+            priority.insert(0, SensorKind::Fence);
+        }
+
+        priority
+    }
+
+    pub fn battery_rule(&self, capacity: u32) -> Option<BatteryRule> {
+        self.toml
+            .read()
+            .battery
+            .rules
+            .iter()
+            .filter(|rule| capacity < rule.below)
+            .min_by_key(|rule| rule.below)
+            .copied()
+    }
+
+    pub fn power_budget(&self, pkg: &str) -> Option<u32> {
+        self.toml.read().power_budget.get(pkg).copied()
+    }
+
+    pub fn refresh_rate(&self, pkg: &str) -> Option<u32> {
+        self.toml.read().refresh_rate.get(pkg).copied()
+    }
+
+    pub fn isolate_core(&self, pkg: &str) -> bool {
+        self.toml
+            .read()
+            .isolate_core
+            .get(pkg)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn skips_fas(&self, pkg: &str) -> bool {
+        self.toml.read().skips_fas(pkg)
+    }
+
+    pub fn freq_control(&self, pkg: &str) -> FreqControl {
+        let toml = self.toml.read();
+        toml.freq_control
+            .get(pkg)
+            .copied()
+            .unwrap_or(toml.config.freq_control)
+    }
+
+    /// Static (min, max) by policy if the game runs at fixed frequencies
+    pub fn fixed_freq(&self, pkg: &str) -> Option<HashMap<i32, (isize, isize)>> {
+        self.toml.read().fixed_freq.get(pkg).map(|limits| {
+            limits
+                .iter()
+                .filter_map(|(key, [min, max])| {
+                    let policy = key.strip_prefix("policy")?.parse().ok()?;
+                    Some((policy, (*min, *max)))
+                })
+                .collect()
+        })
+    }
+
+    pub fn extensions(&self) -> HashMap<String, Table> {
+        self.toml.read().extensions.clone()
+    }
+
+    pub fn thermal(&self) -> ThermalConfig {
+        self.toml.read().thermal.clone()
+    }
+
+    pub fn irq(&self) -> IrqConfig {
+        self.toml.read().irq.clone()
+    }
+
+    /// Session milestones in minutes, ascending
+    pub fn session_milestones(&self) -> Vec<u64> {
+        let mut milestones = self.toml.read().session.milestones.clone();
+        milestones.sort_unstable();
+        milestones.dedup();
+        milestones
+    }
+
+    pub fn charging_mode(&self, temp: Option<u32>) -> Option<Mode> {
+        let toml = self.toml.read();
+        let max_temp = toml.battery.charging_max_temp;
+
+        match (max_temp, temp) {
+            (Some(max_temp), Some(temp)) if temp >= max_temp => None,
+            _ => toml.battery.charging_mode,
+        }
     }
 
     #[must_use]
@@ -0,0 +1,57 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use log::error;
+use regex::Regex;
+
+use super::data::ConfigData;
+
+#[derive(Debug, Clone)]
+pub struct PackagePattern(Regex);
+
+impl PackagePattern {
+    pub fn parse(key: &str) -> Option<Self> {
+        let pattern =
+            if let Some(regex) = key.strip_prefix('/').and_then(|key| key.strip_suffix('/')) {
+                regex.to_string()
+            } else if key.contains(['*', '?']) {
+                let glob = regex::escape(key).replace("\\*", ".*").replace("\\?", ".");
+                format!("^{glob}$")
+            } else {
+                return None;
+            };
+
+        match Regex::new(&pattern) {
+            Ok(regex) => Some(Self(regex)),
+            Err(e) => {
+                error!("Illegal package pattern {key} in game list, reason: {e}");
+                None
+            }
+        }
+    }
+
+    pub fn is_match(&self, pkg: &str) -> bool {
+        self.0.is_match(pkg)
+    }
+}
+
+impl ConfigData {
+    pub fn load_patterns(&mut self) {
+        self.game_patterns = self
+            .game_list
+            .iter()
+            .filter_map(|(key, value)| Some((PackagePattern::parse(key)?, value.clone())))
+            .collect();
+    }
+}
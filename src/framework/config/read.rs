@@ -12,26 +12,92 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::{fs, path::Path, sync::Arc, thread, time::Duration};
+use std::{
+    collections::HashSet,
+    fmt::{self, Display, Formatter},
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+    thread,
+    time::Duration,
+};
 
 use inotify::{Inotify, WatchMask};
-use log::{debug, error};
+use log::{debug, error, warn};
 use parking_lot::RwLock;
 
-use super::data::{ConfigData, SceneAppList};
+use super::{
+    data::ConfigData,
+    format::Format,
+    import::{importers, GameListImporter},
+};
 use crate::framework::error::Result;
 
-const SCENE_PROFILE: &str = "/data/data/com.omarea.vtools/shared_prefs/games.xml";
+#[derive(Debug, Clone)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub key: Option<String>,
+    pub message: String,
+}
+
+impl ParseError {
+    pub fn new(e: &toml::de::Error, source: &str) -> Self {
+        let (line, column) = e.span().map_or((0, 0), |span| {
+            let before = &source[..span.start.min(source.len())];
+            let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+            (
+                before.matches('\n').count() + 1,
+                before[line_start..].chars().count() + 1,
+            )
+        });
+
+        Self::at(source, line, column, e.message())
+    }
+
+    pub fn at(source: &str, line: usize, column: usize, message: &str) -> Self {
+        let key = line
+            .checked_sub(1)
+            .and_then(|line| source.lines().nth(line))
+            .and_then(|line| line.find(['=', ':']).map(|i| &line[..i]))
+            .map(|key| key.trim().trim_matches(['"', '\'']).to_string())
+            .filter(|key| !key.is_empty());
+
+        Self {
+            line,
+            column,
+            key,
+            message: message.trim().replace('\n', " "),
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)?;
+        if let Some(key) = &self.key {
+            write!(f, ", key '{key}'")?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
 
 pub(super) fn wait_and_read(
     path: &Path,
     std_path: &Path,
     toml: &Arc<RwLock<ConfigData>>,
+    parse_error: &Arc<RwLock<Option<ParseError>>>,
 ) -> Result<()> {
     let mut retry_count = 0;
+    let mut rolled_back = false;
+    let format = Format::from_path(path);
+
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
 
     let std_config = fs::read_to_string(std_path)?;
-    let std_config: ConfigData = toml::from_str(&std_config)?;
+    let mut std_config: ConfigData = toml::from_str(&std_config)?;
+    std_config.load_patterns();
+    std_config.load_blacklist();
 
     loop {
         check_counter_final(&mut retry_count, toml, &std_config);
@@ -49,21 +115,36 @@ pub(super) fn wait_and_read(
             }
         };
 
-        *toml.write() = match toml::from_str(&ori) {
+        *toml.write() = match format.parse(&ori) {
             Ok(o) => {
                 retry_count = 0;
+                if rolled_back {
+                    rolled_back = false;
+                } else {
+                    *parse_error.write() = None;
+                }
                 o
             }
-            Err(e) => {
+            Err(diagnostic) => {
+                if retry_count == 0 {
+                    warn!("Failed to parse config {path:?} at {diagnostic}");
+                }
+                *parse_error.write() = Some(diagnostic.clone());
+
                 if retry_count > 3 {
-                    error!("Failed to parse config {path:?}, reason: {e}");
+                    error!("Config {path:?} rejected at {diagnostic}");
+                    let rejected = path.with_extension(format!("{}.rejected", format.extension()));
+                    if fs::write(&rejected, &ori).is_ok() {
+                        error!("The rejected config is saved to {rejected:?}");
+                    }
                     error!(
                         "Trying to roll back to the last configuration that could be resolved..."
                     );
-                    let latest = toml::to_string(&*toml.read())?;
+                    let latest = format.serialize(&toml.read().without_includes())?;
                     if fs::write(path, latest).is_ok() {
                         error!("Rollback successful");
                         retry_count = 0;
+                        rolled_back = true;
                     }
                 }
 
@@ -73,13 +154,16 @@ pub(super) fn wait_and_read(
             }
         };
 
-        if toml.read().config.scene_game_list {
-            let _ = read_scene_games(toml);
-        } else {
-            toml.write().scene_game_list.clear();
-        }
+        toml.write().load_includes(dir);
+        toml.write().load_patterns();
+        toml.write().load_blacklist();
 
-        wait_until_update(path)?;
+        let importers = importers(&toml.read().config);
+        toml.write().imported_games = read_imported_games(&importers);
+
+        let mut watches = toml.read().include_paths(dir);
+        watches.extend(importers.iter().flat_map(|importer| importer.paths()));
+        wait_until_update(path, &watches)?;
     }
 }
 
@@ -97,31 +181,33 @@ fn check_counter_final(
     }
 }
 
-fn read_scene_games(toml: &Arc<RwLock<ConfigData>>) -> Result<()> {
-    if Path::new(SCENE_PROFILE).exists() {
-        let scene_apps = fs::read_to_string(SCENE_PROFILE)?;
-        let scene_apps: SceneAppList = quick_xml::de::from_str(&scene_apps)?;
-        let game_list = scene_apps
-            .apps
-            .into_iter()
-            .filter(|app| app.is_game)
-            .map(|game| game.pkg)
-            .collect();
-
-        toml.write().scene_game_list = game_list;
+fn read_imported_games(importers: &[Box<dyn GameListImporter>]) -> HashSet<String> {
+    let mut games = HashSet::new();
+
+    for importer in importers {
+        match importer.import() {
+            Ok(imported) => {
+                debug!("Imported {} games from {}", imported.len(), importer.name());
+                games.extend(imported);
+            }
+            Err(e) => warn!(
+                "Failed to import games from {}, reason: {e}",
+                importer.name()
+            ),
+        }
     }
 
-    Ok(())
+    games
 }
 
-fn wait_until_update<P: AsRef<Path>>(path: P) -> Result<()> {
+fn wait_until_update<P: AsRef<Path>>(path: P, watches: &[PathBuf]) -> Result<()> {
     let path = path.as_ref();
     let mut inotify = Inotify::init()?;
 
-    if Path::new(SCENE_PROFILE).exists() {
+    for watch in watches.iter().filter(|watch| watch.exists()) {
         let _ = inotify
             .watches()
-            .add(SCENE_PROFILE, WatchMask::CLOSE_WRITE | WatchMask::MODIFY);
+            .add(watch, WatchMask::CLOSE_WRITE | WatchMask::MODIFY);
     }
 
     if inotify
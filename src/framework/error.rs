@@ -12,12 +12,17 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::io;
+use std::{
+    io::{self, ErrorKind},
+    path::PathBuf,
+};
 
 #[cfg(feature = "use_ebpf")]
 use frame_analyzer::AnalyzerError;
 use thiserror::Error as ThisError;
 
+use super::config::ParseError;
+
 pub type Result<T> = std::result::Result<T, Error>;
 
 #[derive(ThisError, Debug)]
@@ -37,6 +42,23 @@ pub enum Error {
     DeToml(#[from] toml::de::Error),
     #[error(transparent)]
     SerXml(#[from] quick_xml::DeError),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    Yaml(#[from] serde_yaml::Error),
+    #[error("Failed to parse config at {0}")]
+    Config(ParseError),
+    #[error("cpufreq policy{policy}: {source}")]
+    Cpufreq {
+        policy: i32,
+        #[source]
+        source: io::Error,
+    },
+    #[error("No frequency table found in {}", .0.display())]
+    NoFreqTable(PathBuf),
+    #[error("Sensor error: {0}")]
+    #[cfg_attr(not(feature = "use_binder"), allow(dead_code))]
+    Sensor(&'static str),
     #[error("Missing {0} when building Scheduler")]
     SchedulerMissing(&'static str),
     #[error(transparent)]
@@ -50,3 +72,36 @@ pub enum Error {
     #[allow(dead_code)]
     Other(&'static str),
 }
+
+/// How the caller of a failed operation should react
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Recovery {
+    /// Transient, the same operation may succeed next time
+    Retry,
+    /// What was operated on changed (hotplug, a node went away), enumerate it again
+    Rescan,
+    /// Won't get better by itself, stop using the feature
+    Disable,
+}
+
+impl Error {
+    pub fn recovery(&self) -> Recovery {
+        match self {
+            Self::Io(e) | Self::Cpufreq { source: e, .. } => match e.kind() {
+                ErrorKind::Interrupted | ErrorKind::WouldBlock | ErrorKind::TimedOut => {
+                    Recovery::Retry
+                }
+                ErrorKind::NotFound => Recovery::Rescan,
+                _ => match e.raw_os_error() {
+                    Some(libc::EBUSY) => Recovery::Retry,
+                    Some(libc::ENODEV | libc::ENXIO) => Recovery::Rescan,
+                    _ => Recovery::Disable,
+                },
+            },
+            #[cfg(feature = "use_ebpf")]
+            Self::FrameAnalyzer(_) => Recovery::Retry,
+            Self::NoFreqTable(_) | Self::NodeNotFound => Recovery::Rescan,
+            _ => Recovery::Disable,
+        }
+    }
+}
@@ -12,15 +12,38 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::path::Path;
+use std::{cell::Cell, path::Path, time::Duration};
 
 use log::error;
 use mlua::{Function, IntoLuaMulti, Lua};
 
-pub fn get_api_version(lua: &Lua) -> u8 {
+thread_local! {
+    static FAILED: Cell<Option<String>> = const { Cell::new(None) };
+}
+
+/// Error of the last callback that failed on this thread since the last call
+pub fn take_failed() -> Option<String> {
+    FAILED.with(Cell::take)
+}
+
+fn get_api_version(lua: &Lua) -> u8 {
     lua.globals().get("API_VERSION").unwrap_or(0)
 }
 
+/// Api versions an extension implements, declared as `API_VERSIONS = { 1, 2 }`,
+/// or the single `API_VERSION` of older extensions
+pub fn get_api_versions(lua: &Lua) -> Vec<u8> {
+    lua.globals()
+        .get::<_, Vec<u8>>("API_VERSIONS")
+        .unwrap_or_else(|_| vec![get_api_version(lua)])
+}
+
+/// How long frametimes are collected before `frametimes` is called, declared as
+/// `FRAME_BATCH_MS`. `0` calls it on every frame
+pub fn get_frame_batch(lua: &Lua) -> Duration {
+    Duration::from_millis(lua.globals().get("FRAME_BATCH_MS").unwrap_or(100))
+}
+
 pub fn do_callback<P: AsRef<Path>, S: AsRef<str>, A: for<'lua> IntoLuaMulti<'lua>>(
     extension: P,
     lua: &Lua,
@@ -32,6 +55,7 @@ pub fn do_callback<P: AsRef<Path>, S: AsRef<str>, A: for<'lua> IntoLuaMulti<'lua
 
     if let Ok(func) = lua.globals().get::<_, Function>(function) {
         func.call(args).unwrap_or_else(|e| {
+            FAILED.with(|failed| failed.set(Some(e.to_string())));
             error!("Got an error when executing extension '{extension:?}', reason: {e:#?}");
         });
     }
@@ -13,11 +13,17 @@
 // limitations under the License.
 
 pub mod misc;
+pub mod settings;
+pub mod telemetry;
 pub mod v0;
 pub mod v1;
 pub mod v2;
 
-use std::sync::atomic::Ordering;
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+    sync::{atomic::Ordering, Mutex},
+};
 
 use crate::cpu_common::OFFSET_MAP;
 
@@ -27,6 +33,10 @@ pub use v0::ApiV0;
 pub trait Api: Send {
     fn handle_api(&self, ext: &ExtensionMap);
 
+    fn version(&self) -> u8;
+
+    fn clone_box(&self) -> Box<dyn Api>;
+
     fn into_box(self) -> Box<dyn Api>
     where
         Self: Sized + 'static,
@@ -35,12 +45,51 @@ pub trait Api: Send {
     }
 }
 
-pub fn set_policy_freq_offset(policy: i32, offset: isize) -> mlua::Result<()> {
-    OFFSET_MAP
+/// Offsets requested by each extension, the offset applied to a policy is their sum
+static POLICY_OFFSETS: Mutex<BTreeMap<PathBuf, HashMap<i32, isize>>> = Mutex::new(BTreeMap::new());
+
+/// Nudge `policy` by `offset` kHz on behalf of `extension`, replacing its previous offset for that policy
+// The sum is stored under the lock, so concurrent requests can't leave a stale total behind
+#[allow(clippy::significant_drop_tightening)]
+pub fn set_policy_offset(extension: &Path, policy: i32, offset: isize) -> mlua::Result<()> {
+    let total = OFFSET_MAP
         .get()
         .unwrap()
+        .read()
+        .unwrap()
         .get(&policy)
-        .ok_or_else(|| mlua::Error::runtime("Policy Not Found!"))?
-        .store(offset, Ordering::Release);
+        .cloned()
+        .ok_or_else(|| mlua::Error::runtime("Policy Not Found!"))?;
+
+    let mut requests = POLICY_OFFSETS.lock().unwrap();
+    requests
+        .entry(extension.to_path_buf())
+        .or_default()
+        .insert(policy, offset);
+
+    total.store(sum_offsets(&requests, policy), Ordering::Release);
     Ok(())
 }
+
+/// Drop every offset set by `extension`, called when it is unloaded or reloaded
+#[allow(clippy::significant_drop_tightening)]
+pub fn release_policy_offsets(extension: &Path) {
+    let mut requests = POLICY_OFFSETS.lock().unwrap();
+    let Some(released) = requests.remove(extension) else {
+        return;
+    };
+
+    let offsets = OFFSET_MAP.get().unwrap().read().unwrap();
+    for policy in released.keys() {
+        if let Some(total) = offsets.get(policy) {
+            total.store(sum_offsets(&requests, *policy), Ordering::Release);
+        }
+    }
+}
+
+fn sum_offsets(requests: &BTreeMap<PathBuf, HashMap<i32, isize>>, policy: i32) -> isize {
+    requests
+        .values()
+        .filter_map(|offsets| offsets.get(&policy))
+        .sum()
+}
@@ -0,0 +1,112 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{collections::HashMap, sync::RwLock};
+
+use log::error;
+use mlua::{Lua, Value as LuaValue};
+use toml::{Table, Value};
+
+static SETTINGS: RwLock<Option<HashMap<String, Table>>> = RwLock::new(None);
+
+/// Replace the `[extensions.<name>]` tables handed to extensions
+pub fn publish(settings: HashMap<String, Table>) {
+    *SETTINGS.write().unwrap() = Some(settings);
+}
+
+pub fn get(extension: &str, key: &str) -> Option<Value> {
+    SETTINGS
+        .read()
+        .unwrap()
+        .as_ref()?
+        .get(extension)?
+        .get(key)
+        .cloned()
+}
+
+/// Whether `extension` takes callbacks while `pkg` is active, limited by the reserved
+/// `packages` / `exclude_packages` keys of its table. Everything is delivered when no package is active
+pub fn enabled_for(extension: &str, pkg: Option<&str>) -> bool {
+    let Some(pkg) = pkg else {
+        return true;
+    };
+
+    let listed = |key: &str| {
+        get(extension, key)
+            .as_ref()
+            .and_then(Value::as_array)
+            .map(|pkgs| pkgs.iter().any(|p| p.as_str() == Some(pkg)))
+    };
+
+    listed("packages").unwrap_or(true) && !listed("exclude_packages").unwrap_or(false)
+}
+
+/// Value of `key` for `extension`, falling back to the default declared in its `CONFIG` table
+/// when the key is missing or has a different type than the default
+pub fn get_config<'lua>(
+    lua: &'lua Lua,
+    extension: &str,
+    key: &str,
+) -> mlua::Result<LuaValue<'lua>> {
+    let default = lua
+        .globals()
+        .get::<_, mlua::Table>("CONFIG")
+        .and_then(|config| config.get(key))
+        .unwrap_or(LuaValue::Nil);
+
+    let Some(value) = get(extension, key) else {
+        return Ok(default);
+    };
+
+    let value = to_lua(lua, value)?;
+    if default.is_nil() || same_type(&default, &value) {
+        Ok(value)
+    } else {
+        error!(
+            "extension: [extensions.{extension}] '{key}' should be {}, got {}",
+            default.type_name(),
+            value.type_name()
+        );
+        Ok(default)
+    }
+}
+
+fn same_type(a: &LuaValue, b: &LuaValue) -> bool {
+    let number = |v: &LuaValue| matches!(v, LuaValue::Integer(_) | LuaValue::Number(_));
+    (number(a) && number(b)) || a.type_name() == b.type_name()
+}
+
+fn to_lua(lua: &Lua, value: Value) -> mlua::Result<LuaValue<'_>> {
+    Ok(match value {
+        Value::String(s) => LuaValue::String(lua.create_string(s)?),
+        Value::Integer(i) => LuaValue::Integer(i as mlua::Integer),
+        Value::Float(f) => LuaValue::Number(f),
+        Value::Boolean(b) => LuaValue::Boolean(b),
+        Value::Datetime(d) => LuaValue::String(lua.create_string(d.to_string())?),
+        Value::Array(array) => {
+            let table = lua.create_table()?;
+            for value in array {
+                table.push(to_lua(lua, value)?)?;
+            }
+            LuaValue::Table(table)
+        }
+        Value::Table(map) => {
+            let table = lua.create_table()?;
+            for (key, value) in map {
+                table.set(key, to_lua(lua, value)?)?;
+            }
+            LuaValue::Table(table)
+        }
+    })
+}
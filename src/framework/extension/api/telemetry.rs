@@ -0,0 +1,53 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::RwLock;
+
+static TELEMETRY: RwLock<Telemetry> = RwLock::new(Telemetry::new());
+
+/// Snapshot of what the scheduler sees, published with the status node and read by extensions
+#[derive(Debug, Clone)]
+pub struct Telemetry {
+    pub current_fps: Option<f64>,
+    pub target_fps: Option<f64>,
+    pub temp: Option<u32>,
+    pub battery: Option<u32>,
+    pub freqs: Vec<(i32, isize)>,
+    /// Policies held down by a kernel thermal limit, as (policy, limit)
+    pub thermal_clamps: Vec<(i32, isize)>,
+    /// GPU busy percentage
+    pub gpu_busy: Option<u32>,
+}
+
+impl Telemetry {
+    pub const fn new() -> Self {
+        Self {
+            current_fps: None,
+            target_fps: None,
+            temp: None,
+            battery: None,
+            freqs: Vec::new(),
+            thermal_clamps: Vec::new(),
+            gpu_busy: None,
+        }
+    }
+}
+
+pub fn publish(telemetry: Telemetry) {
+    *TELEMETRY.write().unwrap() = telemetry;
+}
+
+pub fn snapshot() -> Telemetry {
+    TELEMETRY.read().unwrap().clone()
+}
@@ -16,7 +16,7 @@ use libc::pid_t;
 
 use super::{
     super::core::ExtensionMap,
-    misc::{do_callback, get_api_version},
+    misc::{do_callback, get_api_versions},
     Api,
 };
 
@@ -32,7 +32,10 @@ pub enum ApiV1 {
 
 impl Api for ApiV1 {
     fn handle_api(&self, ext: &ExtensionMap) {
-        for (extension, lua) in ext.iter().filter(|(_, lua)| get_api_version(lua) == 1) {
+        for (extension, lua) in ext
+            .iter()
+            .filter(|(_, lua)| get_api_versions(lua).contains(&1))
+        {
             match self.clone() {
                 Self::LoadFas(pid, pkg) => {
                     do_callback(extension, lua, "load_fas", (pid, pkg));
@@ -55,4 +58,12 @@ impl Api for ApiV1 {
             }
         }
     }
+
+    fn version(&self) -> u8 {
+        1
+    }
+
+    fn clone_box(&self) -> Box<dyn Api> {
+        Box::new(self.clone())
+    }
 }
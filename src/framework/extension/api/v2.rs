@@ -16,7 +16,7 @@ use libc::pid_t;
 
 use super::{
     super::core::ExtensionMap,
-    misc::{do_callback, get_api_version},
+    misc::{do_callback, get_api_versions},
     Api,
 };
 
@@ -28,12 +28,18 @@ pub enum ApiV2 {
     StopFas,
     InitCpuFreq,
     ResetCpuFreq,
-    TargetFpsChange(u32, String),
+    TargetFpsChange(f64, String),
+    Frametimes(Vec<f64>, String),
+    SessionMilestone(u64, String),
+    FpsOvershoot(f64, f64, String),
 }
 
 impl Api for ApiV2 {
     fn handle_api(&self, ext: &ExtensionMap) {
-        for (extension, lua) in ext.iter().filter(|(_, lua)| get_api_version(lua) == 2) {
+        for (extension, lua) in ext
+            .iter()
+            .filter(|(_, lua)| get_api_versions(lua).contains(&2))
+        {
             match self.clone() {
                 Self::LoadFas(pid, pkg) => {
                     do_callback(extension, lua, "load_fas", (pid, pkg));
@@ -56,7 +62,29 @@ impl Api for ApiV2 {
                 Self::TargetFpsChange(target_fps, pkg) => {
                     do_callback(extension, lua, "target_fps_change", (target_fps, pkg));
                 }
+                Self::Frametimes(frametimes, pkg) => {
+                    do_callback(extension, lua, "frametimes", (frametimes, pkg));
+                }
+                Self::SessionMilestone(minutes, pkg) => {
+                    do_callback(extension, lua, "session_milestone", (minutes, pkg));
+                }
+                Self::FpsOvershoot(current_fps, target_fps, pkg) => {
+                    do_callback(
+                        extension,
+                        lua,
+                        "fps_overshoot",
+                        (current_fps, target_fps, pkg),
+                    );
+                }
             }
         }
     }
+
+    fn version(&self) -> u8 {
+        2
+    }
+
+    fn clone_box(&self) -> Box<dyn Api> {
+        Box::new(self.clone())
+    }
 }
@@ -12,22 +12,146 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::{collections::HashMap, fs, path::PathBuf, sync::mpsc::Receiver, time::Duration};
+use std::{
+    cell::Cell,
+    collections::{BTreeMap, HashMap},
+    fs, mem,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::{
+        mpsc::{self, Receiver, SyncSender},
+        Arc, Mutex, OnceLock,
+    },
+    thread,
+    time::{Duration, Instant},
+};
 
 use inotify::{Inotify, WatchMask};
-use log::{debug, error, info};
-use mlua::Lua;
+use log::{debug, error, info, warn};
+use mlua::{HookTriggers, Lua};
 
 use super::{
-    api::{self, Api},
-    EXTENSIONS_PATH,
+    api::{self, misc, settings, telemetry, v2::ApiV2, Api},
+    Message, EXTENSIONS_PATH,
 };
 use crate::framework::error::Result;
 
+/// Time a single callback may run before it is interrupted
+const CALL_BUDGET: Duration = Duration::from_millis(50);
+/// Consecutive failed triggers before an extension is disabled until it is reloaded
+const MAX_FAILURES: u32 = 5;
+
 pub type ExtensionMap = HashMap<PathBuf, Lua>;
 
-pub fn thread(rx: &Receiver<Box<dyn Api>>) {
-    let mut extensions = load_extensions().unwrap_or_default();
+type Workers = HashMap<PathBuf, Worker>;
+
+static STATUS: Mutex<BTreeMap<PathBuf, Status>> = Mutex::new(BTreeMap::new());
+
+/// Where an extension is in its lifecycle, kept for state dumps
+#[derive(Debug, Clone, Default)]
+pub struct Status {
+    pub state: String,
+    pub last_error: Option<String>,
+}
+
+pub fn status() -> Vec<(PathBuf, Status)> {
+    STATUS
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(path, status)| (path.clone(), status.clone()))
+        .collect()
+}
+
+fn set_state(path: &Path, state: impl Into<String>) {
+    STATUS
+        .lock()
+        .unwrap()
+        .entry(path.to_path_buf())
+        .or_default()
+        .state = state.into();
+}
+
+fn set_error(path: &Path, error: String) {
+    STATUS
+        .lock()
+        .unwrap()
+        .entry(path.to_path_buf())
+        .or_default()
+        .last_error = Some(error);
+}
+
+/// What an extension declared when it was loaded
+struct Capabilities {
+    versions: Vec<u8>,
+    frame_batch: Duration,
+}
+
+struct Worker {
+    sx: SyncSender<Box<dyn Api>>,
+    /// Filled by the worker once the extension is loaded
+    caps: Arc<OnceLock<Capabilities>>,
+    frames: Vec<f64>,
+    frames_pkg: String,
+    batch_timer: Instant,
+}
+
+impl Worker {
+    fn wants(&self, trigger: &dyn Api) -> bool {
+        self.caps
+            .get()
+            .is_none_or(|caps| caps.versions.contains(&trigger.version()))
+    }
+
+    fn send(&self, trigger: Box<dyn Api>) {
+        // A busy extension only drops its own triggers
+        let _ = self.sx.try_send(trigger);
+    }
+
+    fn push_frame(&mut self, frametime: Duration, pkg: String) {
+        let Some(caps) = self.caps.get() else {
+            return;
+        };
+
+        if !caps.versions.contains(&2) {
+            return;
+        }
+
+        if self.frames_pkg != pkg {
+            self.flush_frames();
+            self.frames_pkg = pkg;
+        }
+
+        if self.frames.is_empty() {
+            self.batch_timer = Instant::now();
+        }
+
+        self.frames.push(frametime.as_secs_f64() * 1000.0);
+        self.flush_due_frames();
+    }
+
+    fn flush_due_frames(&mut self) {
+        if self
+            .caps
+            .get()
+            .is_some_and(|caps| self.batch_timer.elapsed() >= caps.frame_batch)
+        {
+            self.flush_frames();
+        }
+    }
+
+    fn flush_frames(&mut self) {
+        if self.frames.is_empty() {
+            return;
+        }
+
+        let frames = mem::take(&mut self.frames);
+        self.send(ApiV2::Frametimes(frames, self.frames_pkg.clone()).into_box());
+    }
+}
+
+pub fn thread(rx: &Receiver<Message>) {
+    let mut workers = spawn_workers().unwrap_or_default();
     let mut inotify = Inotify::init().unwrap();
 
     inotify
@@ -40,11 +164,35 @@ pub fn thread(rx: &Receiver<Box<dyn Api>>) {
 
     loop {
         if need_update(&mut inotify) {
-            extensions = load_extensions().unwrap_or_default();
+            for path in workers.keys() {
+                api::release_policy_offsets(path);
+            }
+
+            workers = spawn_workers().unwrap_or_default();
         }
 
-        if let Ok(trigger) = rx.recv_timeout(Duration::from_secs(1)) {
-            trigger.handle_api(&extensions);
+        match rx.recv_timeout(Duration::from_secs(1)) {
+            Ok(Message::Trigger(trigger, pkg)) => {
+                for (path, worker) in &workers {
+                    if worker.wants(trigger.as_ref())
+                        && settings::enabled_for(&extension_name(path), pkg.as_deref())
+                    {
+                        worker.send(trigger.clone_box());
+                    }
+                }
+            }
+            Ok(Message::Frame(frametime, pkg)) => {
+                for (path, worker) in &mut workers {
+                    if settings::enabled_for(&extension_name(path), Some(&pkg)) {
+                        worker.push_frame(frametime, pkg.clone());
+                    }
+                }
+            }
+            Err(_) => {
+                for worker in workers.values_mut() {
+                    worker.flush_due_frames();
+                }
+            }
         }
     }
 }
@@ -53,60 +201,200 @@ fn need_update(inotify: &mut Inotify) -> bool {
     inotify.read_events(&mut [0; 1024]).is_ok()
 }
 
-fn load_extensions() -> Result<ExtensionMap> {
-    let mut map: ExtensionMap = HashMap::new();
+/// Every extension gets its own thread and lua state, replacing `workers` drops the
+/// old senders and lets their threads exit
+fn spawn_workers() -> Result<Workers> {
+    let mut workers = HashMap::new();
+    STATUS.lock().unwrap().clear();
 
     for file in fs::read_dir(EXTENSIONS_PATH)?
         .map(std::result::Result::unwrap)
         .filter(|f| f.file_type().unwrap().is_file() && f.path().extension().unwrap() == "lua")
     {
-        let lua = Lua::new();
         let path = file.path();
-        let file = fs::read_to_string(&path)?;
+        let (sx, rx) = mpsc::sync_channel(16);
+        let caps = Arc::new(OnceLock::new());
+        let worker_path = path.clone();
+        let worker_caps = caps.clone();
+        set_state(&path, "loading");
 
-        lua.globals().set(
-            "log_info",
-            lua.create_function(|_, message: String| {
-                info!("extension: {message}");
-                Ok(())
-            })?,
-        )?;
+        thread::Builder::new()
+            .name("ExtensionWorker".into())
+            .spawn(move || worker(&worker_path, &worker_caps, &rx))?;
 
-        lua.globals().set(
-            "log_debug",
-            lua.create_function(|_, message: String| {
-                debug!("extension: {message}");
-                Ok(())
-            })?,
-        )?;
+        workers.insert(
+            path,
+            Worker {
+                sx,
+                caps,
+                frames: Vec::new(),
+                frames_pkg: String::new(),
+                batch_timer: Instant::now(),
+            },
+        );
+    }
 
-        lua.globals().set(
-            "log_error",
-            lua.create_function(|_, message: String| {
-                error!("extension: {message}");
-                Ok(())
-            })?,
-        )?;
-
-        // Add in api v1
-        lua.globals().set(
-            "set_policy_freq_offset",
-            lua.create_function(|_, (policy, offset): (i32, isize)| {
-                api::set_policy_freq_offset(policy, offset)?;
-                Ok(())
-            })?,
-        )?;
+    Ok(workers)
+}
 
-        match lua.load(&file).exec() {
-            Ok(()) => {
-                info!("Extension loaded successfully: {path:?}");
-                map.insert(path, lua);
+fn worker(path: &Path, caps: &OnceLock<Capabilities>, rx: &Receiver<Box<dyn Api>>) {
+    let lua = match load_extension(path) {
+        Ok(lua) => {
+            let api_versions = misc::get_api_versions(&lua);
+            info!("Extension loaded successfully: {path:?}, api versions: {api_versions:?}");
+            if api_versions == [0] {
+                warn!("Extension {path:?} only implements the deprecated api v0");
             }
-            Err(e) => {
-                error!("Extension loading failed, reason: {e:#?}");
+            set_state(path, format!("loaded, api versions {api_versions:?}"));
+            let _ = caps.set(Capabilities {
+                versions: api_versions,
+                frame_batch: misc::get_frame_batch(&lua),
+            });
+            lua
+        }
+        Err(e) => {
+            error!("Extension loading failed, reason: {e:#?}");
+            set_state(path, "load failed");
+            set_error(path, e.to_string());
+            return;
+        }
+    };
+
+    let deadline = Rc::new(Cell::new(None::<Instant>));
+    let hook_deadline = deadline.clone();
+    lua.set_hook(
+        HookTriggers::new().every_nth_instruction(1000),
+        move |_, _| {
+            if hook_deadline.get().is_some_and(|d| Instant::now() > d) {
+                Err(mlua::Error::runtime("time budget exceeded"))
+            } else {
+                Ok(())
             }
+        },
+    );
+
+    let map: ExtensionMap = HashMap::from([(path.to_path_buf(), lua)]);
+    let mut failures = 0;
+
+    while let Ok(trigger) = rx.recv() {
+        deadline.set(Some(Instant::now() + CALL_BUDGET));
+        trigger.handle_api(&map);
+        deadline.set(None);
+
+        if let Some(e) = misc::take_failed() {
+            failures += 1;
+            set_error(path, e);
+        } else {
+            failures = 0;
+        }
+
+        if failures >= MAX_FAILURES {
+            error!(
+                "Extension {path:?} failed {MAX_FAILURES} times in a row, disabled until it is reloaded"
+            );
+            set_state(path, "disabled after repeated failures");
+            api::release_policy_offsets(path);
+            return;
         }
     }
+}
+
+fn load_extension(path: &Path) -> Result<Lua> {
+    let lua = Lua::new();
+    let file = fs::read_to_string(path)?;
+
+    lua.globals().set(
+        "log_info",
+        lua.create_function(|_, message: String| {
+            info!("extension: {message}");
+            Ok(())
+        })?,
+    )?;
+
+    lua.globals().set(
+        "log_debug",
+        lua.create_function(|_, message: String| {
+            debug!("extension: {message}");
+            Ok(())
+        })?,
+    )?;
+
+    lua.globals().set(
+        "log_error",
+        lua.create_function(|_, message: String| {
+            error!("extension: {message}");
+            Ok(())
+        })?,
+    )?;
+
+    // Add in api v1
+    let owner = path.to_path_buf();
+    lua.globals().set(
+        "set_policy_freq_offset",
+        lua.create_function(move |_, (policy, offset): (i32, isize)| {
+            api::set_policy_offset(&owner, policy, offset)?;
+            Ok(())
+        })?,
+    )?;
+
+    let name = extension_name(path);
+    lua.globals().set(
+        "get_config",
+        lua.create_function(move |lua, key: String| settings::get_config(lua, &name, &key))?,
+    )?;
+
+    lua.globals().set(
+        "get_current_fps",
+        lua.create_function(|_, ()| Ok(telemetry::snapshot().current_fps))?,
+    )?;
+
+    lua.globals().set(
+        "get_target_fps",
+        lua.create_function(|_, ()| Ok(telemetry::snapshot().target_fps))?,
+    )?;
+
+    lua.globals().set(
+        "get_temperature",
+        lua.create_function(|_, ()| Ok(telemetry::snapshot().temp))?,
+    )?;
+
+    lua.globals().set(
+        "get_battery_level",
+        lua.create_function(|_, ()| Ok(telemetry::snapshot().battery))?,
+    )?;
+
+    lua.globals().set(
+        "get_policy_freqs",
+        lua.create_function(|lua, ()| lua.create_table_from(telemetry::snapshot().freqs))?,
+    )?;
+
+    lua.globals().set(
+        "get_thermal_clamps",
+        lua.create_function(|lua, ()| lua.create_table_from(telemetry::snapshot().thermal_clamps))?,
+    )?;
+
+    lua.globals().set(
+        "get_gpu_busy",
+        lua.create_function(|_, ()| Ok(telemetry::snapshot().gpu_busy))?,
+    )?;
+
+    let owner = path.to_path_buf();
+    lua.globals().set(
+        "set_policy_offset",
+        lua.create_function(move |_, (policy, khz): (i32, isize)| {
+            api::set_policy_offset(&owner, policy, khz)?;
+            Ok(())
+        })?,
+    )?;
+
+    lua.load(&file).exec()?;
+
+    Ok(lua)
+}
 
-    Ok(map)
+fn extension_name(path: &Path) -> String {
+    path.file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or_default()
+        .to_string()
 }
@@ -16,18 +16,28 @@ pub mod api;
 mod core;
 
 use std::{
+    cell::RefCell,
     fs,
+    path::PathBuf,
     sync::mpsc::{self, SyncSender},
     thread,
+    time::Duration,
 };
 
 use crate::framework::error::Result;
 pub use api::Api;
+pub use core::Status;
 
 const EXTENSIONS_PATH: &str = "/dev/fas_rs/extensions";
 
+enum Message {
+    Trigger(Box<dyn Api>, Option<String>),
+    Frame(Duration, String),
+}
+
 pub struct Extension {
-    sx: SyncSender<Box<dyn Api>>,
+    sx: SyncSender<Message>,
+    pkg: RefCell<Option<String>>,
 }
 
 impl Extension {
@@ -39,10 +49,44 @@ impl Extension {
             .name("ExtensionThead".into())
             .spawn(move || core::thread(&rx))?;
 
-        Ok(Self { sx })
+        Ok(Self {
+            sx,
+            pkg: RefCell::new(None),
+        })
+    }
+
+    /// Drops every trigger without loading any extension, for running the policy off-device
+    pub fn noop() -> Self {
+        let (sx, _) = mpsc::sync_channel(0);
+
+        Self {
+            sx,
+            pkg: RefCell::new(None),
+        }
+    }
+
+    /// Package of the active buffer, extensions limited to other packages don't get its callbacks
+    pub fn set_package(&self, pkg: Option<&str>) {
+        let mut current = self.pkg.borrow_mut();
+        if current.as_deref() != pkg {
+            *current = pkg.map(ToString::to_string);
+        }
     }
 
     pub fn tigger_extentions(&self, trigger: impl Api + 'static) {
-        let _ = self.sx.try_send(trigger.into_box());
+        let _ = self.sx.try_send(Message::Trigger(
+            trigger.into_box(),
+            self.pkg.borrow().clone(),
+        ));
+    }
+
+    /// Every extension found on the last (re)load with its state and last error
+    pub fn status() -> Vec<(PathBuf, Status)> {
+        core::status()
+    }
+
+    /// Frames are batched per extension by the dispatcher before reaching lua
+    pub fn tigger_frame(&self, frametime: Duration, pkg: &str) {
+        let _ = self.sx.try_send(Message::Frame(frametime, pkg.to_string()));
     }
 }
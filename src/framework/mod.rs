@@ -19,14 +19,15 @@ mod node;
 pub mod prelude;
 mod scheduler;
 mod utils;
+pub mod webui;
 
 #[allow(unused_imports)]
-pub use config::Config;
+pub use config::{Config, Diagnostic, Format, FreqBackend, FreqControl, LogLevel, Severity};
 #[allow(unused_imports)]
-pub use error::Result;
+pub use error::{Error, Recovery, Result};
 #[allow(unused_imports)]
 pub use extension::{api, Api, Extension};
 #[allow(unused_imports)]
 pub use node::Mode;
 #[allow(unused_imports)]
-pub use scheduler::Scheduler;
+pub use scheduler::{detected_daemons, replay, Scheduler};
@@ -29,6 +29,8 @@ impl Node {
 
         let _ = result.remove_node("mode");
         result.create_node("mode", "balance")?;
+        let _ = result.remove_node("status");
+        result.create_node("status", "")?;
 
         Ok(result)
     }
@@ -51,6 +53,17 @@ impl Node {
         self.refresh()
     }
 
+    pub fn write_node<S: AsRef<str>>(&mut self, i: S, v: S) -> Result<()> {
+        let id = i.as_ref();
+        let value = v.as_ref();
+
+        let path = Path::new(NODE_PATH).join(id);
+        fs::write(path, value)?;
+        self.map.insert(id.to_string(), value.to_string());
+
+        Ok(())
+    }
+
     pub fn get_node<S: AsRef<str>>(&mut self, id: S) -> Result<String> {
         let id = id.as_ref();
 
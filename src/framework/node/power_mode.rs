@@ -3,10 +3,13 @@ use std::{
     str::FromStr,
 };
 
+use serde::{Deserialize, Serialize};
+
 use super::Node;
 use crate::framework::error::{Error, Result};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Mode {
     Powersave,
     Balance,
@@ -28,6 +31,18 @@ impl FromStr for Mode {
     }
 }
 
+impl Mode {
+    /// The next mode in the powersave -> fast cycle
+    pub const fn next(self) -> Self {
+        match self {
+            Self::Powersave => Self::Balance,
+            Self::Balance => Self::Performance,
+            Self::Performance => Self::Fast,
+            Self::Fast => Self::Powersave,
+        }
+    }
+}
+
 impl Display for Mode {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         let mode = match self {
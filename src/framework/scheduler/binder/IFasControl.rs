@@ -0,0 +1,515 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![forbid(unsafe_code)]
+#![allow(warnings, clippy::all, clippy::pedantic)]
+#![allow(non_upper_case_globals)]
+#![allow(non_snake_case)]
+#[allow(unused_imports)]
+use binder::binder_impl::IBinderInternal;
+use binder::declare_binder_interface;
+declare_binder_interface! {
+  IFasControl["IFasControl"] {
+    native: BnFasControl(on_transact),
+    proxy: BpFasControl {
+    },
+    async: IFasControlAsync,
+  }
+}
+pub trait IFasControl: binder::Interface + Send {
+    fn get_descriptor() -> &'static str
+    where
+        Self: Sized,
+    {
+        "IFasControl"
+    }
+    fn getStatus(&self) -> binder::Result<String>;
+    fn getMode(&self) -> binder::Result<String>;
+    fn setMode(&self, _arg_mode: &str) -> binder::Result<bool>;
+    fn getConfig(&self) -> binder::Result<String>;
+    fn setConfig(&self, _arg_config: &str) -> binder::Result<String>;
+    fn getDefaultImpl() -> IFasControlDefaultRef
+    where
+        Self: Sized,
+    {
+        DEFAULT_IMPL.lock().unwrap().clone()
+    }
+    fn setDefaultImpl(d: IFasControlDefaultRef) -> IFasControlDefaultRef
+    where
+        Self: Sized,
+    {
+        std::mem::replace(&mut *DEFAULT_IMPL.lock().unwrap(), d)
+    }
+}
+pub trait IFasControlAsync<P>: binder::Interface + Send {
+    fn get_descriptor() -> &'static str
+    where
+        Self: Sized,
+    {
+        "IFasControl"
+    }
+    fn getStatus<'a>(&'a self) -> binder::BoxFuture<'a, binder::Result<String>>;
+    fn getMode<'a>(&'a self) -> binder::BoxFuture<'a, binder::Result<String>>;
+    fn setMode<'a>(&'a self, _arg_mode: &'a str) -> binder::BoxFuture<'a, binder::Result<bool>>;
+    fn getConfig<'a>(&'a self) -> binder::BoxFuture<'a, binder::Result<String>>;
+    fn setConfig<'a>(
+        &'a self,
+        _arg_config: &'a str,
+    ) -> binder::BoxFuture<'a, binder::Result<String>>;
+}
+#[::async_trait::async_trait]
+pub trait IFasControlAsyncServer: binder::Interface + Send {
+    fn get_descriptor() -> &'static str
+    where
+        Self: Sized,
+    {
+        "IFasControl"
+    }
+    async fn getStatus(&self) -> binder::Result<String>;
+    async fn getMode(&self) -> binder::Result<String>;
+    async fn setMode(&self, _arg_mode: &str) -> binder::Result<bool>;
+    async fn getConfig(&self) -> binder::Result<String>;
+    async fn setConfig(&self, _arg_config: &str) -> binder::Result<String>;
+}
+impl BnFasControl {
+    /// Create a new async binder service.
+    pub fn new_async_binder<T, R>(
+        inner: T,
+        rt: R,
+        features: binder::BinderFeatures,
+    ) -> binder::Strong<dyn IFasControl>
+    where
+        T: IFasControlAsyncServer + binder::Interface + Send + Sync + 'static,
+        R: binder::binder_impl::BinderAsyncRuntime + Send + Sync + 'static,
+    {
+        struct Wrapper<T, R> {
+            _inner: T,
+            _rt: R,
+        }
+        impl<T, R> binder::Interface for Wrapper<T, R>
+        where
+            T: binder::Interface,
+            R: Send + Sync,
+        {
+            fn as_binder(&self) -> binder::SpIBinder {
+                self._inner.as_binder()
+            }
+            fn dump(
+                &self,
+                _file: &std::fs::File,
+                _args: &[&std::ffi::CStr],
+            ) -> std::result::Result<(), binder::StatusCode> {
+                self._inner.dump(_file, _args)
+            }
+        }
+        impl<T, R> IFasControl for Wrapper<T, R>
+        where
+            T: IFasControlAsyncServer + Send + Sync + 'static,
+            R: binder::binder_impl::BinderAsyncRuntime + Send + Sync + 'static,
+        {
+            fn getStatus(&self) -> binder::Result<String> {
+                self._rt.block_on(self._inner.getStatus())
+            }
+            fn getMode(&self) -> binder::Result<String> {
+                self._rt.block_on(self._inner.getMode())
+            }
+            fn setMode(&self, _arg_mode: &str) -> binder::Result<bool> {
+                self._rt.block_on(self._inner.setMode(_arg_mode))
+            }
+            fn getConfig(&self) -> binder::Result<String> {
+                self._rt.block_on(self._inner.getConfig())
+            }
+            fn setConfig(&self, _arg_config: &str) -> binder::Result<String> {
+                self._rt.block_on(self._inner.setConfig(_arg_config))
+            }
+        }
+        let wrapped = Wrapper {
+            _inner: inner,
+            _rt: rt,
+        };
+        Self::new_binder(wrapped, features)
+    }
+}
+pub trait IFasControlDefault: Send + Sync {
+    fn getStatus(&self) -> binder::Result<String> {
+        Err(binder::StatusCode::UNKNOWN_TRANSACTION.into())
+    }
+    fn getMode(&self) -> binder::Result<String> {
+        Err(binder::StatusCode::UNKNOWN_TRANSACTION.into())
+    }
+    fn setMode(&self, _arg_mode: &str) -> binder::Result<bool> {
+        Err(binder::StatusCode::UNKNOWN_TRANSACTION.into())
+    }
+    fn getConfig(&self) -> binder::Result<String> {
+        Err(binder::StatusCode::UNKNOWN_TRANSACTION.into())
+    }
+    fn setConfig(&self, _arg_config: &str) -> binder::Result<String> {
+        Err(binder::StatusCode::UNKNOWN_TRANSACTION.into())
+    }
+}
+pub mod transactions {
+    pub const getStatus: binder::binder_impl::TransactionCode =
+        binder::binder_impl::FIRST_CALL_TRANSACTION + 0;
+    pub const getMode: binder::binder_impl::TransactionCode =
+        binder::binder_impl::FIRST_CALL_TRANSACTION + 1;
+    pub const setMode: binder::binder_impl::TransactionCode =
+        binder::binder_impl::FIRST_CALL_TRANSACTION + 2;
+    pub const getConfig: binder::binder_impl::TransactionCode =
+        binder::binder_impl::FIRST_CALL_TRANSACTION + 3;
+    pub const setConfig: binder::binder_impl::TransactionCode =
+        binder::binder_impl::FIRST_CALL_TRANSACTION + 4;
+}
+pub type IFasControlDefaultRef = Option<std::sync::Arc<dyn IFasControlDefault>>;
+use lazy_static::lazy_static;
+lazy_static! {
+    static ref DEFAULT_IMPL: std::sync::Mutex<IFasControlDefaultRef> = std::sync::Mutex::new(None);
+}
+impl BpFasControl {
+    fn build_parcel_getStatus(&self) -> binder::Result<binder::binder_impl::Parcel> {
+        let aidl_data = self.binder.prepare_transact()?;
+        Ok(aidl_data)
+    }
+    fn read_response_getStatus(
+        &self,
+        _aidl_reply: std::result::Result<binder::binder_impl::Parcel, binder::StatusCode>,
+    ) -> binder::Result<String> {
+        if let Err(binder::StatusCode::UNKNOWN_TRANSACTION) = _aidl_reply {
+            if let Some(_aidl_default_impl) = <Self as IFasControl>::getDefaultImpl() {
+                return _aidl_default_impl.getStatus();
+            }
+        }
+        let _aidl_reply = _aidl_reply?;
+        let _aidl_status: binder::Status = _aidl_reply.read()?;
+        if !_aidl_status.is_ok() {
+            return Err(_aidl_status);
+        }
+        let _aidl_return: String = _aidl_reply.read()?;
+        Ok(_aidl_return)
+    }
+    fn build_parcel_getMode(&self) -> binder::Result<binder::binder_impl::Parcel> {
+        let aidl_data = self.binder.prepare_transact()?;
+        Ok(aidl_data)
+    }
+    fn read_response_getMode(
+        &self,
+        _aidl_reply: std::result::Result<binder::binder_impl::Parcel, binder::StatusCode>,
+    ) -> binder::Result<String> {
+        if let Err(binder::StatusCode::UNKNOWN_TRANSACTION) = _aidl_reply {
+            if let Some(_aidl_default_impl) = <Self as IFasControl>::getDefaultImpl() {
+                return _aidl_default_impl.getMode();
+            }
+        }
+        let _aidl_reply = _aidl_reply?;
+        let _aidl_status: binder::Status = _aidl_reply.read()?;
+        if !_aidl_status.is_ok() {
+            return Err(_aidl_status);
+        }
+        let _aidl_return: String = _aidl_reply.read()?;
+        Ok(_aidl_return)
+    }
+    fn build_parcel_setMode(&self, _arg_mode: &str) -> binder::Result<binder::binder_impl::Parcel> {
+        let mut aidl_data = self.binder.prepare_transact()?;
+        aidl_data.write(_arg_mode)?;
+        Ok(aidl_data)
+    }
+    fn read_response_setMode(
+        &self,
+        _arg_mode: &str,
+        _aidl_reply: std::result::Result<binder::binder_impl::Parcel, binder::StatusCode>,
+    ) -> binder::Result<bool> {
+        if let Err(binder::StatusCode::UNKNOWN_TRANSACTION) = _aidl_reply {
+            if let Some(_aidl_default_impl) = <Self as IFasControl>::getDefaultImpl() {
+                return _aidl_default_impl.setMode(_arg_mode);
+            }
+        }
+        let _aidl_reply = _aidl_reply?;
+        let _aidl_status: binder::Status = _aidl_reply.read()?;
+        if !_aidl_status.is_ok() {
+            return Err(_aidl_status);
+        }
+        let _aidl_return: bool = _aidl_reply.read()?;
+        Ok(_aidl_return)
+    }
+    fn build_parcel_getConfig(&self) -> binder::Result<binder::binder_impl::Parcel> {
+        let aidl_data = self.binder.prepare_transact()?;
+        Ok(aidl_data)
+    }
+    fn read_response_getConfig(
+        &self,
+        _aidl_reply: std::result::Result<binder::binder_impl::Parcel, binder::StatusCode>,
+    ) -> binder::Result<String> {
+        if let Err(binder::StatusCode::UNKNOWN_TRANSACTION) = _aidl_reply {
+            if let Some(_aidl_default_impl) = <Self as IFasControl>::getDefaultImpl() {
+                return _aidl_default_impl.getConfig();
+            }
+        }
+        let _aidl_reply = _aidl_reply?;
+        let _aidl_status: binder::Status = _aidl_reply.read()?;
+        if !_aidl_status.is_ok() {
+            return Err(_aidl_status);
+        }
+        let _aidl_return: String = _aidl_reply.read()?;
+        Ok(_aidl_return)
+    }
+    fn build_parcel_setConfig(
+        &self,
+        _arg_config: &str,
+    ) -> binder::Result<binder::binder_impl::Parcel> {
+        let mut aidl_data = self.binder.prepare_transact()?;
+        aidl_data.write(_arg_config)?;
+        Ok(aidl_data)
+    }
+    fn read_response_setConfig(
+        &self,
+        _arg_config: &str,
+        _aidl_reply: std::result::Result<binder::binder_impl::Parcel, binder::StatusCode>,
+    ) -> binder::Result<String> {
+        if let Err(binder::StatusCode::UNKNOWN_TRANSACTION) = _aidl_reply {
+            if let Some(_aidl_default_impl) = <Self as IFasControl>::getDefaultImpl() {
+                return _aidl_default_impl.setConfig(_arg_config);
+            }
+        }
+        let _aidl_reply = _aidl_reply?;
+        let _aidl_status: binder::Status = _aidl_reply.read()?;
+        if !_aidl_status.is_ok() {
+            return Err(_aidl_status);
+        }
+        let _aidl_return: String = _aidl_reply.read()?;
+        Ok(_aidl_return)
+    }
+}
+impl IFasControl for BpFasControl {
+    fn getStatus(&self) -> binder::Result<String> {
+        let _aidl_data = self.build_parcel_getStatus()?;
+        let _aidl_reply = self.binder.submit_transact(
+            transactions::getStatus,
+            _aidl_data,
+            binder::binder_impl::FLAG_PRIVATE_LOCAL,
+        );
+        self.read_response_getStatus(_aidl_reply)
+    }
+    fn getMode(&self) -> binder::Result<String> {
+        let _aidl_data = self.build_parcel_getMode()?;
+        let _aidl_reply = self.binder.submit_transact(
+            transactions::getMode,
+            _aidl_data,
+            binder::binder_impl::FLAG_PRIVATE_LOCAL,
+        );
+        self.read_response_getMode(_aidl_reply)
+    }
+    fn setMode(&self, _arg_mode: &str) -> binder::Result<bool> {
+        let _aidl_data = self.build_parcel_setMode(_arg_mode)?;
+        let _aidl_reply = self.binder.submit_transact(
+            transactions::setMode,
+            _aidl_data,
+            binder::binder_impl::FLAG_PRIVATE_LOCAL,
+        );
+        self.read_response_setMode(_arg_mode, _aidl_reply)
+    }
+    fn getConfig(&self) -> binder::Result<String> {
+        let _aidl_data = self.build_parcel_getConfig()?;
+        let _aidl_reply = self.binder.submit_transact(
+            transactions::getConfig,
+            _aidl_data,
+            binder::binder_impl::FLAG_PRIVATE_LOCAL,
+        );
+        self.read_response_getConfig(_aidl_reply)
+    }
+    fn setConfig(&self, _arg_config: &str) -> binder::Result<String> {
+        let _aidl_data = self.build_parcel_setConfig(_arg_config)?;
+        let _aidl_reply = self.binder.submit_transact(
+            transactions::setConfig,
+            _aidl_data,
+            binder::binder_impl::FLAG_PRIVATE_LOCAL,
+        );
+        self.read_response_setConfig(_arg_config, _aidl_reply)
+    }
+}
+impl<P: binder::BinderAsyncPool> IFasControlAsync<P> for BpFasControl {
+    fn getStatus<'a>(&'a self) -> binder::BoxFuture<'a, binder::Result<String>> {
+        let _aidl_data = match self.build_parcel_getStatus() {
+            Ok(_aidl_data) => _aidl_data,
+            Err(err) => return Box::pin(std::future::ready(Err(err))),
+        };
+        let binder = self.binder.clone();
+        P::spawn(
+            move || {
+                binder.submit_transact(
+                    transactions::getStatus,
+                    _aidl_data,
+                    binder::binder_impl::FLAG_PRIVATE_LOCAL,
+                )
+            },
+            move |_aidl_reply| async move { self.read_response_getStatus(_aidl_reply) },
+        )
+    }
+    fn getMode<'a>(&'a self) -> binder::BoxFuture<'a, binder::Result<String>> {
+        let _aidl_data = match self.build_parcel_getMode() {
+            Ok(_aidl_data) => _aidl_data,
+            Err(err) => return Box::pin(std::future::ready(Err(err))),
+        };
+        let binder = self.binder.clone();
+        P::spawn(
+            move || {
+                binder.submit_transact(
+                    transactions::getMode,
+                    _aidl_data,
+                    binder::binder_impl::FLAG_PRIVATE_LOCAL,
+                )
+            },
+            move |_aidl_reply| async move { self.read_response_getMode(_aidl_reply) },
+        )
+    }
+    fn setMode<'a>(&'a self, _arg_mode: &'a str) -> binder::BoxFuture<'a, binder::Result<bool>> {
+        let _aidl_data = match self.build_parcel_setMode(_arg_mode) {
+            Ok(_aidl_data) => _aidl_data,
+            Err(err) => return Box::pin(std::future::ready(Err(err))),
+        };
+        let binder = self.binder.clone();
+        P::spawn(
+            move || {
+                binder.submit_transact(
+                    transactions::setMode,
+                    _aidl_data,
+                    binder::binder_impl::FLAG_PRIVATE_LOCAL,
+                )
+            },
+            move |_aidl_reply| async move { self.read_response_setMode(_arg_mode, _aidl_reply) },
+        )
+    }
+    fn getConfig<'a>(&'a self) -> binder::BoxFuture<'a, binder::Result<String>> {
+        let _aidl_data = match self.build_parcel_getConfig() {
+            Ok(_aidl_data) => _aidl_data,
+            Err(err) => return Box::pin(std::future::ready(Err(err))),
+        };
+        let binder = self.binder.clone();
+        P::spawn(
+            move || {
+                binder.submit_transact(
+                    transactions::getConfig,
+                    _aidl_data,
+                    binder::binder_impl::FLAG_PRIVATE_LOCAL,
+                )
+            },
+            move |_aidl_reply| async move { self.read_response_getConfig(_aidl_reply) },
+        )
+    }
+    fn setConfig<'a>(
+        &'a self,
+        _arg_config: &'a str,
+    ) -> binder::BoxFuture<'a, binder::Result<String>> {
+        let _aidl_data = match self.build_parcel_setConfig(_arg_config) {
+            Ok(_aidl_data) => _aidl_data,
+            Err(err) => return Box::pin(std::future::ready(Err(err))),
+        };
+        let binder = self.binder.clone();
+        P::spawn(
+            move || {
+                binder.submit_transact(
+                    transactions::setConfig,
+                    _aidl_data,
+                    binder::binder_impl::FLAG_PRIVATE_LOCAL,
+                )
+            },
+            move |_aidl_reply| async move { self.read_response_setConfig(_arg_config, _aidl_reply) },
+        )
+    }
+}
+impl IFasControl for binder::binder_impl::Binder<BnFasControl> {
+    fn getStatus(&self) -> binder::Result<String> {
+        self.0.getStatus()
+    }
+    fn getMode(&self) -> binder::Result<String> {
+        self.0.getMode()
+    }
+    fn setMode(&self, _arg_mode: &str) -> binder::Result<bool> {
+        self.0.setMode(_arg_mode)
+    }
+    fn getConfig(&self) -> binder::Result<String> {
+        self.0.getConfig()
+    }
+    fn setConfig(&self, _arg_config: &str) -> binder::Result<String> {
+        self.0.setConfig(_arg_config)
+    }
+}
+fn on_transact(
+    _aidl_service: &dyn IFasControl,
+    _aidl_code: binder::binder_impl::TransactionCode,
+    _aidl_data: &binder::binder_impl::BorrowedParcel<'_>,
+    _aidl_reply: &mut binder::binder_impl::BorrowedParcel<'_>,
+) -> std::result::Result<(), binder::StatusCode> {
+    match _aidl_code {
+        transactions::getStatus => {
+            let _aidl_return = _aidl_service.getStatus();
+            match &_aidl_return {
+                Ok(_aidl_return) => {
+                    _aidl_reply.write(&binder::Status::from(binder::StatusCode::OK))?;
+                    _aidl_reply.write(_aidl_return)?;
+                }
+                Err(_aidl_status) => _aidl_reply.write(_aidl_status)?,
+            }
+            Ok(())
+        }
+        transactions::getMode => {
+            let _aidl_return = _aidl_service.getMode();
+            match &_aidl_return {
+                Ok(_aidl_return) => {
+                    _aidl_reply.write(&binder::Status::from(binder::StatusCode::OK))?;
+                    _aidl_reply.write(_aidl_return)?;
+                }
+                Err(_aidl_status) => _aidl_reply.write(_aidl_status)?,
+            }
+            Ok(())
+        }
+        transactions::setMode => {
+            let _arg_mode: String = _aidl_data.read()?;
+            let _aidl_return = _aidl_service.setMode(&_arg_mode);
+            match &_aidl_return {
+                Ok(_aidl_return) => {
+                    _aidl_reply.write(&binder::Status::from(binder::StatusCode::OK))?;
+                    _aidl_reply.write(_aidl_return)?;
+                }
+                Err(_aidl_status) => _aidl_reply.write(_aidl_status)?,
+            }
+            Ok(())
+        }
+        transactions::getConfig => {
+            let _aidl_return = _aidl_service.getConfig();
+            match &_aidl_return {
+                Ok(_aidl_return) => {
+                    _aidl_reply.write(&binder::Status::from(binder::StatusCode::OK))?;
+                    _aidl_reply.write(_aidl_return)?;
+                }
+                Err(_aidl_status) => _aidl_reply.write(_aidl_status)?,
+            }
+            Ok(())
+        }
+        transactions::setConfig => {
+            let _arg_config: String = _aidl_data.read()?;
+            let _aidl_return = _aidl_service.setConfig(&_arg_config);
+            match &_aidl_return {
+                Ok(_aidl_return) => {
+                    _aidl_reply.write(&binder::Status::from(binder::StatusCode::OK))?;
+                    _aidl_reply.write(_aidl_return)?;
+                }
+                Err(_aidl_status) => _aidl_reply.write(_aidl_status)?,
+            }
+            Ok(())
+        }
+        _ => Err(binder::StatusCode::UNKNOWN_TRANSACTION),
+    }
+}
+pub(crate) mod mangled {
+    pub use super::IFasControl as _11_IFasControl;
+}
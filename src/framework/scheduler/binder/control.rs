@@ -0,0 +1,94 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{fs, str::FromStr};
+
+use binder::{Interface, ThreadState};
+use log::{info, warn};
+
+use super::IFasControl::IFasControl;
+use crate::framework::{config::Config, node::Mode};
+
+const STATUS_NODE: &str = "/dev/fas_rs/status";
+const MODE_NODE: &str = "/dev/fas_rs/mode";
+
+/// Same control surface as the WebUI backend, for devices whose SELinux policy
+/// blocks sockets between apps and the daemon but allows binder
+pub struct ControlServer {
+    config: Config,
+}
+
+impl ControlServer {
+    pub const fn new(config: Config) -> Self {
+        Self { config }
+    }
+
+    /// Anyone may read, changes are only taken from root, shell and `[control] uids`
+    fn may_change(&self, call: &str) -> bool {
+        let uid = ThreadState::get_calling_uid();
+        let allowed = self.config.control_allowed(uid);
+
+        if !allowed {
+            warn!("Rejected {call} over binder from uid {uid}");
+        }
+
+        allowed
+    }
+}
+
+impl Interface for ControlServer {}
+
+impl IFasControl for ControlServer {
+    fn getStatus(&self) -> binder::Result<String> {
+        Ok(fs::read_to_string(STATUS_NODE).unwrap_or_default())
+    }
+
+    fn getMode(&self) -> binder::Result<String> {
+        Ok(fs::read_to_string(MODE_NODE)
+            .map(|mode| mode.trim().to_string())
+            .unwrap_or_default())
+    }
+
+    fn setMode(&self, mode: &str) -> binder::Result<bool> {
+        if !self.may_change("setMode") {
+            return Ok(false);
+        }
+
+        let Ok(mode) = Mode::from_str(mode.trim()) else {
+            return Ok(false);
+        };
+
+        info!("Mode set to {mode} over binder");
+        Ok(fs::write(MODE_NODE, mode.to_string()).is_ok())
+    }
+
+    fn getConfig(&self) -> binder::Result<String> {
+        Ok(fs::read_to_string(self.config.path()).unwrap_or_default())
+    }
+
+    /// Returns an empty string on success, otherwise why the config was rejected
+    fn setConfig(&self, config: &str) -> binder::Result<String> {
+        if !self.may_change("setConfig") {
+            return Ok("Permission denied".into());
+        }
+
+        match Config::write_checked(self.config.path(), config) {
+            Ok(()) => {
+                info!("Config updated over binder");
+                Ok(String::new())
+            }
+            Err(e) => Ok(e.to_string()),
+        }
+    }
+}
@@ -13,7 +13,9 @@
 // limitations under the License.
 
 #![allow(non_snake_case)]
+mod IFasControl;
 mod IRemoteService;
+mod control;
 
 use std::{
     process,
@@ -31,6 +33,8 @@ use crate::framework::{
     error::{Error, Result},
     node::Node,
 };
+use control::ControlServer;
+use IFasControl::BnFasControl;
 use IRemoteService::BnRemoteService;
 
 pub struct FasServer {
@@ -76,12 +80,21 @@ impl FasServer {
     }
 
     fn run(sx: Sender<FasData>, config: Config) -> Result<()> {
+        let control = config.config().binder_control.then(|| config.clone());
         let server = Self { config, sx };
         let server = BnRemoteService::new_binder(server, BinderFeatures::default());
 
         binder::add_service("fas_rs_server", server.as_binder())
             .map_err(|_| Error::Other("Failed to register binder service?"))?;
 
+        if let Some(config) = control {
+            let control = ControlServer::new(config);
+            let control = BnFasControl::new_binder(control, BinderFeatures::default());
+
+            binder::add_service("fas_rs_control", control.as_binder())
+                .map_err(|_| Error::Other("Failed to register binder control service?"))?;
+        }
+
         ProcessState::set_thread_pool_max_thread_count(8);
         ProcessState::start_thread_pool();
         ProcessState::join_thread_pool();
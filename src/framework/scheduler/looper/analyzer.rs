@@ -0,0 +1,113 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::{Duration, Instant};
+
+use frame_analyzer::Analyzer;
+use log::{error, info, warn};
+
+use super::{Looper, State};
+use crate::{
+    framework::{config::SensorKind, error::Error},
+    misc,
+};
+
+/// Consecutive loops whose attach failed inside the analyzer before the bpf object is reloaded
+const MAX_ATTACH_FAILURES: u32 = 10;
+/// No event for this long while a game is being handled means the probes died
+const SILENCE_TIME: Duration = Duration::from_millis(1500);
+
+/// Event stream health of the analyzer, shown in the status node
+pub struct AnalyzerHealth {
+    pub events: u64,
+    pub silences: u64,
+    pub restarts: u64,
+    attach_failures: u32,
+    last_event: Instant,
+    /// Already reloaded during the current silence, a second reload won't do better
+    reloaded_in_silence: bool,
+}
+
+impl AnalyzerHealth {
+    pub fn new() -> Self {
+        Self {
+            events: 0,
+            silences: 0,
+            restarts: 0,
+            attach_failures: 0,
+            last_event: Instant::now(),
+            reloaded_in_silence: false,
+        }
+    }
+
+    pub fn event_received(&mut self) {
+        self.events += 1;
+        self.last_event = Instant::now();
+        self.reloaded_in_silence = false;
+    }
+
+    pub fn silence(&self) -> Duration {
+        self.last_event.elapsed()
+    }
+}
+
+impl Looper {
+    /// Attach the analyzer to game processes, reloading its bpf object on SIGHUP, once
+    /// attaching keeps failing, or when the event stream dies while a game is still running,
+    /// so a wedged analyzer doesn't need a daemon restart
+    pub fn check_analyzer(&mut self) {
+        match self.update_analyzer() {
+            Ok(()) => self.analyzer_health.attach_failures = 0,
+            Err(Error::FrameAnalyzer(e)) => {
+                self.analyzer_health.attach_failures += 1;
+                warn!("Failed to attach the frame analyzer: {e}");
+            }
+            Err(_) => (),
+        }
+
+        let silent = self.sensor.kind() == SensorKind::Ebpf
+            && self.state != State::NotWorking
+            && !self.analyzer_health.reloaded_in_silence
+            && self.analyzer_health.silence() > SILENCE_TIME;
+        if silent {
+            self.analyzer_health.silences += 1;
+            self.analyzer_health.reloaded_in_silence = true;
+            warn!(
+                "No frame from the analyzer for {:?} while a game is running",
+                self.analyzer_health.silence()
+            );
+        }
+
+        if misc::take_reload_request()
+            || silent
+            || self.analyzer_health.attach_failures >= MAX_ATTACH_FAILURES
+        {
+            self.reload_analyzer();
+        }
+    }
+
+    fn reload_analyzer(&mut self) {
+        self.analyzer_health.attach_failures = 0;
+
+        match Analyzer::new() {
+            Ok(analyzer) => {
+                // Dropping the old analyzer detaches its programs, the next loop attaches the new one
+                self.analyzer = analyzer;
+                self.analyzer_health.restarts += 1;
+                info!("Frame analyzer reloaded");
+            }
+            Err(e) => error!("Failed to reload the frame analyzer, keep using the old one: {e}"),
+        }
+    }
+}
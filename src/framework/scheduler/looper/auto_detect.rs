@@ -0,0 +1,137 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use dumpsys_rs::Dumpsys;
+use log::info;
+
+use super::{Looper, State};
+use crate::framework::utils::get_process_name;
+
+const PROBE_INTERVAL: Duration = Duration::from_secs(2);
+const MIN_FPS: f64 = 30.0;
+const REQUIRED_HITS: u32 = 3;
+
+struct Candidate {
+    last_timestamp: i64,
+    hits: u32,
+}
+
+pub struct AutoDetect {
+    surfaceflinger: Option<Dumpsys>,
+    candidates: HashMap<String, Candidate>,
+    timer: Instant,
+}
+
+impl AutoDetect {
+    pub fn new() -> Self {
+        Self {
+            surfaceflinger: None,
+            candidates: HashMap::new(),
+            timer: Instant::now(),
+        }
+    }
+
+    /// Whether `pkg` keeps producing frames on a `SurfaceView` layer at a game-like rate
+    fn probe(&mut self, pkg: &str) -> bool {
+        if self.surfaceflinger.is_none() {
+            self.surfaceflinger = Dumpsys::new("SurfaceFlinger");
+        }
+        let Some(surfaceflinger) = &self.surfaceflinger else {
+            return false;
+        };
+
+        let Some(timestamps) = surfaceview_timestamps(surfaceflinger, pkg) else {
+            self.candidates.remove(pkg);
+            return false;
+        };
+
+        let candidate = self.candidates.entry(pkg.to_string()).or_insert(Candidate {
+            last_timestamp: 0,
+            hits: 0,
+        });
+
+        let (Some(first), Some(last)) = (timestamps.first(), timestamps.last()) else {
+            candidate.hits = 0;
+            return false;
+        };
+
+        let span = Duration::from_nanos((last - first).max(1) as u64);
+        let fps = (timestamps.len() - 1) as f64 / span.as_secs_f64();
+
+        if *last > candidate.last_timestamp && fps >= MIN_FPS {
+            candidate.hits += 1;
+        } else {
+            candidate.hits = 0;
+        }
+        candidate.last_timestamp = *last;
+
+        candidate.hits >= REQUIRED_HITS
+    }
+}
+
+impl Looper {
+    pub fn update_auto_detect(&mut self) {
+        if !self.config.config().auto_detect
+            || self.state != State::NotWorking
+            || self.auto_detect.timer.elapsed() < PROBE_INTERVAL
+        {
+            return;
+        }
+        self.auto_detect.timer = Instant::now();
+
+        let pkgs: Vec<_> = self
+            .windows_watcher
+            .topapp_pids()
+            .iter()
+            .filter_map(|pid| get_process_name(*pid).ok())
+            .filter(|pkg| !self.config.need_fas(pkg) && !self.config.blacklisted(pkg))
+            .collect();
+
+        self.auto_detect
+            .candidates
+            .retain(|pkg, _| pkgs.contains(pkg));
+
+        for pkg in pkgs {
+            if self.auto_detect.probe(&pkg) {
+                info!("Detected game-like rendering of [{pkg}], enable fas for it");
+                self.auto_detect.candidates.remove(&pkg);
+                self.config.add_auto_game(pkg);
+            }
+        }
+    }
+}
+
+fn surfaceview_timestamps(dumper: &Dumpsys, pkg: &str) -> Option<Vec<i64>> {
+    let layers = dumper.dump(&["--list"]).ok()?;
+    let layer = layers
+        .lines()
+        .find(|layer| layer.contains(pkg) && layer.contains("SurfaceView"))?
+        .trim();
+
+    let latency = dumper.dump(&["--latency", layer]).ok()?;
+    let mut timestamps: Vec<i64> = latency
+        .lines()
+        .skip(1)
+        .filter_map(|line| line.split_whitespace().nth(1)?.parse().ok())
+        .filter(|timestamp| *timestamp != 0 && *timestamp != i64::MAX)
+        .collect();
+    timestamps.sort_unstable();
+
+    (timestamps.len() > 1).then_some(timestamps)
+}
@@ -0,0 +1,55 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use log::info;
+
+use super::Looper;
+
+impl Looper {
+    pub fn update_battery(&mut self) {
+        let charging_mode = if self.power_supply.charging() {
+            self.config.charging_mode(self.power_supply.temp())
+        } else {
+            None
+        };
+
+        if charging_mode != self.charging_mode {
+            if let Some(mode) = charging_mode {
+                info!("Charging, apply mode: {mode}");
+            } else {
+                info!("Charging mode released");
+            }
+
+            self.charging_mode = charging_mode;
+        }
+
+        let rule = if self.power_supply.charging() {
+            None
+        } else {
+            self.power_supply
+                .capacity()
+                .and_then(|capacity| self.config.battery_rule(capacity))
+        };
+
+        if rule != self.battery_rule {
+            if let Some(rule) = rule {
+                info!("Battery below {}%, apply rule: {rule:?}", rule.below);
+            } else {
+                info!("Battery rule released");
+            }
+
+            self.battery_rule = rule;
+        }
+    }
+}
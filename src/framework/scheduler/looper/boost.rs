@@ -0,0 +1,66 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::{Duration, Instant};
+
+use libc::pid_t;
+use log::info;
+
+use super::Looper;
+use crate::framework::utils::get_process_age;
+
+const LAUNCH_WINDOW: Duration = Duration::from_secs(10);
+
+impl Looper {
+    pub fn try_launch_boost(&mut self, pid: pid_t) {
+        let boost_time = self.config.config().launch_boost_time;
+
+        if boost_time == 0 || self.state.is_active() || self.launch_boost.is_some() {
+            return;
+        }
+
+        if !get_process_age(pid).is_ok_and(|age| age <= LAUNCH_WINDOW) {
+            return;
+        }
+
+        info!("Launch boost for {boost_time}ms");
+
+        self.controller.launch_boost();
+        // The controller logs its writes in a dry run, the io booster would really apply them
+        if !self.config.config().dry_run {
+            self.io_booster.boost();
+        }
+        self.launch_boost = Some(Instant::now());
+    }
+
+    pub fn check_launch_boost(&mut self) {
+        let Some(timer) = self.launch_boost else {
+            return;
+        };
+
+        let boost_time = Duration::from_millis(self.config.config().launch_boost_time);
+        if timer.elapsed() < boost_time && !self.buffers.is_empty() {
+            return;
+        }
+
+        self.launch_boost = None;
+        if !self.io_wait.boosted() {
+            self.io_booster.restore();
+        }
+
+        if !self.state.is_active() {
+            self.controller.end_launch_boost();
+        }
+    }
+}
@@ -14,33 +14,35 @@
 
 use std::time::Duration;
 
-#[cfg(debug_assertions)]
 use log::debug;
 
 use super::Buffer;
-use crate::{api::v2::ApiV2, framework::config::TargetFps, Extension};
+use crate::{
+    api::v2::ApiV2,
+    framework::config::{TargetFps, AUTO_TARGET_FPS},
+    Extension,
+};
 
 impl Buffer {
     pub fn calculate_current_fps(&mut self) {
         let avg_time: Duration = self
             .frametimes
-            .iter()
-            .sum::<Duration>()
+            .sum()
             .saturating_add(self.additional_frametime)
             .checked_div(self.frametimes.len().try_into().unwrap())
             .unwrap_or_default();
-        #[cfg(debug_assertions)]
         debug!("avg_time: {avg_time:?}");
 
         self.avg_time = avg_time;
 
         let current_fps = 1.0 / avg_time.as_secs_f64();
 
-        #[cfg(debug_assertions)]
         debug!("current_fps: {:.2}", current_fps);
 
         self.current_fps = current_fps;
 
+        self.calculate_jitter();
+
         while self.current_fpses.len() >= 5 {
             self.current_fpses.pop_back();
         }
@@ -48,6 +50,17 @@ impl Buffer {
         self.current_fpses.push_front(current_fps);
     }
 
+    fn calculate_jitter(&mut self) {
+        self.jitter = self.frametimes.std_dev();
+
+        debug!("jitter: {:?}", self.jitter);
+    }
+
+    pub fn fps_ratio(&self) -> f64 {
+        self.target_fps
+            .map_or(f64::INFINITY, |target_fps| self.current_fps / target_fps)
+    }
+
     pub fn calculate_target_fps(&mut self, extension: &Extension) {
         let new_target_fps = self.target_fps();
         if self.target_fps != new_target_fps {
@@ -60,12 +73,33 @@ impl Buffer {
         }
     }
 
-    fn target_fps(&self) -> Option<u32> {
-        let target_fpses = match &self.target_fps_config {
+    /// Targets a sustained miss can step down to, the configured ones or the common rates
+    pub fn ladder_steps(&self) -> Vec<f64> {
+        match &self.target_fps_config {
+            TargetFps::Array(arr) if arr.len() > 1 => arr.clone(),
+            _ => AUTO_TARGET_FPS.to_vec(),
+        }
+    }
+
+    fn target_fps(&self) -> Option<f64> {
+        let mut target_fpses = match &self.target_fps_config {
             TargetFps::Value(t) => vec![*t],
             TargetFps::Array(arr) => arr.clone(),
         };
 
+        if let Some(fps_cap) = self
+            .fps_cap
+            .map(f64::from)
+            .into_iter()
+            .chain(self.ladder)
+            .reduce(f64::min)
+        {
+            target_fpses.retain(|fps| *fps <= fps_cap);
+            if target_fpses.is_empty() {
+                target_fpses.push(fps_cap);
+            }
+        }
+
         let mut current_fps: Option<f64> = None;
         for next_fps in self.current_fpses.iter().copied().take(144) {
             if let Some(fps) = current_fps {
@@ -77,13 +111,12 @@ impl Buffer {
 
         let current_fps = current_fps?;
 
-        if current_fps < (target_fpses[0].saturating_sub(10).max(10)).into() {
+        if current_fps < (target_fpses[0] - 10.0).max(10.0) {
             return None;
         }
 
         for target_fps in target_fpses.iter().copied() {
-            if current_fps <= f64::from(target_fps) + 3.0 {
-                #[cfg(debug_assertions)]
+            if current_fps <= target_fps + 3.0 {
                 debug!(
                     "Matched target_fps: current: {:.2} target_fps: {target_fps}",
                     current_fps
@@ -13,6 +13,10 @@
 // limitations under the License.
 
 pub mod calculate;
+mod pool;
+mod ring;
+mod smooth;
+mod vsync;
 
 use std::{
     collections::VecDeque,
@@ -20,8 +24,20 @@ use std::{
 };
 
 use libc::pid_t;
+pub use pool::BufferPool;
+use pool::Storage;
+use ring::FrameRing;
 
-use crate::{framework::config::TargetFps, Extension};
+use super::engine::Engine;
+
+use crate::{
+    framework::config::{Config, TargetFps},
+    Extension,
+};
+
+const OUTLIER_MIN_SAMPLES: usize = 10;
+/// Most frametimes a buffer keeps, the window is the target fps worth of frames up to this
+pub const MAX_FRAMETIMES: usize = 240;
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum BufferState {
@@ -33,70 +49,234 @@ pub enum BufferState {
 pub struct Buffer {
     pub pid: pid_t,
     pub pkg: String,
-    pub target_fps: Option<u32>,
+    pub activity: Option<String>,
+    pub engine: Engine,
+    pub target_fps: Option<f64>,
     pub current_fps: f64,
     pub current_fpses: VecDeque<f64>,
     pub avg_time: Duration,
-    pub frametimes: VecDeque<Duration>,
+    pub jitter: Duration,
+    pub frametimes: FrameRing<MAX_FRAMETIMES>,
     pub last_update: Instant,
     target_fps_config: TargetFps,
+    fps_cap: Option<u32>,
+    /// Lower target stepped down to after sustained misses at max frequency
+    ladder: Option<f64>,
     timer: Instant,
     pub state: BufferState,
     state_timer: Instant,
     additional_frametime: Duration,
+    spike_streak: u32,
+    ema_frametime: Option<Duration>,
+    vsync: Option<(Duration, Instant)>,
+    last_vsync_slot: Option<i64>,
 }
 
 impl Buffer {
     pub fn new(target_fps_config: TargetFps, pid: pid_t, pkg: String) -> Self {
+        Self::with_storage(target_fps_config, pid, pkg, Storage::default())
+    }
+
+    pub fn with_storage(
+        target_fps_config: TargetFps,
+        pid: pid_t,
+        pkg: String,
+        storage: Storage,
+    ) -> Self {
         Self {
             pid,
             pkg,
+            activity: None,
+            engine: Engine::Custom,
             target_fps: None,
             target_fps_config,
+            fps_cap: None,
+            ladder: None,
             current_fps: 0.0,
-            current_fpses: VecDeque::with_capacity(144 * 3),
+            current_fpses: storage.current_fpses,
             avg_time: Duration::ZERO,
-            frametimes: VecDeque::with_capacity(144),
+            jitter: Duration::ZERO,
+            frametimes: FrameRing::default(),
             last_update: Instant::now(),
             timer: Instant::now(),
             state: BufferState::Unusable,
             state_timer: Instant::now(),
             additional_frametime: Duration::ZERO,
+            spike_streak: 0,
+            ema_frametime: None,
+            vsync: None,
+            last_vsync_slot: None,
         }
     }
 
-    pub fn push_frametime(&mut self, d: Duration, extension: &Extension) {
+    pub fn push_frametime(&mut self, d: Duration, config: &Config, extension: &Extension) {
+        self.push_frametime_at(d, Instant::now(), config, extension);
+    }
+
+    pub fn push_frametime_at(
+        &mut self,
+        d: Duration,
+        now: Instant,
+        config: &Config,
+        extension: &Extension,
+    ) {
         self.additional_frametime = Duration::ZERO;
-        self.last_update = Instant::now();
+        self.last_update = now;
+        extension.tigger_frame(d, &self.pkg);
+        let d = self.align_to_vsync(d, now);
+
+        let outlier_ratio = match config.config().outlier_ratio {
+            ratio if ratio > 1.0 => ratio,
+            _ => self.engine.outlier_ratio(),
+        };
+        if self.is_outlier(d, outlier_ratio) {
+            return;
+        }
 
-        while self.frametimes.len() >= self.target_fps.unwrap_or(144) as usize {
+        let window = (self.target_fps.unwrap_or(144.0) as usize).min(MAX_FRAMETIMES);
+        while self.frametimes.len() >= window {
             self.frametimes.pop_back();
             self.try_usable();
         }
 
         self.frametimes.push_front(d);
+        self.update_smoothing(d, config);
 
-        if self.timer.elapsed() >= Duration::from_secs(1) {
-            self.timer = Instant::now();
+        if now.duration_since(self.timer) >= Duration::from_secs(1) {
+            self.timer = now;
             self.calculate_current_fps();
             self.calculate_target_fps(extension);
         }
     }
 
+    fn is_outlier(&mut self, d: Duration, ratio: f64) -> bool {
+        if ratio <= 1.0 || self.frametimes.len() < OUTLIER_MIN_SAMPLES {
+            return false;
+        }
+
+        let Some(median) = self.frametimes.median() else {
+            return false;
+        };
+
+        if d > median.mul_f64(ratio) {
+            self.spike_streak += 1;
+        } else {
+            self.spike_streak = 0;
+        }
+
+        // Only an isolated spike is dropped, sustained slow frames are real load
+        self.spike_streak == 1
+    }
+
+    pub fn set_target_fps_config(&mut self, target_fps_config: TargetFps) {
+        self.target_fps_config = target_fps_config;
+        self.ladder = None;
+    }
+
+    pub const fn ladder(&self) -> Option<f64> {
+        self.ladder
+    }
+
+    pub const fn set_ladder(&mut self, ladder: Option<f64>) {
+        self.ladder = ladder;
+    }
+
+    pub const fn set_fps_cap(&mut self, fps_cap: Option<u32>) {
+        self.fps_cap = fps_cap;
+    }
+
     pub fn try_usable(&mut self) {
         if self.state == BufferState::Unusable
-            && self.state_timer.elapsed() >= Duration::from_secs(1)
+            && self.last_update.duration_since(self.state_timer) >= Duration::from_secs(1)
         {
             self.state = BufferState::Usable;
         }
     }
 
-    pub fn unusable(&mut self) {
+    pub const fn unusable(&mut self) {
         self.state = BufferState::Unusable;
-        self.state_timer = Instant::now();
+        self.state_timer = self.last_update;
     }
 
     pub fn additional_frametime(&mut self) {
         self.additional_frametime = self.last_update.elapsed();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use super::*;
+    use crate::framework::{config::AUTO_TARGET_FPS, Mode};
+
+    fn config(name: &str) -> Config {
+        let path = env::temp_dir().join(format!("fas-rs-{}-{name}.toml", process::id()));
+        fs::write(&path, include_str!("../../../../../module/games.toml")).unwrap();
+        Config::new(&path, &path).unwrap()
+    }
+
+    /// Feed `secs` seconds of frames at `fps` on a synthetic timeline
+    fn trace(buffer: &mut Buffer, config: &Config, fps: f64, secs: u32) {
+        let extension = Extension::noop();
+        let frametime = Duration::from_secs_f64(fps.recip());
+        let mut now = Instant::now();
+
+        for _ in 0..(fps * f64::from(secs)) as usize {
+            now += frametime;
+            buffer.push_frametime_at(frametime, now, config, &extension);
+        }
+    }
+
+    fn auto_buffer() -> Buffer {
+        Buffer::new(TargetFps::Array(AUTO_TARGET_FPS.to_vec()), 0, "game".into())
+    }
+
+    #[test]
+    fn matches_144_and_165hz_targets() {
+        for (name, fps) in [("144", 144.0), ("165", 165.0)] {
+            let config = config(name);
+            let mut buffer = auto_buffer();
+            trace(&mut buffer, &config, fps, 4);
+
+            assert_eq!(buffer.target_fps, Some(fps));
+            assert_eq!(buffer.state, BufferState::Usable);
+            assert!((buffer.current_fps - fps).abs() < 1.0);
+        }
+    }
+
+    #[test]
+    fn window_follows_the_target_fps() {
+        let config = config("window");
+        let mut buffer = auto_buffer();
+        trace(&mut buffer, &config, 165.0, 4);
+        assert_eq!(buffer.frametimes.len(), 165);
+
+        let mut buffer = Buffer::new(TargetFps::Value(240.0), 0, "game".into());
+        trace(&mut buffer, &config, 240.0, 4);
+        assert_eq!(buffer.frametimes.len(), MAX_FRAMETIMES);
+    }
+
+    #[test]
+    fn on_target_165hz_frames_need_no_boost() {
+        let config = config("event");
+        let mut buffer = auto_buffer();
+        trace(&mut buffer, &config, 165.0, 4);
+
+        let event = buffer.event(&config, Mode::Balance).unwrap();
+        assert!(event.frame.abs_diff(Duration::from_secs(1)) < Duration::from_millis(10));
+        assert!(event.factor(165.0, &config) <= 0.0);
+    }
+
+    #[test]
+    fn late_165hz_frames_ask_for_more() {
+        let config = config("late");
+        let mut buffer = auto_buffer();
+        trace(&mut buffer, &config, 165.0, 4);
+        trace(&mut buffer, &config, 150.0, 1);
+
+        let event = buffer.event(&config, Mode::Balance).unwrap();
+        assert!(event.frame > event.target);
+        assert!(event.factor(165.0, &config) > 0.0);
+    }
+}
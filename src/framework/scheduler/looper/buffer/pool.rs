@@ -0,0 +1,62 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{collections::VecDeque, mem};
+
+use super::Buffer;
+
+/// Retired storages kept around, more than a few games rarely come and go at once
+const POOL_SIZE: usize = 4;
+
+/// The heap allocations of a buffer, frametimes live inline in its `FrameRing`
+#[derive(Debug)]
+pub struct Storage {
+    pub current_fpses: VecDeque<f64>,
+}
+
+impl Default for Storage {
+    fn default() -> Self {
+        Self {
+            current_fpses: VecDeque::with_capacity(144 * 3),
+        }
+    }
+}
+
+/// Storages of dropped buffers, so a restarted game or a pid change doesn't reallocate them
+#[derive(Debug, Default)]
+pub struct BufferPool {
+    storages: Vec<Storage>,
+}
+
+impl BufferPool {
+    pub fn take(&mut self) -> Storage {
+        self.storages.pop().unwrap_or_default()
+    }
+
+    pub fn recycle(&mut self, buffer: Buffer) {
+        if self.storages.len() < POOL_SIZE {
+            self.storages.push(buffer.into_storage());
+        }
+    }
+}
+
+impl Buffer {
+    fn into_storage(mut self) -> Storage {
+        self.current_fpses.clear();
+
+        Storage {
+            current_fpses: mem::take(&mut self.current_fpses),
+        }
+    }
+}
@@ -0,0 +1,121 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::Duration;
+
+/// Frametimes of a buffer, newest first, in a fixed array of `N` slots. The sum, the sum of
+/// squares and a sorted copy are kept up to date on every push and pop, so the mean, jitter and
+/// median don't rescan the window
+#[derive(Debug)]
+pub struct FrameRing<const N: usize> {
+    slots: [Duration; N],
+    sorted: [Duration; N],
+    /// Slot of the newest frametime
+    head: usize,
+    len: usize,
+    sum: u128,
+    sum_squares: u128,
+}
+
+impl<const N: usize> Default for FrameRing<N> {
+    fn default() -> Self {
+        Self {
+            slots: [Duration::ZERO; N],
+            sorted: [Duration::ZERO; N],
+            head: N - 1,
+            len: 0,
+            sum: 0,
+            sum_squares: 0,
+        }
+    }
+}
+
+impl<const N: usize> FrameRing<N> {
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn front(&self) -> Option<Duration> {
+        (!self.is_empty()).then(|| self.slots[self.head])
+    }
+
+    /// Newest first
+    pub fn iter(&self) -> impl Iterator<Item = Duration> + '_ {
+        (0..self.len).map(|age| self.slots[(self.head + N - age) % N])
+    }
+
+    /// Push the newest frametime, dropping the oldest one when all `N` slots are taken
+    pub fn push_front(&mut self, frametime: Duration) {
+        if self.len == N {
+            self.pop_back();
+        }
+
+        self.head = (self.head + 1) % N;
+        self.slots[self.head] = frametime;
+
+        let pos = self.sorted[..self.len].partition_point(|sorted| *sorted < frametime);
+        self.sorted.copy_within(pos..self.len, pos + 1);
+        self.sorted[pos] = frametime;
+
+        self.len += 1;
+        let nanos = frametime.as_nanos();
+        self.sum += nanos;
+        self.sum_squares += nanos * nanos;
+    }
+
+    pub fn pop_back(&mut self) -> Option<Duration> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let frametime = self.slots[(self.head + N - (self.len - 1)) % N];
+
+        if let Ok(pos) = self.sorted[..self.len].binary_search(&frametime) {
+            self.sorted.copy_within(pos + 1..self.len, pos);
+        }
+
+        self.len -= 1;
+        let nanos = frametime.as_nanos();
+        self.sum -= nanos;
+        self.sum_squares -= nanos * nanos;
+
+        Some(frametime)
+    }
+
+    pub fn sum(&self) -> Duration {
+        Duration::from_nanos(u64::try_from(self.sum).unwrap_or(u64::MAX))
+    }
+
+    pub fn median(&self) -> Option<Duration> {
+        (!self.is_empty()).then(|| self.sorted[self.len / 2])
+    }
+
+    /// Population standard deviation
+    pub fn std_dev(&self) -> Duration {
+        if self.is_empty() {
+            return Duration::ZERO;
+        }
+
+        // n * Σx² - (Σx)² is exact in integers, only the final division rounds
+        let len = self.len as u128;
+        let scaled = (len * self.sum_squares).saturating_sub(self.sum * self.sum);
+        let variance = scaled as f64 / (len * len) as f64;
+
+        Duration::from_nanos(variance.sqrt() as u64)
+    }
+}
@@ -0,0 +1,60 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::Duration;
+
+use smallvec::SmallVec;
+
+use super::Buffer;
+use crate::framework::config::{Config, Smoothing};
+
+/// Smoothing is tuned per frame at this rate, faster targets scale it to cover the same time
+const SMOOTHING_REFERENCE_FPS: f64 = 120.0;
+
+impl Buffer {
+    fn frames_per_reference(&self) -> f64 {
+        self.target_fps
+            .map_or(1.0, |target_fps| target_fps / SMOOTHING_REFERENCE_FPS)
+            .max(1.0)
+    }
+
+    pub fn update_smoothing(&mut self, d: Duration, config: &Config) {
+        let alpha = config.config().smoothing_alpha.clamp(0.0, 1.0);
+        let alpha = 1.0 - (1.0 - alpha).powf(self.frames_per_reference().recip());
+
+        self.ema_frametime = Some(self.ema_frametime.map_or(d, |ema| {
+            d.mul_f64(alpha).saturating_add(ema.mul_f64(1.0 - alpha))
+        }));
+    }
+
+    pub fn smoothed_frametime(&self, config: &Config) -> Option<Duration> {
+        let frames_per_reference = self.frames_per_reference();
+        let config = config.config();
+
+        match config.smoothing {
+            Smoothing::Raw => self.frametimes.front(),
+            Smoothing::Ema => self.ema_frametime,
+            Smoothing::Median => {
+                let window = config.smoothing_window.max(1) as f64 * frames_per_reference;
+                let mut frametimes: SmallVec<[Duration; 32]> = self
+                    .frametimes
+                    .iter()
+                    .take(window.round() as usize)
+                    .collect();
+                frametimes.sort_unstable();
+                frametimes.get(frametimes.len() / 2).copied()
+            }
+        }
+    }
+}
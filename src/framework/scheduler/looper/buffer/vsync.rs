@@ -0,0 +1,65 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::{Duration, Instant};
+
+use super::Buffer;
+
+impl Buffer {
+    /// Vsync period and the instant of any past vsync, `None` judges frames by length only
+    pub const fn set_vsync(&mut self, vsync: Option<(Duration, Instant)>) {
+        if vsync.is_none() {
+            self.last_vsync_slot = None;
+        }
+
+        self.vsync = vsync;
+    }
+
+    /// A frame longer than the target that still made its vsync needs no boost, while one that
+    /// just missed it is shown a whole period late, so judge it by the vsync it landed on
+    pub fn align_to_vsync(&mut self, d: Duration, now: Instant) -> Duration {
+        let (Some((period, anchor)), Some(target_fps)) = (self.vsync, self.target_fps) else {
+            return d;
+        };
+        if period.is_zero() || target_fps <= 0.0 {
+            return d;
+        }
+
+        let period_ns = period.as_nanos() as i64;
+        let since_anchor = if now >= anchor {
+            now.duration_since(anchor).as_nanos() as i64
+        } else {
+            -(anchor.duration_since(now).as_nanos() as i64)
+        };
+        let slot = since_anchor.div_euclid(period_ns);
+
+        let Some(last_slot) = self.last_vsync_slot.replace(slot) else {
+            return d;
+        };
+
+        let target = Duration::from_secs_f64(target_fps.recip());
+        let expected = (target.as_secs_f64() / period.as_secs_f64())
+            .round()
+            .max(1.0) as i64;
+        let passed = slot - last_slot;
+
+        if passed > expected {
+            d.max(period * passed as u32)
+        } else if d > target {
+            target
+        } else {
+            d
+        }
+    }
+}
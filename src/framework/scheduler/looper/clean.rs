@@ -15,8 +15,27 @@
 use std::{collections::HashMap, ffi::CString, fs, path::Path, ptr};
 
 use libc::{mount, umount, umount2, MS_BIND, MS_REC};
+use log::info;
 
-use crate::framework::error::Result;
+use crate::{
+    cpu_common,
+    framework::{config::Config, error::Result},
+    journal,
+    misc::{getprop, setprop},
+};
+
+/// Cpusets of background processes, confined to the little cluster while gaming
+const BACKGROUND_CPUSETS: [&str; 3] = [
+    "/dev/cpuset/background/cpus",
+    "/dev/cpuset/system-background/cpus",
+    "/dev/cpuset/restricted/cpus",
+];
+/// `cpu.shares` is cgroup v1 and `cpu.weight` v2, only one of them exists
+const BACKGROUND_CPUCTL: [(&str, &str); 3] = [
+    ("/dev/cpuctl/background/cpu.uclamp.max", "30"),
+    ("/dev/cpuctl/background/cpu.shares", "52"),
+    ("/dev/cpuctl/background/cpu.weight", "10"),
+];
 
 fn lock_value<P: AsRef<Path>, S: AsRef<str>>(p: P, v: S) -> Result<()> {
     let value = v.as_ref();
@@ -57,56 +76,190 @@ fn unmount(file_system: &str) {
     let _ = unsafe { umount(path.as_ptr()) };
 }
 
-macro_rules! lock_values {
-    ($map: expr, ($($path: literal),*), $value: literal) => {
-        $(
-            if let Ok(last_value) = fs::read_to_string($path) {
-                $map.insert($path, last_value);
+enum Action {
+    Lock(&'static str, &'static str),
+    Prop(&'static str, &'static str),
+    Service(&'static str),
+}
+
+struct Daemon {
+    name: &'static str,
+    optional: bool,
+    actions: &'static [Action],
+}
+
+const DAEMONS: &[Daemon] = &[
+    Daemon {
+        name: "fpsgo",
+        optional: false,
+        actions: &[
+            Action::Lock("/sys/module/mtk_fpsgo/parameters/perfmgr_enable", "0"),
+            Action::Lock("/sys/module/perfmgr/parameters/perfmgr_enable", "0"),
+            Action::Lock("/sys/module/perfmgr_policy/parameters/perfmgr_enable", "0"),
+            Action::Lock("/sys/module/perfmgr_mtk/parameters/perfmgr_enable", "0"),
+        ],
+    },
+    Daemon {
+        name: "migt",
+        optional: false,
+        actions: &[
+            Action::Lock("/sys/module/migt/parameters/glk_fbreak_enable", "0"),
+            Action::Lock("/sys/module/migt/parameters/glk_disable", "1"),
+        ],
+    },
+    Daemon {
+        name: "game_opt",
+        optional: false,
+        actions: &[Action::Lock("/proc/game_opt/disable_cpufreq_limit", "1")],
+    },
+    Daemon {
+        name: "powerhal hint sessions",
+        optional: true,
+        actions: &[Action::Prop("debug.hwui.use_hint_manager", "false")],
+    },
+    Daemon {
+        name: "oiface",
+        optional: true,
+        actions: &[Action::Service("oiface")],
+    },
+];
+
+impl Action {
+    fn detected(&self) -> bool {
+        match self {
+            Self::Lock(path, _) => Path::new(path).exists(),
+            Self::Prop(..) => true,
+            Self::Service(service) => {
+                getprop(format!("init.svc.{service}")).is_some_and(|state| state == "running")
             }
+        }
+    }
+}
 
-            let _ = lock_value($path, $value);
-        )*
+impl Daemon {
+    fn detected(&self) -> bool {
+        self.actions.iter().any(Action::detected)
     }
 }
 
+/// Vendor daemons present on this device that fas-rs neutralizes while working
+pub fn detected_daemons() -> Vec<&'static str> {
+    DAEMONS
+        .iter()
+        .filter(|daemon| daemon.detected())
+        .map(|daemon| daemon.name)
+        .collect()
+}
+
 pub struct Cleaner {
     map: HashMap<&'static str, String>,
+    props: HashMap<&'static str, String>,
+    services: Vec<&'static str>,
+    throttled: HashMap<&'static str, String>,
 }
 
 impl Cleaner {
     pub fn new() -> Self {
-        let map = HashMap::new();
+        Self {
+            map: HashMap::new(),
+            props: HashMap::new(),
+            services: Vec::new(),
+            throttled: HashMap::new(),
+        }
+    }
+
+    pub fn cleanup(&mut self, config: &Config) {
+        let config = config.config();
+
+        for daemon in DAEMONS
+            .iter()
+            .filter(|daemon| config.stop_vendor_daemons || !daemon.optional)
+            .filter(|daemon| !(config.fpsgo_coexist && daemon.name == "fpsgo"))
+            .filter(|daemon| daemon.detected())
+        {
+            info!("Neutralize vendor daemon: {}", daemon.name);
 
-        Self { map }
+            for action in daemon.actions.iter().filter(|action| action.detected()) {
+                self.apply(action);
+            }
+        }
+
+        if config.throttle_background {
+            self.throttle_background();
+        }
     }
 
-    pub fn cleanup(&mut self) {
-        lock_values!(
-            self.map,
-            (
-                "/sys/module/mtk_fpsgo/parameters/perfmgr_enable",
-                "/sys/module/perfmgr/parameters/perfmgr_enable",
-                "/sys/module/perfmgr_policy/parameters/perfmgr_enable",
-                "/sys/module/perfmgr_mtk/parameters/perfmgr_enable",
-                "/sys/module/migt/parameters/glk_fbreak_enable"
-            ),
-            "0"
-        );
+    /// Cgroup nodes are written directly instead of bind mounted, init and the framework
+    /// never rewrite them, and the journal brings them back if fas-rs dies
+    fn throttle_background(&mut self) {
+        let Some(little) = cpu_common::policies().ok().and_then(|policies| {
+            policies
+                .into_iter()
+                .min_by_key(|policy| policy.freqs.last().copied())
+        }) else {
+            return;
+        };
 
-        lock_values!(
-            self.map,
-            (
-                "/sys/module/migt/parameters/glk_disable",
-                "/proc/game_opt/disable_cpufreq_limit"
-            ),
-            "1"
-        );
+        let cpus: Vec<_> = little.cpus.iter().map(ToString::to_string).collect();
+        let cpus = cpus.join(",");
+
+        let writes = BACKGROUND_CPUSETS
+            .iter()
+            .map(|path| (*path, cpus.as_str()))
+            .chain(BACKGROUND_CPUCTL);
+        for (path, value) in writes {
+            let Ok(last_value) = fs::read_to_string(path) else {
+                continue;
+            };
+
+            journal::record(Path::new(path));
+            if fs::write(path, value).is_ok() {
+                self.throttled.entry(path).or_insert(last_value);
+            }
+        }
+
+        if !self.throttled.is_empty() {
+            info!("Throttled background processes to cpus {cpus}");
+        }
     }
 
-    pub fn undo_cleanup(&self) {
-        for (path, value) in &self.map {
+    pub fn undo_cleanup(&mut self) {
+        for (path, value) in self.map.drain() {
             unmount(path);
             let _ = fs::write(path, value);
         }
+
+        for (key, value) in self.props.drain() {
+            setprop(key, &value);
+        }
+
+        for service in self.services.drain(..) {
+            setprop("ctl.start", service);
+        }
+
+        for (path, value) in self.throttled.drain() {
+            let _ = fs::write(path, value.trim());
+        }
+    }
+
+    fn apply(&mut self, action: &Action) {
+        match *action {
+            Action::Lock(path, value) => {
+                if let Ok(last_value) = fs::read_to_string(path) {
+                    self.map.entry(path).or_insert(last_value);
+                }
+
+                let _ = lock_value(path, value);
+            }
+            Action::Prop(key, value) => {
+                let last_value = getprop(key).unwrap_or_default();
+                self.props.entry(key).or_insert(last_value);
+                setprop(key, value);
+            }
+            Action::Service(service) => {
+                self.services.push(service);
+                setprop("ctl.stop", service);
+            }
+        }
     }
 }
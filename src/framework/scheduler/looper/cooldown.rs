@@ -0,0 +1,52 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::{Duration, Instant};
+
+use log::info;
+
+use super::{Looper, State};
+
+impl Looper {
+    pub fn update_cooldown(&mut self) {
+        let config = self.config.config();
+        let ceiling = config.cooldown_ceiling.clamp(0.0, 1.0);
+        // No fas updates run during the cooldown to notice the limit going away
+        self.controller.relax_clamps();
+
+        match self.state {
+            State::Working
+                if config.cooldown_time > 0
+                    && self.fixed_freq.is_none()
+                    && self.controller.heavily_throttled(ceiling) =>
+            {
+                info!(
+                    "Heavy thermal throttling, hold {:.0}% of max freq for {}s",
+                    ceiling * 100.0,
+                    config.cooldown_time
+                );
+                self.state = State::Cooldown;
+                self.cooldown_timer = Instant::now();
+                self.controller.hold_freq(ceiling);
+            }
+            State::Cooldown
+                if self.cooldown_timer.elapsed() >= Duration::from_secs(config.cooldown_time) =>
+            {
+                info!("Cooldown finished, resume fas");
+                self.state = State::Working;
+            }
+            _ => (),
+        }
+    }
+}
@@ -0,0 +1,83 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use log::info;
+
+use crate::{cpu_common, init::SocProfile, journal};
+
+pub struct CpuIdle {
+    map: HashMap<PathBuf, String>,
+}
+
+impl CpuIdle {
+    pub fn new() -> Self {
+        Self {
+            map: HashMap::new(),
+        }
+    }
+
+    pub fn restrict(&mut self) {
+        let Ok(policies) = cpu_common::policies() else {
+            return;
+        };
+
+        let little_clusters = SocProfile::current().little_clusters;
+        for cpu in policies
+            .iter()
+            .skip(little_clusters)
+            .flat_map(|policy| &policy.cpus)
+        {
+            let Some(path) = deepest_state(*cpu) else {
+                continue;
+            };
+
+            if let Ok(last_value) = fs::read_to_string(&path) {
+                self.map.entry(path.clone()).or_insert(last_value);
+                journal::record(&path);
+                let _ = fs::write(&path, "1");
+            }
+        }
+
+        if !self.map.is_empty() {
+            info!("Disabled deepest cpuidle state of {} cpus", self.map.len());
+        }
+    }
+
+    pub fn restore(&mut self) {
+        for (path, value) in self.map.drain() {
+            let _ = fs::write(path, value);
+        }
+    }
+}
+
+fn deepest_state(cpu: i32) -> Option<PathBuf> {
+    let path = PathBuf::from(format!("/sys/devices/system/cpu/cpu{cpu}/cpuidle"));
+
+    fs::read_dir(&path)
+        .ok()?
+        .filter_map(std::result::Result::ok)
+        .filter_map(|entry| {
+            entry
+                .file_name()
+                .to_str()?
+                .strip_prefix("state")?
+                .parse::<u32>()
+                .ok()
+        })
+        .filter(|state| *state > 0)
+        .max()
+        .map(|state| path.join(format!("state{state}/disable")))
+}
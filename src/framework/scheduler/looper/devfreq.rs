@@ -0,0 +1,153 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    fs,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use super::{policy::FrameEvent, Looper};
+use crate::journal;
+
+const DEVFREQ_PATH: &str = "/sys/class/devfreq";
+const DEVFREQ_KEYWORDS: [&str; 5] = ["llcc", "ddr", "bus", "cpubw", "dmc"];
+const MAX_LEVEL: usize = 4;
+const RAISE_TIME: Duration = Duration::from_millis(500);
+const STABLE_TIME: Duration = Duration::from_secs(3);
+
+struct Device {
+    min_freq_path: PathBuf,
+    freqs: Vec<u64>,
+    origin: String,
+}
+
+pub struct DevfreqBooster {
+    devices: Vec<Device>,
+    level: usize,
+    timer: Instant,
+    raise_timer: Instant,
+}
+
+impl DevfreqBooster {
+    pub fn new() -> Self {
+        Self {
+            devices: Vec::new(),
+            level: 0,
+            timer: Instant::now(),
+            raise_timer: Instant::now(),
+        }
+    }
+
+    fn probe(&mut self) {
+        let Ok(entries) = fs::read_dir(DEVFREQ_PATH) else {
+            return;
+        };
+
+        for entry in entries.filter_map(std::result::Result::ok) {
+            let name = entry.file_name();
+            let name = name.to_string_lossy().to_lowercase();
+            if !DEVFREQ_KEYWORDS
+                .iter()
+                .any(|keyword| name.contains(keyword))
+            {
+                continue;
+            }
+
+            let path = entry.path();
+            let Ok(freqs) = fs::read_to_string(path.join("available_frequencies")) else {
+                continue;
+            };
+            let mut freqs: Vec<u64> = freqs
+                .split_whitespace()
+                .filter_map(|freq| freq.parse().ok())
+                .collect();
+            freqs.sort_unstable();
+
+            let min_freq_path = path.join("min_freq");
+            let Ok(origin) = fs::read_to_string(&min_freq_path) else {
+                continue;
+            };
+
+            if freqs.len() > 1 {
+                self.devices.push(Device {
+                    min_freq_path,
+                    freqs,
+                    origin,
+                });
+            }
+        }
+    }
+
+    fn set_level(&mut self, level: usize) {
+        if level == self.level {
+            return;
+        }
+
+        if self.level == 0 {
+            self.probe();
+        }
+
+        self.level = level;
+
+        for device in &self.devices {
+            if level == 0 {
+                let _ = fs::write(&device.min_freq_path, &device.origin);
+            } else {
+                let index = (device.freqs.len() - 1) * level / MAX_LEVEL;
+                journal::record(&device.min_freq_path);
+                let _ = fs::write(&device.min_freq_path, device.freqs[index].to_string());
+            }
+        }
+
+        if level == 0 {
+            self.devices.clear();
+        }
+    }
+
+    pub fn jank(&mut self) {
+        self.timer = Instant::now();
+
+        if self.level == 0 || self.raise_timer.elapsed() >= RAISE_TIME {
+            self.raise_timer = Instant::now();
+            self.set_level((self.level + 1).min(MAX_LEVEL));
+        }
+    }
+
+    pub fn stable(&mut self) {
+        if self.level > 0 && self.timer.elapsed() >= STABLE_TIME {
+            self.timer = Instant::now();
+            self.set_level(self.level - 1);
+        }
+    }
+
+    pub fn restore(&mut self) {
+        self.set_level(0);
+    }
+}
+
+impl Looper {
+    pub fn update_devfreq(&mut self, event: &FrameEvent) {
+        if !self.config.config().devfreq_boost || self.config.config().dry_run {
+            self.devfreq_booster.restore();
+            return;
+        }
+
+        if event.frame > event.target {
+            self.devfreq_booster.jank();
+        } else {
+            self.devfreq_booster.stable();
+        }
+    }
+}
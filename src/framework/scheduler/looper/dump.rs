@@ -0,0 +1,133 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    fmt::Write,
+    fs,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use log::{error, info};
+
+use super::Looper;
+use crate::{misc, Extension};
+
+const DUMP_DIR: &str = "/sdcard/Android/fas-rs/dumps";
+
+fn unix_millis(time: SystemTime) -> u128 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis()
+}
+
+impl Looper {
+    /// Write a diagnostic snapshot for bug reports when one was requested by SIGQUIT or the overlay socket
+    pub fn update_dump(&self) {
+        if !misc::take_dump_request() {
+            return;
+        }
+
+        let dir = Path::new(DUMP_DIR);
+        let _ = fs::create_dir_all(dir);
+        let path = dir.join(format!(
+            "dump_{}.txt",
+            unix_millis(SystemTime::now()) / 1000
+        ));
+
+        match fs::write(&path, self.dump_state()) {
+            Ok(()) => info!("State dumped to {}", path.display()),
+            Err(e) => error!("Failed to write state dump {}: {e}", path.display()),
+        }
+    }
+
+    fn dump_state(&self) -> String {
+        let mut dump = String::new();
+        let _ = writeln!(dump, "fas-rs {}", env!("CARGO_PKG_VERSION"));
+        let _ = writeln!(dump, "time={}", unix_millis(SystemTime::now()));
+        let _ = writeln!(dump, "mode={}", self.mode);
+        let _ = writeln!(dump, "state={:?}", self.state);
+        let _ = writeln!(dump, "policy_freq={}", self.controller.policy_freq());
+        let _ = writeln!(dump, "freq_ceiling={}", self.controller.freq_ceiling());
+        let _ = writeln!(
+            dump,
+            "write_conflicts={}",
+            self.controller.write_conflicts()
+        );
+
+        let _ = writeln!(dump, "\n[config]");
+        match self.config.dump() {
+            Ok(config) => dump.push_str(&config),
+            Err(e) => {
+                let _ = writeln!(dump, "failed to serialize: {e}");
+            }
+        }
+        if let Some(e) = self.config.parse_error() {
+            let _ = writeln!(dump, "parse_error={e}");
+        }
+
+        let _ = writeln!(dump, "\n[buffers]");
+        for buffer in self.buffers.values() {
+            let _ = writeln!(dump, "pid={} pkg={}", buffer.pid, buffer.pkg);
+            let _ = writeln!(dump, "  activity={:?}", buffer.activity);
+            let _ = writeln!(dump, "  state={:?}", buffer.state);
+            let _ = writeln!(dump, "  target_fps={:?}", buffer.target_fps);
+            let _ = writeln!(dump, "  current_fps={:.1}", buffer.current_fps);
+            let _ = writeln!(dump, "  avg_time={:?}", buffer.avg_time);
+            let _ = writeln!(dump, "  jitter={:?}", buffer.jitter);
+            let _ = writeln!(dump, "  last_update={:?} ago", buffer.last_update.elapsed());
+            let frametimes: Vec<_> = buffer
+                .frametimes
+                .iter()
+                .map(|frametime| frametime.as_micros().to_string())
+                .collect();
+            let _ = writeln!(dump, "  frametimes_us={}", frametimes.join(","));
+        }
+
+        let _ = writeln!(dump, "\n[freq_writes]");
+        for (time, policy, freq) in self.controller.write_history() {
+            let freq = freq.map_or_else(|| "reset".to_string(), |freq| freq.to_string());
+            let _ = writeln!(dump, "{} policy{policy} {freq}", unix_millis(*time));
+        }
+
+        let _ = writeln!(dump, "\n[decisions]");
+        for decision in self.controller.decisions() {
+            let _ = write!(
+                dump,
+                "{} frametime_us={} factor={:.3} policy_freq={}",
+                unix_millis(decision.time),
+                decision.frametime.as_micros(),
+                decision.factor,
+                decision.policy_freq
+            );
+            for policy in &decision.policies {
+                let freq = policy
+                    .freq
+                    .map_or_else(|| "disabled".to_string(), |freq| freq.to_string());
+                let _ = write!(dump, " policy{}={freq}@{:.2}", policy.policy, policy.weight);
+            }
+            let _ = writeln!(dump);
+        }
+
+        let _ = writeln!(dump, "\n[extensions]");
+        for (path, status) in Extension::status() {
+            let _ = writeln!(dump, "{}: {}", path.display(), status.state);
+            if let Some(e) = status.last_error {
+                let _ = writeln!(dump, "  last_error={e}");
+            }
+        }
+
+        dump
+    }
+}
@@ -0,0 +1,130 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    fmt::{self, Display, Formatter},
+    fs,
+};
+
+use libc::pid_t;
+use log::info;
+
+use super::Looper;
+
+/// Libraries an engine's games load, checked in `/proc/<pid>/maps`
+const ENGINE_LIBS: [(&str, Engine); 4] = [
+    ("/libunity.so", Engine::Unity),
+    ("/libUE4.so", Engine::Unreal),
+    ("/libUnreal.so", Engine::Unreal),
+    ("/libcocos", Engine::Cocos),
+];
+
+const UNITY_THREADS: &[&str] = &["unitymain", "unitygfx", "unitychoreograp"];
+const UNREAL_THREADS: &[&str] = &[
+    "gamethread",
+    "renderthread",
+    "rhithread",
+    "mainthread-ue",
+    "ue4render",
+];
+const COCOS_THREADS: &[&str] = &["glthread", "gl-", "cocos"];
+const GENERIC_RENDER_THREADS: &[&str] = &[
+    "renderthread",
+    "glthread",
+    "gl-",
+    "thread-render",
+    "vkqueue",
+];
+
+/// Engine a game is built on, in-house engines are `Custom` and keep the generic tuning
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Engine {
+    Unity,
+    Unreal,
+    Cocos,
+    Custom,
+}
+
+impl Engine {
+    pub fn detect(pid: pid_t) -> Self {
+        let Ok(maps) = fs::read_to_string(format!("/proc/{pid}/maps")) else {
+            return Self::Custom;
+        };
+
+        ENGINE_LIBS
+            .iter()
+            .find(|(lib, _)| maps.contains(lib))
+            .map_or(Self::Custom, |(_, engine)| *engine)
+    }
+
+    /// Thread name prefixes of the render path, so weighting doesn't pick up worker threads
+    /// that merely match the generic names
+    pub const fn critical_threads(self) -> Option<&'static [&'static str]> {
+        match self {
+            Self::Unity => Some(UNITY_THREADS),
+            Self::Unreal => Some(UNREAL_THREADS),
+            Self::Cocos => Some(COCOS_THREADS),
+            Self::Custom => None,
+        }
+    }
+
+    /// Prefixes of the thread submitting frames, most likely first
+    pub const fn render_threads(self) -> &'static [&'static str] {
+        match self {
+            Self::Unity => &["unitygfx", "unitymain"],
+            Self::Unreal => &["renderthread", "rhithread", "gamethread"],
+            Self::Cocos => &["glthread", "gl-"],
+            Self::Custom => GENERIC_RENDER_THREADS,
+        }
+    }
+
+    /// Garbage collection in Unity and shader compilation in Unreal show up as isolated
+    /// hitches, used when `outlier_ratio` is left off
+    pub const fn outlier_ratio(self) -> f64 {
+        match self {
+            Self::Unity | Self::Unreal => 3.0,
+            Self::Cocos | Self::Custom => 0.0,
+        }
+    }
+}
+
+impl Display for Engine {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let engine = match self {
+            Self::Unity => "unity",
+            Self::Unreal => "unreal",
+            Self::Cocos => "cocos",
+            Self::Custom => "custom",
+        };
+
+        write!(f, "{engine}")
+    }
+}
+
+impl Looper {
+    pub fn update_engine(&mut self) {
+        let engine = self.target_buffer().map(|buffer| buffer.engine);
+        if engine == self.engine {
+            return;
+        }
+
+        if let Some(engine) = engine {
+            info!("Game engine: {engine}");
+        }
+
+        self.controller
+            .set_critical_threads(engine.and_then(Engine::critical_threads));
+        self.engine = engine;
+    }
+}
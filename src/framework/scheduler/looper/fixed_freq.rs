@@ -0,0 +1,43 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use log::info;
+
+use super::{Looper, State};
+
+impl Looper {
+    /// Games with `[fixed_freq]` get static limits instead of FAS, re-applied when the config changes
+    pub fn update_fixed_freq(&mut self) {
+        let limits = self
+            .target_buffer()
+            .filter(|_| self.state == State::Working)
+            .and_then(|buffer| self.config.fixed_freq(&buffer.pkg));
+
+        if limits == self.fixed_freq {
+            return;
+        }
+
+        if let Some(limits) = &limits {
+            info!("Fixed frequency: {limits:?}");
+            self.controller.fix_freqs(limits);
+        } else {
+            info!("Fixed frequency released");
+            if self.state == State::Working {
+                self.controller.init_game(&self.extension);
+            }
+        }
+
+        self.fixed_freq = limits;
+    }
+}
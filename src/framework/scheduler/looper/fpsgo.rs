@@ -0,0 +1,74 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{fs, path::Path};
+
+use libc::pid_t;
+use log::info;
+
+use super::Looper;
+
+const FSTB_FPS_LIST: &str = "/sys/kernel/fpsgo/fstb/fstb_fps_list";
+
+#[derive(Debug)]
+pub struct Fpsgo {
+    available: bool,
+    target: Option<(pid_t, u32)>,
+}
+
+impl Fpsgo {
+    pub fn new() -> Self {
+        Self {
+            available: Path::new(FSTB_FPS_LIST).exists(),
+            target: None,
+        }
+    }
+
+    pub fn set_target(&mut self, target: Option<(pid_t, u32)>) {
+        if target == self.target {
+            return;
+        }
+
+        if let Some((pid, _)) = self.target {
+            let _ = fs::write(FSTB_FPS_LIST, format!("{pid} 0"));
+        }
+
+        if let Some((pid, fps)) = target {
+            info!("Sync target fps {fps} of pid {pid} to fpsgo");
+            let _ = fs::write(FSTB_FPS_LIST, format!("{pid} 1 {fps}-{fps}"));
+        }
+
+        self.target = target;
+    }
+}
+
+impl Looper {
+    pub fn update_fpsgo(&mut self) {
+        if !self.fpsgo.available {
+            return;
+        }
+
+        let target = if self.config.config().fpsgo_coexist
+            && !self.config.config().dry_run
+            && self.state.is_active()
+        {
+            self.target_buffer()
+                .and_then(|buffer| Some((buffer.pid, buffer.target_fps?.round() as u32)))
+        } else {
+            None
+        };
+
+        self.fpsgo.set_target(target);
+    }
+}
@@ -0,0 +1,100 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    cell::OnceCell,
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use log::info;
+
+use super::Looper;
+use crate::trace;
+
+/// Busy percentage nodes of Adreno (kgsl) and Mali (vendor gpu node, `MediaTek` GED)
+const GPU_LOAD_NODES: [&str; 3] = [
+    "/sys/class/kgsl/kgsl-3d0/gpu_busy_percentage",
+    "/sys/kernel/gpu/gpu_busy",
+    "/sys/module/ged/parameters/gpu_loading",
+];
+const REFRESH_TIME: Duration = Duration::from_millis(200);
+
+#[derive(Debug)]
+pub struct GpuLoad {
+    node: OnceCell<Option<PathBuf>>,
+    busy: Option<u32>,
+    timer: Option<Instant>,
+}
+
+impl GpuLoad {
+    pub const fn new() -> Self {
+        Self {
+            node: OnceCell::new(),
+            busy: None,
+            timer: None,
+        }
+    }
+
+    pub const fn busy(&self) -> Option<u32> {
+        self.busy
+    }
+
+    fn node(&self) -> Option<&Path> {
+        self.node
+            .get_or_init(|| {
+                let node = GPU_LOAD_NODES
+                    .iter()
+                    .map(Path::new)
+                    .find(|path| path.exists())
+                    .map(Path::to_path_buf);
+                info!("GPU load node: {node:?}");
+                node
+            })
+            .as_deref()
+    }
+}
+
+impl Looper {
+    pub fn update_gpu_load(&mut self) {
+        if self.gpu.timer.is_some_and(|t| t.elapsed() < REFRESH_TIME) {
+            return;
+        }
+        self.gpu.timer = Some(Instant::now());
+
+        let busy = self
+            .gpu
+            .node()
+            .and_then(|node| fs::read_to_string(node).ok())
+            .and_then(|load| parse_busy(&load));
+        self.gpu.busy = busy;
+        self.controller.set_gpu_busy(busy);
+
+        if let Some(busy) = busy {
+            trace::counter("fas-rs gpu_busy", busy);
+        }
+    }
+}
+
+/// kgsl writes `42 %`, the others a bare number
+fn parse_busy(load: &str) -> Option<u32> {
+    let busy: u32 = load
+        .split(|c: char| !c.is_ascii_digit())
+        .find(|s| !s.is_empty())?
+        .parse()
+        .ok()?;
+
+    Some(busy.min(100))
+}
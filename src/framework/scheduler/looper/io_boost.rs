@@ -0,0 +1,85 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use crate::journal;
+
+const BLOCK_PATH: &str = "/sys/block";
+const DEVFREQ_PATH: &str = "/sys/class/devfreq";
+const BOOST_READ_AHEAD_KB: &str = "2048";
+
+pub struct IoBooster {
+    map: HashMap<PathBuf, String>,
+}
+
+impl IoBooster {
+    pub fn new() -> Self {
+        Self {
+            map: HashMap::new(),
+        }
+    }
+
+    pub fn boost(&mut self) {
+        let Ok(entries) = fs::read_dir(BLOCK_PATH) else {
+            return;
+        };
+
+        for entry in entries.filter_map(std::result::Result::ok) {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if !(name.starts_with("sd") || name.starts_with("mmcblk")) {
+                continue;
+            }
+
+            let path = entry.path().join("queue/read_ahead_kb");
+            if let Ok(last_value) = fs::read_to_string(&path) {
+                self.map.entry(path.clone()).or_insert(last_value);
+                journal::record(&path);
+                let _ = fs::write(&path, BOOST_READ_AHEAD_KB);
+            }
+        }
+
+        self.boost_devfreq();
+    }
+
+    fn boost_devfreq(&mut self) {
+        let Ok(entries) = fs::read_dir(DEVFREQ_PATH) else {
+            return;
+        };
+
+        for entry in entries.filter_map(std::result::Result::ok) {
+            if !entry.file_name().to_string_lossy().contains("ufs") {
+                continue;
+            }
+
+            let path = entry.path().join("min_freq");
+            let Ok(max_freq) = fs::read_to_string(entry.path().join("max_freq")) else {
+                continue;
+            };
+
+            if let Ok(last_value) = fs::read_to_string(&path) {
+                self.map.entry(path.clone()).or_insert(last_value);
+                journal::record(&path);
+                let _ = fs::write(&path, max_freq.trim());
+            }
+        }
+    }
+
+    pub fn restore(&mut self) {
+        for (path, value) in self.map.drain() {
+            let _ = fs::write(path, value);
+        }
+    }
+}
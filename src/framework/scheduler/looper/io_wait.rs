@@ -0,0 +1,138 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    fs,
+    time::{Duration, Instant},
+};
+
+use libc::pid_t;
+use log::info;
+
+use super::{policy::FrameEvent, Looper};
+
+const SAMPLE_TIME: Duration = Duration::from_secs(1);
+const JANK_TIME: Duration = Duration::from_secs(1);
+const BOOST_TIME: Duration = Duration::from_secs(5);
+const USER_HZ: f64 = 100.0;
+const IOWAIT_RATIO: f64 = 0.1;
+
+pub struct IoWait {
+    ticks: Option<u64>,
+    ratio: f64,
+    timer: Instant,
+    jank_since: Option<Instant>,
+    boosted: Option<Instant>,
+}
+
+impl IoWait {
+    pub fn new() -> Self {
+        Self {
+            ticks: None,
+            ratio: 0.0,
+            timer: Instant::now(),
+            jank_since: None,
+            boosted: None,
+        }
+    }
+
+    pub const fn boosted(&self) -> bool {
+        self.boosted.is_some()
+    }
+
+    fn sample(&mut self, pid: pid_t) {
+        let elapsed = self.timer.elapsed();
+        if elapsed < SAMPLE_TIME {
+            return;
+        }
+        self.timer = Instant::now();
+
+        let ticks = blkio_ticks(pid);
+        self.ratio = match (self.ticks, ticks) {
+            (Some(last), Some(ticks)) => {
+                ticks.saturating_sub(last) as f64 / USER_HZ / elapsed.as_secs_f64()
+            }
+            _ => 0.0,
+        };
+        self.ticks = ticks;
+    }
+}
+
+impl Looper {
+    pub fn update_io_boost(&mut self, event: &FrameEvent) {
+        let Some(pid) = self.target_buffer().map(|buffer| buffer.pid) else {
+            return;
+        };
+
+        if !self.config.config().io_boost || self.config.config().dry_run {
+            self.end_io_boost();
+            return;
+        }
+
+        if event.frame > event.target {
+            self.io_wait.jank_since.get_or_insert_with(Instant::now);
+        } else {
+            self.io_wait.jank_since = None;
+        }
+
+        self.io_wait.sample(pid);
+
+        let io_bound = self.io_wait.ratio >= IOWAIT_RATIO;
+        let sustained_jank = self
+            .io_wait
+            .jank_since
+            .is_some_and(|since| since.elapsed() >= JANK_TIME);
+
+        if io_bound && sustained_jank {
+            if self.io_wait.boosted.is_none() {
+                info!(
+                    "Jank with {:.0}% iowait, boost storage",
+                    self.io_wait.ratio * 100.0
+                );
+                self.io_booster.boost();
+            }
+
+            self.io_wait.boosted = Some(Instant::now());
+        } else if self
+            .io_wait
+            .boosted
+            .is_some_and(|since| since.elapsed() >= BOOST_TIME)
+        {
+            self.end_io_boost();
+        }
+    }
+
+    pub fn end_io_boost(&mut self) {
+        self.io_wait.jank_since = None;
+
+        if self.io_wait.boosted.take().is_some() && self.launch_boost.is_none() {
+            self.io_booster.restore();
+        }
+    }
+}
+
+fn blkio_ticks(pid: pid_t) -> Option<u64> {
+    let tasks = fs::read_dir(format!("/proc/{pid}/task")).ok()?;
+
+    let ticks = tasks
+        .filter_map(std::result::Result::ok)
+        .filter_map(|task| fs::read_to_string(task.path().join("stat")).ok())
+        .filter_map(|stat| {
+            let (_, stat) = stat.rsplit_once(')')?;
+            stat.split_whitespace().nth(39)?.parse::<u64>().ok()
+        })
+        .sum();
+
+    Some(ticks)
+}
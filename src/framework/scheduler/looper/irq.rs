@@ -0,0 +1,89 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{collections::HashMap, fs, path::Path};
+
+use log::info;
+
+use crate::{cpu_common, framework::config::IrqConfig, journal};
+
+pub struct IrqAffinity {
+    map: HashMap<String, String>,
+}
+
+impl IrqAffinity {
+    pub fn new() -> Self {
+        Self {
+            map: HashMap::new(),
+        }
+    }
+
+    pub fn apply(&mut self, config: &IrqConfig) {
+        let Some(policy) = config.policy else {
+            return;
+        };
+
+        let Some(cpus) = cpu_common::policies().ok().and_then(|policies| {
+            policies
+                .into_iter()
+                .find(|info| info.policy == policy)
+                .map(|info| info.cpus)
+        }) else {
+            return;
+        };
+        let cpus: Vec<_> = cpus.iter().map(ToString::to_string).collect();
+        let cpus = cpus.join(",");
+
+        let Ok(interrupts) = fs::read_to_string("/proc/interrupts") else {
+            return;
+        };
+
+        for (irq, name) in interrupts.lines().filter_map(parse_interrupt) {
+            let name = name.to_lowercase();
+            if !config
+                .keywords
+                .iter()
+                .any(|keyword| name.contains(keyword.as_str()))
+            {
+                continue;
+            }
+
+            let path = format!("/proc/irq/{irq}/smp_affinity_list");
+            if let Ok(last_value) = fs::read_to_string(&path) {
+                journal::record(Path::new(&path));
+                if fs::write(&path, &cpus).is_ok() {
+                    self.map.entry(path).or_insert(last_value);
+                }
+            }
+        }
+
+        if !self.map.is_empty() {
+            info!("Moved {} irqs to policy{policy}", self.map.len());
+        }
+    }
+
+    pub fn restore(&mut self) {
+        for (path, value) in self.map.drain() {
+            let _ = fs::write(path, value.trim());
+        }
+    }
+}
+
+fn parse_interrupt(line: &str) -> Option<(u32, &str)> {
+    let (irq, rest) = line.trim_start().split_once(':')?;
+    let irq = irq.parse().ok()?;
+    let name = rest.split_whitespace().last()?;
+
+    Some((irq, name))
+}
@@ -0,0 +1,229 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use libc::pid_t;
+use log::{info, warn};
+
+use super::{engine::Engine, Looper, State};
+use crate::{cpu_common, journal};
+
+const CPUSET: &str = "/dev/cpuset";
+const GROUP: &str = "fas_rs_isolated";
+/// Render threads can be recreated on scene changes, so the pick is checked again
+const CHECK_TIME: Duration = Duration::from_secs(2);
+
+/// One core of the fastest cluster taken out of every cpuset and left to the render thread
+pub struct CoreIsolation {
+    cpu: Option<i32>,
+    cpus: HashMap<PathBuf, String>,
+    tid: Option<pid_t>,
+    timer: Option<Instant>,
+}
+
+impl CoreIsolation {
+    pub fn new() -> Self {
+        Self {
+            cpu: None,
+            cpus: HashMap::new(),
+            tid: None,
+            timer: None,
+        }
+    }
+
+    pub const fn cpu(&self) -> Option<i32> {
+        self.cpu
+    }
+
+    fn isolate(&mut self) -> Option<i32> {
+        let cpu = cpu_common::policies()
+            .ok()?
+            .into_iter()
+            .max_by_key(|info| info.freqs.last().copied())?
+            .cpus
+            .last()
+            .copied()?;
+
+        let group = Path::new(CPUSET).join(GROUP);
+        let mems = fs::read_to_string(Path::new(CPUSET).join("mems")).ok()?;
+        if !group.exists() {
+            fs::create_dir(&group)
+                .map_err(|e| warn!("Failed to create cpuset {GROUP}: {e}"))
+                .ok()?;
+        }
+        fs::write(group.join("mems"), mems.trim()).ok()?;
+        fs::write(group.join("cpus"), cpu.to_string()).ok()?;
+
+        for entry in fs::read_dir(CPUSET).ok()?.flatten() {
+            let path = entry.path().join("cpus");
+            if entry.file_name() == GROUP || !path.exists() {
+                continue;
+            }
+
+            let Ok(value) = fs::read_to_string(&path) else {
+                continue;
+            };
+            let cpus: Vec<_> = parse_cpus(&value)
+                .into_iter()
+                .filter(|c| *c != cpu)
+                .collect();
+            // A cpuset can't be left without cpus, nor shrunk below what its children use
+            if cpus.is_empty() {
+                continue;
+            }
+
+            journal::record(&path);
+            if fs::write(&path, format_cpus(&cpus)).is_ok() {
+                self.cpus.insert(path, value);
+            }
+        }
+
+        info!("Isolated cpu{cpu} for the render thread");
+        self.cpu = Some(cpu);
+        Some(cpu)
+    }
+
+    fn pin(&mut self, pid: pid_t, engine: Engine) {
+        // Moving the whole process between cgroups also takes the render thread back
+        if self.tid.is_some_and(|tid| {
+            fs::read_to_string(format!("/proc/{pid}/task/{tid}/cpuset"))
+                .is_ok_and(|cpuset| cpuset.trim().trim_start_matches('/') == GROUP)
+        }) {
+            return;
+        }
+
+        let Some(tid) = render_thread(pid, engine) else {
+            return;
+        };
+
+        let tasks = Path::new(CPUSET).join(GROUP).join("tasks");
+        if fs::write(tasks, tid.to_string()).is_ok() {
+            info!("Render thread {tid} moved to the isolated core");
+            self.tid = Some(tid);
+        }
+    }
+
+    pub fn restore(&mut self) {
+        if self.cpu.take().is_none() {
+            return;
+        }
+
+        for (path, value) in self.cpus.drain() {
+            let _ = fs::write(path, value.trim());
+        }
+
+        if let Some(tid) = self.tid.take() {
+            let _ = fs::write(Path::new(CPUSET).join("top-app/tasks"), tid.to_string());
+        }
+
+        let _ = fs::remove_dir(Path::new(CPUSET).join(GROUP));
+        self.timer = None;
+        info!("Core isolation released");
+    }
+}
+
+/// The busiest thread matching the first render thread name the game has, in-house
+/// engines name them freely so the busiest of all generic names is taken
+fn render_thread(pid: pid_t, engine: Engine) -> Option<pid_t> {
+    let tasks: Vec<_> = fs::read_dir(format!("/proc/{pid}/task"))
+        .ok()?
+        .flatten()
+        .filter_map(|task| {
+            let tid = task.file_name().to_str()?.parse().ok()?;
+            let comm = fs::read_to_string(task.path().join("comm")).ok()?;
+            let runtime: u64 = fs::read_to_string(task.path().join("schedstat"))
+                .ok()?
+                .split_whitespace()
+                .next()?
+                .parse()
+                .ok()?;
+            Some((tid, comm.trim().to_lowercase(), runtime))
+        })
+        .collect();
+
+    let busiest = |prefixes: &[&str]| {
+        tasks
+            .iter()
+            .filter(|(_, comm, _)| prefixes.iter().any(|prefix| comm.starts_with(prefix)))
+            .max_by_key(|(_, _, runtime)| *runtime)
+            .map(|(tid, _, _)| *tid)
+    };
+
+    if engine == Engine::Custom {
+        busiest(engine.render_threads())
+    } else {
+        engine
+            .render_threads()
+            .iter()
+            .find_map(|prefix| busiest(&[*prefix]))
+    }
+}
+
+/// Cpu list format of cpusets, such as `0-3,5`
+fn parse_cpus(value: &str) -> Vec<i32> {
+    value
+        .trim()
+        .split(',')
+        .filter_map(|range| match range.split_once('-') {
+            Some((start, end)) => {
+                Some((start.parse::<i32>().ok()?..=end.parse().ok()?).collect::<Vec<_>>())
+            }
+            None => range.parse().ok().map(|cpu| vec![cpu]),
+        })
+        .flatten()
+        .collect()
+}
+
+fn format_cpus(cpus: &[i32]) -> String {
+    cpus.iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+impl Looper {
+    pub fn update_isolation(&mut self) {
+        let game = self
+            .target_buffer()
+            .filter(|_| self.state == State::Working && !self.config.config().dry_run)
+            .filter(|buffer| self.config.isolate_core(&buffer.pkg))
+            .map(|buffer| (buffer.pid, buffer.engine));
+
+        let Some((pid, engine)) = game else {
+            self.isolation.restore();
+            return;
+        };
+
+        if self
+            .isolation
+            .timer
+            .is_some_and(|t| t.elapsed() < CHECK_TIME)
+        {
+            return;
+        }
+        self.isolation.timer = Some(Instant::now());
+
+        if self.isolation.cpu.is_none() && self.isolation.isolate().is_none() {
+            return;
+        }
+
+        self.isolation.pin(pid, engine);
+    }
+}
@@ -0,0 +1,85 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::{Duration, Instant};
+
+use log::info;
+
+use super::{Looper, State};
+
+/// Below this share of the target the game counts as missing it
+const MISS_RATIO: f64 = 0.9;
+/// Frame rates within this much of a step are considered holding it
+const STEP_MARGIN: f64 = 3.0;
+
+#[derive(Debug)]
+pub struct Ladder {
+    missing_since: Option<Instant>,
+}
+
+impl Ladder {
+    pub const fn new() -> Self {
+        Self {
+            missing_since: None,
+        }
+    }
+}
+
+impl Looper {
+    pub fn update_ladder(&mut self) {
+        let ladder_time = self.config.config().ladder_time;
+        let at_max = self.controller.policy_freq() >= self.controller.freq_ceiling();
+
+        let missing = self
+            .target_buffer()
+            .filter(|_| {
+                ladder_time > 0 && self.state == State::Working && self.fixed_freq.is_none()
+            })
+            .and_then(|buffer| {
+                let target_fps = buffer.target_fps?;
+                (at_max && buffer.current_fps < target_fps * MISS_RATIO)
+                    .then_some((buffer.pid, target_fps))
+            });
+
+        let Some((pid, target_fps)) = missing else {
+            self.ladder.missing_since = None;
+            return;
+        };
+
+        let since = *self.ladder.missing_since.get_or_insert_with(Instant::now);
+        if since.elapsed() < Duration::from_secs(ladder_time) {
+            return;
+        }
+        self.ladder.missing_since = None;
+
+        let Some(buffer) = self.buffers.get_mut(&pid) else {
+            return;
+        };
+
+        let current_fps = buffer.current_fps;
+        let step = buffer
+            .ladder_steps()
+            .into_iter()
+            .filter(|step| *step < target_fps && *step <= current_fps + STEP_MARGIN)
+            .reduce(f64::max);
+
+        if let Some(step) = step {
+            info!(
+                "[{}] holds {current_fps:.1}fps of {target_fps} at max freq for {ladder_time}s, step target down to {step}",
+                buffer.pkg
+            );
+            buffer.set_ladder(Some(step));
+        }
+    }
+}
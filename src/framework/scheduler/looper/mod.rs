@@ -12,42 +12,125 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+#[cfg(feature = "use_ebpf")]
+mod analyzer;
+mod auto_detect;
+mod battery;
+mod boost;
 mod buffer;
 mod clean;
+mod cooldown;
+mod cpuidle;
+mod devfreq;
+mod dump;
+mod engine;
+mod fixed_freq;
+mod fpsgo;
+mod gpu;
+mod io_boost;
+mod io_wait;
+mod irq;
+mod isolate;
+mod ladder;
+mod mode_sync;
+mod overlay;
+mod pacing;
+mod peak_refresh;
 mod policy;
+mod power_budget;
+mod power_supply;
+mod recorder;
+mod refresh_rate;
+mod relax;
+mod replay;
+mod restart;
+mod session;
+mod status;
+mod thermal;
+mod toggle;
 mod utils;
+mod video;
+mod vsync;
 
 #[cfg(feature = "use_binder")]
 use std::sync::mpsc::{Receiver, RecvTimeoutError};
-use std::time::{Duration, Instant};
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
 
 #[cfg(feature = "use_ebpf")]
 use frame_analyzer::Analyzer;
-#[cfg(debug_assertions)]
-use log::debug;
-use log::info;
+use libc::pid_t;
+use log::{debug, info};
 
-use super::{topapp::TimedWatcher, FasData};
+use super::{sensor::Sensor, topapp::TimedWatcher, FasData};
 #[cfg(feature = "use_binder")]
 use crate::framework::error::Error;
 use crate::{
     framework::{
-        config::Config,
+        config::{BatteryRule, Config, ThermalRule},
         error::Result,
         node::{Mode, Node},
+        utils::get_process_name,
         Extension,
     },
-    Controller,
+    logger, misc, trace, Controller,
 };
 
-use buffer::{Buffer, BufferState};
+#[cfg(feature = "use_ebpf")]
+use analyzer::AnalyzerHealth;
+use auto_detect::AutoDetect;
+use buffer::{Buffer, BufferPool, BufferState};
+pub use clean::detected_daemons;
 use clean::Cleaner;
+use cpuidle::CpuIdle;
+use devfreq::DevfreqBooster;
+use engine::Engine;
+use fpsgo::Fpsgo;
+use gpu::GpuLoad;
+use io_boost::IoBooster;
+use io_wait::IoWait;
+use irq::IrqAffinity;
+use isolate::CoreIsolation;
+use ladder::Ladder;
+use mode_sync::ModeSync;
+use overlay::Overlay;
+pub use overlay::SOCKET_NAME as CONTROL_SOCKET;
+use pacing::Pacing;
+use peak_refresh::PeakRefresh;
+use power_budget::PowerBudget;
+use power_supply::PowerSupply;
+use recorder::Recorder;
+use refresh_rate::RefreshRate;
+use relax::Relax;
+pub use replay::replay;
+use restart::Restarting;
+use session::Session;
+use thermal::Thermal;
+use toggle::Toggle;
+use video::Video;
+use vsync::Vsync;
 
-#[derive(PartialEq)]
+/// Housekeeping cadence while a game is handled, frames wake the loop in between
+const ACTIVE_TICK: Duration = Duration::from_millis(50);
+/// Housekeeping cadence with no game
+const IDLE_TICK: Duration = Duration::from_millis(500);
+
+#[derive(Debug, PartialEq)]
 enum State {
     NotWorking,
     Waiting,
     Working,
+    /// Frequencies held at a reduced ceiling after heavy thermal throttling
+    Cooldown,
+}
+
+impl State {
+    /// fas-rs owns the game, whether it is controlling frequencies or cooling down
+    const fn is_active(&self) -> bool {
+        matches!(self, Self::Working | Self::Cooldown)
+    }
 }
 
 pub struct Looper {
@@ -55,16 +138,55 @@ pub struct Looper {
     rx: Receiver<FasData>,
     #[cfg(feature = "use_ebpf")]
     analyzer: Analyzer,
+    #[cfg(feature = "use_ebpf")]
+    analyzer_health: AnalyzerHealth,
     config: Config,
     node: Node,
+    power_supply: PowerSupply,
+    battery_rule: Option<BatteryRule>,
+    charging_mode: Option<Mode>,
+    power_budget: PowerBudget,
+    thermal: Thermal,
+    thermal_rule: Option<ThermalRule>,
+    gpu: GpuLoad,
+    status_timer: Instant,
+    fpsgo: Fpsgo,
+    recorder: Recorder,
     extension: Extension,
+    sensor: Sensor,
     mode: Mode,
+    mode_sync: ModeSync,
+    toggle: Toggle,
     controller: Controller,
     windows_watcher: TimedWatcher,
     cleaner: Cleaner,
-    buffer: Option<Buffer>,
+    cpuidle: CpuIdle,
+    irq_affinity: IrqAffinity,
+    isolation: CoreIsolation,
+    buffers: HashMap<pid_t, Buffer>,
+    buffer_pool: BufferPool,
+    restarting: Option<Restarting>,
     state: State,
     delay_timer: Instant,
+    valid_frames: u32,
+    cooldown_timer: Instant,
+    next_tick: Instant,
+    launch_boost: Option<Instant>,
+    fixed_freq: Option<HashMap<i32, (isize, isize)>>,
+    engine: Option<Engine>,
+    io_booster: IoBooster,
+    io_wait: IoWait,
+    devfreq_booster: DevfreqBooster,
+    relax: Relax,
+    pacing: Pacing,
+    session: Session,
+    ladder: Ladder,
+    auto_detect: AutoDetect,
+    refresh_rate: RefreshRate,
+    peak_refresh: PeakRefresh,
+    overlay: Overlay,
+    video: Video,
+    vsync: Vsync,
 }
 
 impl Looper {
@@ -74,6 +196,7 @@ impl Looper {
         config: Config,
         node: Node,
         extension: Extension,
+        sensor: Sensor,
         controller: Controller,
     ) -> Self {
         Self {
@@ -81,54 +204,194 @@ impl Looper {
             rx,
             #[cfg(feature = "use_ebpf")]
             analyzer,
+            #[cfg(feature = "use_ebpf")]
+            analyzer_health: AnalyzerHealth::new(),
             config,
             node,
+            power_supply: PowerSupply::new(),
+            battery_rule: None,
+            charging_mode: None,
+            power_budget: PowerBudget::new(),
+            thermal: Thermal::new(),
+            thermal_rule: None,
+            gpu: GpuLoad::new(),
+            status_timer: Instant::now(),
+            fpsgo: Fpsgo::new(),
+            recorder: Recorder::new(),
             extension,
+            sensor,
             mode: Mode::Balance,
+            mode_sync: ModeSync::new(),
+            toggle: Toggle::default(),
             controller,
             windows_watcher: TimedWatcher::new(),
             cleaner: Cleaner::new(),
-            buffer: None,
+            cpuidle: CpuIdle::new(),
+            irq_affinity: IrqAffinity::new(),
+            isolation: CoreIsolation::new(),
+            buffers: HashMap::new(),
+            buffer_pool: BufferPool::default(),
+            restarting: None,
             state: State::NotWorking,
             delay_timer: Instant::now(),
+            valid_frames: 0,
+            cooldown_timer: Instant::now(),
+            next_tick: Instant::now(),
+            launch_boost: None,
+            fixed_freq: None,
+            engine: None,
+            io_booster: IoBooster::new(),
+            io_wait: IoWait::new(),
+            devfreq_booster: DevfreqBooster::new(),
+            relax: Relax::Normal,
+            pacing: Pacing::new(),
+            session: Session::new(),
+            ladder: Ladder::new(),
+            auto_detect: AutoDetect::new(),
+            refresh_rate: RefreshRate::new(),
+            peak_refresh: PeakRefresh::new(),
+            overlay: Overlay::new(),
+            video: Video::new(),
+            vsync: Vsync::new(),
         }
     }
 
     pub fn enter_loop(&mut self) -> Result<()> {
         loop {
-            self.switch_mode();
+            if misc::shutdown_requested() {
+                self.shutdown();
+                return Ok(());
+            }
 
-            #[cfg(feature = "use_ebpf")]
-            let _ = self.update_analyzer();
-            self.retain_topapp();
+            if Instant::now() >= self.next_tick {
+                self.housekeeping();
+                self.next_tick = Instant::now() + self.tick();
+            }
 
-            let target_fps = self.buffer.as_ref().and_then(|b| b.target_fps);
+            let target = self.target_buffer().map(|b| (b.pid, b.target_fps));
+
+            // Sleep until a frame arrives or the next housekeeping is due, so nothing but
+            // frames wakes the loop in between
+            let timeout = self
+                .next_tick
+                .saturating_duration_since(Instant::now())
+                .max(Duration::from_millis(1));
 
             #[cfg(feature = "use_binder")]
-            let fas_data = self.recv_message()?;
+            let fas_data = self.recv_message(timeout)?;
             #[cfg(feature = "use_ebpf")]
-            let fas_data = self.recv_message();
+            let fas_data = self.recv_message(timeout);
 
-            if self.windows_watcher.visible_freeform_window() {
+            if self.windows_watcher.visible_freeform_window() && !self.game_visible() {
                 self.disable_fas();
                 continue;
             }
 
             if let Some(data) = fas_data {
+                self.sensor.frame_received();
+                self.record_frame(&data);
+
                 if let Some(state) = self.buffer_update(&data) {
-                    match state {
-                        BufferState::Usable => self.do_policy(target_fps),
-                        BufferState::Unusable => self.disable_fas(),
+                    if target.is_some_and(|(pid, _)| pid == data.pid) {
+                        self.valid_frames = match state {
+                            BufferState::Usable => self.valid_frames.saturating_add(1),
+                            BufferState::Unusable => 0,
+                        };
+                    }
+
+                    match (state, target) {
+                        (BufferState::Usable, Some((pid, target_fps))) if pid == data.pid => {
+                            self.do_policy(target_fps);
+                        }
+                        (BufferState::Unusable, Some((pid, _))) if pid == data.pid => {
+                            self.disable_fas();
+                        }
+                        _ => (),
                     }
                 }
-            } else if let Some(buffer) = self.buffer.as_mut() {
-                buffer.additional_frametime();
+            } else {
+                for buffer in self.buffers.values_mut() {
+                    buffer.additional_frametime();
+                }
             }
+
+            // The first frames of a game switch to the active cadence right away
+            self.next_tick = self.next_tick.min(Instant::now() + self.tick());
+        }
+    }
+
+    /// Everything that is not driven by frames: config, topapp, sensors, timers
+    fn housekeeping(&mut self) {
+        logger::set_level(self.config.config().log_level);
+        trace::set_enabled(self.config.config().trace_marker);
+        self.controller.set_dry_run(self.config.config().dry_run);
+        self.controller.set_weighting(
+            Duration::from_millis(self.config.config().weighting_interval),
+            Duration::from_millis(self.config.config().weighting_window),
+            self.config.config().weighting_decay,
+        );
+        let freq_control = self.target_buffer().map_or_else(
+            || self.config.config().freq_control,
+            |buffer| self.config.freq_control(&buffer.pkg),
+        );
+        self.controller.set_freq_control(freq_control);
+        self.update_battery();
+        self.update_thermal();
+        self.update_cooldown();
+        self.verify_writes();
+        self.update_gpu_load();
+        self.update_mode_sync();
+        self.switch_mode();
+        self.update_auto_detect();
+        self.update_sensor();
+
+        #[cfg(feature = "use_ebpf")]
+        self.check_analyzer();
+        self.update_video();
+        self.retain_topapp();
+        self.update_engine();
+        self.update_isolation();
+        self.update_session();
+        self.update_activity();
+        self.update_peak_refresh();
+        self.update_refresh_rate();
+        self.update_vsync();
+        self.update_ladder();
+        self.update_pacing();
+        self.update_fps_cap();
+        self.check_launch_boost();
+        self.update_fixed_freq();
+        self.update_power_budget();
+        self.update_fpsgo();
+        self.update_status();
+        self.update_overlay();
+        self.update_dump();
+        self.extension
+            .set_package(self.target_buffer().map(|buffer| buffer.pkg.as_str()));
+    }
+
+    fn verify_writes(&mut self) {
+        if self.state == State::Working && self.fixed_freq.is_none() {
+            self.controller.verify_writes();
+        }
+    }
+
+    /// Housekeeping runs often while a game is handled and rarely when idle
+    fn tick(&self) -> Duration {
+        if self.buffers.is_empty() {
+            IDLE_TICK
+        } else {
+            ACTIVE_TICK
         }
     }
 
     fn switch_mode(&mut self) {
-        if let Ok(new_mode) = self.node.get_mode() {
+        let new_mode = match self.mode_override() {
+            Some(mode) => Ok(mode),
+            None => self.node.get_mode(),
+        };
+
+        if let Ok(new_mode) = new_mode {
             if self.mode != new_mode {
                 info!(
                     "Switch mode: {} -> {}",
@@ -137,7 +400,7 @@ impl Looper {
                 );
                 self.mode = new_mode;
 
-                if self.state == State::Working {
+                if self.state == State::Working && self.fixed_freq.is_none() {
                     self.controller.init_game(&self.extension);
                 }
             }
@@ -145,12 +408,17 @@ impl Looper {
     }
 
     #[cfg(feature = "use_binder")]
-    fn recv_message(&self) -> Result<Option<FasData>> {
-        match self.rx.recv_timeout(Duration::from_millis(500)) {
+    fn recv_message(&self, timeout: Duration) -> Result<Option<FasData>> {
+        if !self.sensor.kind().is_native() {
+            while self.rx.try_recv().is_ok() {}
+            return Ok(self.sensor.recv_timeout(timeout));
+        }
+
+        match self.rx.recv_timeout(timeout) {
             Ok(m) => Ok(Some(m)),
             Err(e) => {
                 if e == RecvTimeoutError::Disconnected {
-                    return Err(Error::Other("Binder Server Disconnected"));
+                    return Err(Error::Sensor("Binder Server Disconnected"));
                 }
 
                 Ok(None)
@@ -159,15 +427,29 @@ impl Looper {
     }
 
     #[cfg(feature = "use_ebpf")]
-    fn recv_message(&mut self) -> Option<FasData> {
-        self.analyzer
-            .recv_timeout(Duration::from_millis(500))
-            .map(|(pid, frametime)| FasData { pid, frametime })
+    fn recv_message(&mut self, timeout: Duration) -> Option<FasData> {
+        if !self.sensor.kind().is_native() {
+            return self.sensor.recv_timeout(timeout);
+        }
+
+        let data = self
+            .analyzer
+            .recv_timeout(timeout)
+            .map(|(pid, frametime)| FasData { pid, frametime });
+        if data.is_some() {
+            self.analyzer_health.event_received();
+        }
+
+        data
     }
 
     #[cfg(feature = "use_ebpf")]
     fn update_analyzer(&mut self) -> Result<()> {
-        use crate::framework::utils::get_process_name;
+        use crate::framework::config::SensorKind;
+
+        if self.sensor.kind() != SensorKind::Ebpf {
+            return Ok(());
+        }
 
         for pid in self.windows_watcher.topapp_pids().iter().copied() {
             let pkg = get_process_name(pid)?;
@@ -179,27 +461,68 @@ impl Looper {
         Ok(())
     }
 
-    fn do_policy(&mut self, target_fps: Option<u32>) {
-        if self.state != State::Working {
-            #[cfg(debug_assertions)]
+    fn update_sensor(&mut self) {
+        let game = self
+            .windows_watcher
+            .topapp_pids()
+            .clone()
+            .into_iter()
+            .find_map(|pid| {
+                let pkg = get_process_name(pid).ok()?;
+                self.config.need_fas(&pkg).then_some((pid, pkg))
+            });
+
+        self.sensor.update(game, &self.config);
+    }
+
+    /// With `multi_window` a pop-up window only stops fas when none of the tracked games is
+    /// among the visible tasks
+    fn game_visible(&mut self) -> bool {
+        if !self.config.config().multi_window {
+            return false;
+        }
+
+        let visible = self.windows_watcher.visible_packages();
+        self.buffers
+            .values()
+            .any(|buffer| visible.contains(&buffer.pkg))
+    }
+
+    fn target_buffer(&self) -> Option<&Buffer> {
+        self.buffers
+            .values()
+            .min_by(|a, b| a.fps_ratio().total_cmp(&b.fps_ratio()))
+    }
+
+    fn do_policy(&mut self, target_fps: Option<f64>) {
+        if self.state != State::Working || self.fixed_freq.is_some() {
             debug!("Not running policy!");
             return;
         }
 
         let Some(event) = self
-            .buffer
-            .as_ref()
+            .target_buffer()
             .and_then(|buffer| buffer.event(&self.config, self.mode))
         else {
             self.disable_fas();
             return;
         };
 
-        let target_fps = target_fps.unwrap_or(120);
+        let event = self.relax_event(event);
+        self.update_devfreq(&event);
+        self.update_io_boost(&event);
+        let target_fps = target_fps.unwrap_or(120.0);
 
-        let factor = Controller::scale_factor(target_fps, event.frame, event.target);
-        if let Some(process) = self.buffer.as_ref().map(|b| b.pid) {
-            self.controller.fas_update_freq(process, factor);
+        let factor = event.factor(target_fps, &self.config);
+        let factor = self.pacing_factor(factor);
+        trace::counter("fas-rs scale_factor", (factor * 1000.0) as i64);
+        if let Some((process, frametime)) = self
+            .target_buffer()
+            .map(|b| (b.pid, b.frametimes.front().unwrap_or_default()))
+        {
+            self.controller.fas_update_freq(process, frametime, factor);
         }
+
+        self.trace_metrics();
     }
 }
@@ -0,0 +1,130 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    fs,
+    process::Command,
+    time::{Duration, Instant},
+};
+
+use log::info;
+use serde::Deserialize;
+
+use super::Looper;
+use crate::framework::node::Mode;
+
+/// Per-app performance modes set in scene
+const SCENE_APP_MODES: &str = "/data/data/com.omarea.vtools/shared_prefs/powercfg.xml";
+const REFRESH_TIME: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Deserialize)]
+#[serde(rename = "map")]
+struct SceneModeList {
+    #[serde(rename = "string", default)]
+    apps: Vec<SceneAppMode>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SceneAppMode {
+    #[serde(rename = "@name")]
+    pkg: String,
+    #[serde(rename = "$text", default)]
+    mode: String,
+}
+
+#[derive(Debug)]
+pub struct ModeSync {
+    pkg: Option<String>,
+    mode: Option<Mode>,
+    timer: Option<Instant>,
+}
+
+impl ModeSync {
+    pub const fn new() -> Self {
+        Self {
+            pkg: None,
+            mode: None,
+            timer: None,
+        }
+    }
+
+    pub const fn mode(&self) -> Option<Mode> {
+        self.mode
+    }
+}
+
+impl Looper {
+    pub fn update_mode_sync(&mut self) {
+        if !self.config.config().mode_sync {
+            self.mode_sync = ModeSync::new();
+            return;
+        }
+
+        let pkg = self.target_buffer().map(|buffer| buffer.pkg.clone());
+        if pkg == self.mode_sync.pkg
+            && self
+                .mode_sync
+                .timer
+                .is_some_and(|t| t.elapsed() < REFRESH_TIME)
+        {
+            return;
+        }
+        self.mode_sync.timer = Some(Instant::now());
+
+        let mode = pkg
+            .as_deref()
+            .and_then(|pkg| scene_mode(pkg).or_else(|| game_mode(pkg)));
+        if let (Some(pkg), Some(mode)) = (&pkg, mode) {
+            if self.mode_sync.mode != Some(mode) {
+                info!("Sync mode of [{pkg}]: {mode}");
+            }
+        }
+
+        self.mode_sync.pkg = pkg;
+        self.mode_sync.mode = mode;
+    }
+}
+
+/// Same names as the scene powercfg entry in `module/vtools/powercfg.sh`
+fn scene_mode(pkg: &str) -> Option<Mode> {
+    let modes = fs::read_to_string(SCENE_APP_MODES).ok()?;
+    let modes: SceneModeList = quick_xml::de::from_str(&modes).ok()?;
+    let app = modes.apps.into_iter().find(|app| app.pkg == pkg)?;
+
+    match app.mode.trim() {
+        "init" | "fast" | "pedestal" => Some(Mode::Fast),
+        "powersave" | "standby" => Some(Mode::Powersave),
+        "balance" => Some(Mode::Balance),
+        "performance" => Some(Mode::Performance),
+        _ => None,
+    }
+}
+
+/// Android 12+ game mode, `cmd game list-modes` prints `<pkg> current mode: <mode>, ...`
+fn game_mode(pkg: &str) -> Option<Mode> {
+    let output = Command::new("cmd")
+        .args(["game", "list-modes", pkg])
+        .output()
+        .ok()?;
+    let output = String::from_utf8_lossy(&output.stdout);
+    let (_, mode) = output.split_once("current mode: ")?;
+    let mode = mode.split([',', '\n']).next()?.trim();
+
+    match mode {
+        "battery" => Some(Mode::Powersave),
+        "standard" => Some(Mode::Balance),
+        "performance" => Some(Mode::Performance),
+        _ => None,
+    }
+}
@@ -0,0 +1,297 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    fs,
+    io::{ErrorKind, Read, Write},
+    mem,
+    net::Shutdown,
+    os::{
+        fd::AsRawFd,
+        linux::net::SocketAddrExt,
+        unix::net::{SocketAddr, UnixListener, UnixStream},
+    },
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use log::{error, info, warn};
+use serde_json::json;
+
+use super::Looper;
+use crate::{
+    framework::{config::Config, error::Error},
+    misc,
+};
+
+/// Abstract unix socket, so no file has to be created or cleaned up. It is also the control
+/// socket the `WebUI` backend goes through
+pub const SOCKET_NAME: &[u8] = b"fas_rs_overlay";
+/// Longest request line, a whole config sent with `config-set` has to fit
+const MAX_REQUEST: usize = 2 * 1024 * 1024;
+/// A reply may be larger than the socket buffer, a client not reading it is cut off after this
+const REPLY_TIMEOUT: Duration = Duration::from_secs(1);
+
+struct Client {
+    stream: UnixStream,
+    /// From `SO_PEERCRED` on accept, `None` if it couldn't be read
+    uid: Option<u32>,
+    /// Whether dropped commands of this client were logged already
+    warned: bool,
+    /// Start of a request line whose newline hasn't arrived yet
+    pending: Vec<u8>,
+}
+
+/// Pushes one json line per interval to every connected overlay app
+pub struct Overlay {
+    clients: Option<Arc<Mutex<Vec<Client>>>>,
+    timer: Instant,
+}
+
+impl Overlay {
+    pub fn new() -> Self {
+        Self {
+            clients: None,
+            timer: Instant::now(),
+        }
+    }
+
+    fn listen() -> Option<Arc<Mutex<Vec<Client>>>> {
+        let addr = SocketAddr::from_abstract_name(SOCKET_NAME).ok()?;
+        let listener = UnixListener::bind_addr(&addr)
+            .map_err(|e| error!("Failed to bind overlay socket: {e}"))
+            .ok()?;
+        let clients = Arc::new(Mutex::new(Vec::new()));
+        let accepted = clients.clone();
+
+        thread::Builder::new()
+            .name("OverlaySocket".into())
+            .spawn(move || {
+                misc::set_idle_priority();
+                for stream in listener.incoming().filter_map(Result::ok) {
+                    if stream.set_nonblocking(true).is_ok() {
+                        let uid = peer_uid(&stream);
+                        accepted.lock().unwrap().push(Client {
+                            stream,
+                            uid,
+                            warned: false,
+                            pending: Vec::new(),
+                        });
+                    }
+                }
+            })
+            .ok()?;
+
+        info!("Overlay socket listening on @fas_rs_overlay");
+        Some(clients)
+    }
+
+    /// Clients may send a `dump` line to request a state dump, other lines are returned
+    /// with the connection to answer them on. Anyone may read the pushed stats, requests
+    /// are only taken from root, shell and `[control] uids`. Closed clients are dropped
+    fn poll_requests(&self, config: &Config) -> Vec<(UnixStream, String)> {
+        let Some(clients) = &self.clients else {
+            return Vec::new();
+        };
+
+        let mut requests = Vec::new();
+        let mut buf = [0; 4096];
+        clients.lock().unwrap().retain_mut(|client| {
+            let allowed = client.uid.is_some_and(|uid| config.control_allowed(uid));
+            let mut received = 0;
+            loop {
+                match client.stream.read(&mut buf) {
+                    Ok(0) => return false,
+                    Ok(len) => {
+                        received += len;
+                        if allowed {
+                            client.pending.extend_from_slice(&buf[..len]);
+                        }
+                    }
+                    Err(e) if e.kind() == ErrorKind::Interrupted => (),
+                    Err(_) => break,
+                }
+
+                if received > MAX_REQUEST {
+                    warn!(
+                        "Dropped a control client of uid {:?}, request too large",
+                        client.uid
+                    );
+                    return false;
+                }
+            }
+
+            if !allowed {
+                if received > 0 && !client.warned {
+                    warn!("Ignored overlay commands from uid {:?}", client.uid);
+                    client.warned = true;
+                }
+                return true;
+            }
+
+            while let Some(end) = client.pending.iter().position(|byte| *byte == b'\n') {
+                let line: Vec<_> = client.pending.drain(..=end).collect();
+                match String::from_utf8_lossy(&line).trim() {
+                    "" => (),
+                    "dump" => misc::request_dump(),
+                    request => {
+                        if let Ok(stream) = client.stream.try_clone() {
+                            requests.push((stream, request.to_string()));
+                        }
+                    }
+                }
+            }
+
+            true
+        });
+
+        requests
+    }
+
+    fn push(&self, line: &str) {
+        let Some(clients) = &self.clients else {
+            return;
+        };
+
+        // Clients too slow to drain the socket are dropped rather than blocking the looper
+        clients.lock().unwrap().retain_mut(|client| {
+            match client.stream.write_all(line.as_bytes()) {
+                Ok(()) => true,
+                Err(e) if e.kind() == ErrorKind::Interrupted => true,
+                Err(_) => false,
+            }
+        });
+    }
+}
+
+impl Looper {
+    pub fn update_overlay(&mut self) {
+        let interval = self.config.config().overlay_interval;
+        // The WebUI backend reaches the daemon through this socket as well
+        if interval == 0 && self.config.config().webui_port == 0 {
+            return;
+        }
+
+        if self.overlay.clients.is_none() {
+            self.overlay.clients = Overlay::listen();
+        }
+
+        // Requests are handled every tick so a tile press applies right away
+        for (mut stream, request) in self.overlay.poll_requests(&self.config) {
+            self.handle_request(&mut stream, &request);
+        }
+
+        if interval == 0 || self.overlay.timer.elapsed() < Duration::from_millis(interval) {
+            return;
+        }
+        self.overlay.timer = Instant::now();
+
+        let buffer = self.target_buffer();
+        let freqs: serde_json::Map<_, _> = self
+            .controller
+            .cur_freqs()
+            .into_iter()
+            .map(|(policy, freq)| (policy.to_string(), json!(freq)))
+            .collect();
+        let line = json!({
+            "pkg": buffer.map(|buffer| buffer.pkg.as_str()),
+            "fps": buffer.map(|buffer| (buffer.current_fps * 10.0).round() / 10.0),
+            "target": buffer.and_then(|buffer| buffer.target_fps),
+            "freqs": freqs,
+            "temp": self.thermal.temp(),
+            "mode": self.mode.to_string(),
+            "paused": self.toggle.paused(),
+        });
+
+        self.overlay.push(&format!("{line}\n"));
+    }
+}
+
+impl Looper {
+    /// `stats`, `config` and `config-set <json string>` are answered with a json line whose
+    /// `reply` is the verb, every other request is a quick toggle command
+    fn handle_request(&mut self, stream: &mut UnixStream, request: &str) {
+        let (verb, arg) = request.split_once(' ').unwrap_or((request, ""));
+        let reply = match verb {
+            "stats" => {
+                let stats: serde_json::Map<_, _> = self
+                    .status()
+                    .lines()
+                    .filter_map(|line| line.split_once('='))
+                    .map(|(key, value)| (key.to_string(), json!(value)))
+                    .collect();
+                json!({ "reply": "stats", "stats": stats })
+            }
+            "config" => match fs::read_to_string(self.config.path()) {
+                Ok(config) => json!({ "reply": "config", "config": config }),
+                Err(e) => json!({ "reply": "config", "error": e.to_string() }),
+            },
+            "config-set" => self.set_config(arg),
+            _ => {
+                self.quick_toggle(request);
+                return;
+            }
+        };
+
+        let _ = stream.set_nonblocking(false);
+        let _ = stream.set_write_timeout(Some(REPLY_TIMEOUT));
+        if let Err(e) = stream.write_all(format!("{reply}\n").as_bytes()) {
+            warn!("Failed to answer '{verb}' on the control socket: {e}");
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+        let _ = stream.set_nonblocking(true);
+    }
+
+    /// The config comes as a json string, it is only written if it parses
+    fn set_config(&self, arg: &str) -> serde_json::Value {
+        let Ok(source) = serde_json::from_str::<String>(arg) else {
+            return json!({ "reply": "config-set", "rejected": "the config must be a json string" });
+        };
+
+        match Config::write_checked(self.config.path(), &source) {
+            Ok(()) => {
+                info!("Config updated over the control socket");
+                json!({ "reply": "config-set" })
+            }
+            Err(Error::Config(e)) => json!({ "reply": "config-set", "rejected": e.to_string() }),
+            Err(e) => {
+                error!("Failed to write config from the control socket: {e}");
+                json!({ "reply": "config-set", "error": e.to_string() })
+            }
+        }
+    }
+}
+
+/// Uid of the process on the other end of `stream`
+fn peer_uid(stream: &UnixStream) -> Option<u32> {
+    let mut cred = libc::ucred {
+        pid: 0,
+        uid: 0,
+        gid: 0,
+    };
+    let mut len = mem::size_of::<libc::ucred>() as libc::socklen_t;
+
+    let ret = unsafe {
+        libc::getsockopt(
+            stream.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            (&raw mut cred).cast(),
+            &raw mut len,
+        )
+    };
+
+    (ret == 0).then_some(cred.uid)
+}
@@ -0,0 +1,95 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::{Duration, Instant};
+
+use log::info;
+
+use super::{Looper, State};
+use crate::api::v2::ApiV2;
+
+/// Above this share of the target the game renders more frames than asked for
+const OVERSHOOT_RATIO: f64 = 1.1;
+/// How long the overshoot has to last before pacing kicks in
+const OVERSHOOT_TIME: Duration = Duration::from_secs(3);
+/// Downward steps are this much larger while pacing
+const PACING_STEP: f64 = 3.0;
+const REPORT_TIME: Duration = Duration::from_secs(5);
+
+/// Experimental: uncapped menus run far above target, step down harder and tell extensions
+#[derive(Debug)]
+pub struct Pacing {
+    since: Option<Instant>,
+    active: bool,
+    reported: Option<Instant>,
+}
+
+impl Pacing {
+    pub const fn new() -> Self {
+        Self {
+            since: None,
+            active: false,
+            reported: None,
+        }
+    }
+}
+
+impl Looper {
+    pub fn update_pacing(&mut self) {
+        let overshoot = self
+            .target_buffer()
+            .filter(|_| self.config.config().frame_pacing && self.state == State::Working)
+            .and_then(|buffer| {
+                let target_fps = buffer.target_fps?;
+                (buffer.current_fps > target_fps * OVERSHOOT_RATIO)
+                    .then(|| (buffer.current_fps, target_fps, buffer.pkg.clone()))
+            });
+
+        let Some((current_fps, target_fps, pkg)) = overshoot else {
+            if self.pacing.active {
+                info!("Frame pacing released");
+            }
+            self.pacing = Pacing::new();
+            return;
+        };
+
+        let since = *self.pacing.since.get_or_insert_with(Instant::now);
+        if since.elapsed() < OVERSHOOT_TIME {
+            return;
+        }
+
+        if !self.pacing.active {
+            info!("[{pkg}] renders {current_fps:.1}fps of {target_fps}, frame pacing");
+            self.pacing.active = true;
+        }
+
+        if self
+            .pacing
+            .reported
+            .is_none_or(|reported| reported.elapsed() >= REPORT_TIME)
+        {
+            self.pacing.reported = Some(Instant::now());
+            self.extension
+                .tigger_extentions(ApiV2::FpsOvershoot(current_fps, target_fps, pkg));
+        }
+    }
+
+    pub fn pacing_factor(&self, factor: f64) -> f64 {
+        if self.pacing.active && factor < 0.0 {
+            factor * PACING_STEP
+        } else {
+            factor
+        }
+    }
+}
@@ -0,0 +1,104 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::process::Command;
+
+use log::info;
+
+use super::Looper;
+
+const PEAK_REFRESH_RATE: &str = "peak_refresh_rate";
+const MIN_REFRESH_RATE: &str = "min_refresh_rate";
+
+#[derive(Debug, Default)]
+pub struct PeakRefresh {
+    pinned: Option<u32>,
+    saved: Option<(Option<String>, Option<String>)>,
+}
+
+impl PeakRefresh {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn pin(&mut self, rate: u32) {
+        if self.saved.is_none() {
+            self.saved = Some((
+                get_setting(PEAK_REFRESH_RATE),
+                get_setting(MIN_REFRESH_RATE),
+            ));
+        }
+
+        info!("Pin display refresh rate to {rate}Hz");
+        let value = format!("{rate}.0");
+        put_setting(PEAK_REFRESH_RATE, Some(&value));
+        put_setting(MIN_REFRESH_RATE, Some(&value));
+        self.pinned = Some(rate);
+    }
+
+    pub fn restore(&mut self) {
+        if let Some((peak, min)) = self.saved.take() {
+            info!("Restore display refresh rate");
+            put_setting(PEAK_REFRESH_RATE, peak.as_deref());
+            put_setting(MIN_REFRESH_RATE, min.as_deref());
+        }
+
+        self.pinned = None;
+    }
+}
+
+impl Looper {
+    pub fn update_peak_refresh(&mut self) {
+        let rate = if self.state.is_active() && !self.config.config().dry_run {
+            self.target_buffer()
+                .and_then(|buffer| self.config.refresh_rate(&buffer.pkg))
+        } else {
+            None
+        };
+
+        if rate == self.peak_refresh.pinned {
+            return;
+        }
+
+        match rate {
+            Some(rate) => self.peak_refresh.pin(rate),
+            None => self.peak_refresh.restore(),
+        }
+    }
+}
+
+fn get_setting(key: &str) -> Option<String> {
+    let output = Command::new("settings")
+        .args(["get", "system", key])
+        .output()
+        .ok()?;
+    let value = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+    (!value.is_empty() && value != "null").then_some(value)
+}
+
+fn put_setting(key: &str, value: Option<&str>) {
+    let _ = value.map_or_else(
+        || {
+            Command::new("settings")
+                .args(["delete", "system", key])
+                .status()
+        },
+        |value| {
+            Command::new("settings")
+                .args(["put", "system", key, value])
+                .status()
+        },
+    );
+}
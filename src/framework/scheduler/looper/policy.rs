@@ -14,30 +14,47 @@
 
 use std::time::Duration;
 
-#[cfg(debug_assertions)]
 use log::debug;
 
 use super::buffer::Buffer;
-use crate::framework::prelude::*;
+use crate::{framework::prelude::*, Controller};
 
 #[derive(Debug, PartialEq, Eq, Ord, PartialOrd, Copy, Clone)]
 pub struct FrameEvent {
     pub frame: Duration,
     pub target: Duration,
+    pub jitter: Duration,
 }
 
 impl Buffer {
     pub fn event(&self, config: &Config, mode: Mode) -> Option<FrameEvent> {
-        let normalized_last_frame = self.frametimes.front().copied()? * self.target_fps?;
+        let normalized_last_frame = self.smoothed_frametime(config)?.mul_f64(self.target_fps?);
 
-        #[cfg(debug_assertions)]
         debug!("normalized_last_frame: {normalized_last_frame:?}");
 
         let frame = normalized_last_frame;
         let margin = config.mode_config(mode).margin;
         let margin = Duration::from_millis(margin);
         let target = Duration::from_secs(1) + margin;
+        let jitter = self.jitter.mul_f64(self.target_fps?);
 
-        Some(FrameEvent { frame, target })
+        Some(FrameEvent {
+            frame,
+            target,
+            jitter,
+        })
+    }
+}
+
+impl FrameEvent {
+    /// Frequency step for this frame before pacing, shared by the looper and replays
+    pub fn factor(&self, target_fps: f64, config: &Config) -> f64 {
+        let factor = Controller::scale_factor(target_fps, self.frame, self.target);
+        Controller::jitter_factor(
+            target_fps,
+            factor,
+            self.jitter,
+            config.config().jitter_threshold,
+        )
     }
 }
@@ -0,0 +1,109 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use log::info;
+
+use super::{power_supply::PowerSupply, Looper};
+
+const SAMPLE_TIME: Duration = Duration::from_secs(1);
+const SAMPLE_WINDOW: usize = 5;
+const CEILING_STEP: isize = 100_000;
+const RELEASE_RATIO: f64 = 0.9;
+
+#[derive(Debug)]
+pub struct PowerBudget {
+    samples: VecDeque<u32>,
+    timer: Instant,
+    limiting: bool,
+}
+
+impl PowerBudget {
+    pub fn new() -> Self {
+        Self {
+            samples: VecDeque::with_capacity(SAMPLE_WINDOW),
+            timer: Instant::now(),
+            limiting: false,
+        }
+    }
+
+    fn sample(&mut self) -> Option<u32> {
+        if self.timer.elapsed() < SAMPLE_TIME {
+            return None;
+        }
+
+        self.timer = Instant::now();
+
+        if self.samples.len() >= SAMPLE_WINDOW {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(PowerSupply::power()?);
+
+        if self.samples.len() < SAMPLE_WINDOW {
+            return None;
+        }
+
+        Some(self.samples.iter().sum::<u32>() / self.samples.len() as u32)
+    }
+
+    fn clear(&mut self) {
+        self.samples.clear();
+        self.limiting = false;
+    }
+}
+
+impl Looper {
+    pub fn update_power_budget(&mut self) {
+        let budget = self
+            .target_buffer()
+            .and_then(|buffer| self.config.power_budget(&buffer.pkg));
+
+        let Some(budget) = budget.filter(|_| self.state.is_active()) else {
+            if self.power_budget.limiting {
+                info!("Power budget released");
+                self.controller.set_freq_ceiling(isize::MAX);
+            }
+            self.power_budget.clear();
+            return;
+        };
+
+        let Some(power) = self.power_budget.sample() else {
+            return;
+        };
+
+        let ceiling = self.controller.freq_ceiling();
+
+        if power > budget {
+            if !self.power_budget.limiting {
+                info!("Power {power}mW exceeds budget {budget}mW, limit frequency");
+                self.power_budget.limiting = true;
+            }
+
+            let ceiling = ceiling.min(self.controller.policy_freq()) - CEILING_STEP;
+            self.controller.set_freq_ceiling(ceiling);
+        } else if self.power_budget.limiting && f64::from(power) < f64::from(budget) * RELEASE_RATIO
+        {
+            self.controller.set_freq_ceiling(ceiling + CEILING_STEP);
+
+            if self.controller.freq_ceiling() == ceiling {
+                info!("Power budget released");
+                self.power_budget.limiting = false;
+            }
+        }
+    }
+}
@@ -0,0 +1,83 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    fs,
+    path::Path,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+const POWER_SUPPLY: &str = "/sys/class/power_supply/battery";
+const REFRESH_TIME: Duration = Duration::from_secs(5);
+
+pub struct PowerSupply {
+    capacity: Option<u32>,
+    charging: bool,
+    temp: Option<u32>,
+    timer: Option<Instant>,
+}
+
+impl PowerSupply {
+    pub const fn new() -> Self {
+        Self {
+            capacity: None,
+            charging: false,
+            temp: None,
+            timer: None,
+        }
+    }
+
+    pub fn capacity(&mut self) -> Option<u32> {
+        self.refresh();
+        self.capacity
+    }
+
+    pub fn charging(&mut self) -> bool {
+        self.refresh();
+        self.charging
+    }
+
+    pub fn temp(&mut self) -> Option<u32> {
+        self.refresh();
+        self.temp
+    }
+
+    pub fn power() -> Option<u32> {
+        let current: i64 = read_node("current_now")?;
+        let voltage: i64 = read_node("voltage_now")?;
+
+        Some((current.unsigned_abs() * voltage.unsigned_abs() / 1_000_000_000) as u32)
+    }
+
+    fn refresh(&mut self) {
+        if self.timer.is_some_and(|t| t.elapsed() < REFRESH_TIME) {
+            return;
+        }
+
+        self.timer = Some(Instant::now());
+        self.capacity = read_node("capacity");
+        self.charging = read_node::<String>("status")
+            .is_some_and(|status| status == "Charging" || status == "Full");
+        self.temp = read_node::<i32>("temp").map(|temp| (temp.max(0) / 10) as u32);
+    }
+}
+
+fn read_node<T: FromStr>(node: &str) -> Option<T> {
+    fs::read_to_string(Path::new(POWER_SUPPLY).join(node))
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
@@ -0,0 +1,163 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    fmt::Write as _,
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::Path,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use log::info;
+
+use super::{FasData, Looper};
+
+const RECORD_DIR: &str = "/sdcard/Android/fas-rs/records";
+const MAX_FILE_SIZE: u64 = 16 * 1024 * 1024;
+const MAX_FILES: usize = 10;
+
+pub struct Recorder {
+    writer: Option<BufWriter<File>>,
+    size: u64,
+    start: Instant,
+    /// One file per session, a failed start isn't retried until the session ends
+    started: bool,
+}
+
+impl Recorder {
+    pub fn new() -> Self {
+        Self {
+            writer: None,
+            size: 0,
+            start: Instant::now(),
+            started: false,
+        }
+    }
+
+    fn start(&mut self, pkg: &str, policies: usize) {
+        self.started = true;
+
+        let dir = Path::new(RECORD_DIR);
+        let _ = fs::create_dir_all(dir);
+        prune(dir);
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let path = dir.join(format!("{pkg}_{timestamp}.csv"));
+
+        let Ok(file) = File::create(&path) else {
+            return;
+        };
+        info!("Recording session to {}", path.display());
+
+        let mut header = String::from("timestamp_ms,frametime_us");
+        for policy in 0..policies {
+            let _ = write!(header, ",freq{policy}");
+        }
+        header.push_str(",temp\n");
+
+        self.writer = Some(BufWriter::new(file));
+        self.size = 0;
+        self.start = Instant::now();
+        self.write(&header);
+    }
+
+    fn write(&mut self, row: &str) {
+        let Some(writer) = self.writer.as_mut() else {
+            return;
+        };
+
+        if self.size + row.len() as u64 > MAX_FILE_SIZE {
+            info!("Record file reached size limit, stop recording");
+            self.stop();
+            return;
+        }
+
+        if writer.write_all(row.as_bytes()).is_err() {
+            self.stop();
+            return;
+        }
+        self.size += row.len() as u64;
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(mut writer) = self.writer.take() {
+            let _ = writer.flush();
+        }
+    }
+}
+
+impl Looper {
+    pub fn record_frame(&mut self, data: &FasData) {
+        if !self.config.config().record || !self.state.is_active() {
+            self.recorder.stop();
+            return;
+        }
+
+        let Some(buffer) = self.target_buffer().filter(|buffer| buffer.pid == data.pid) else {
+            return;
+        };
+
+        if !self.recorder.started {
+            let pkg = buffer.pkg.clone();
+            self.recorder.start(&pkg, self.controller.freqs().count());
+        }
+
+        let mut row = format!(
+            "{},{}",
+            self.recorder.start.elapsed().as_millis(),
+            data.frametime.as_micros()
+        );
+        for freq in self.controller.freqs() {
+            let _ = write!(row, ",{}", freq.unwrap_or_default());
+        }
+        match self.thermal.temp() {
+            Some(temp) => {
+                let _ = writeln!(row, ",{temp}");
+            }
+            None => row.push_str(",\n"),
+        }
+
+        self.recorder.write(&row);
+    }
+
+    pub fn end_record(&mut self) {
+        self.recorder.stop();
+        self.recorder.size = 0;
+        self.recorder.started = false;
+    }
+}
+
+fn prune(dir: &Path) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    let mut files: Vec<_> = entries
+        .filter_map(std::result::Result::ok)
+        .filter_map(|entry| {
+            let modified = entry.metadata().ok()?.modified().ok()?;
+            Some((modified, entry.path()))
+        })
+        .collect();
+    files.sort_unstable();
+
+    let excess = (files.len() + 1).saturating_sub(MAX_FILES);
+    for (_, path) in files.into_iter().take(excess) {
+        let _ = fs::remove_file(path);
+    }
+}
@@ -0,0 +1,177 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::{Duration, Instant};
+
+use dumpsys_rs::Dumpsys;
+use log::info;
+
+use super::{Looper, Vsync};
+
+const REFRESH_TIME: Duration = Duration::from_secs(2);
+
+/// The display the game is on, which changes when a foldable is folded or the game is
+/// moved to an external display
+pub struct RefreshRate {
+    dumper: Option<Dumpsys>,
+    display: Option<String>,
+    rate: Option<u32>,
+    timer: Option<Instant>,
+}
+
+impl RefreshRate {
+    pub const fn new() -> Self {
+        Self {
+            dumper: None,
+            display: None,
+            rate: None,
+            timer: None,
+        }
+    }
+
+    pub const fn rate(&self) -> Option<u32> {
+        self.rate
+    }
+
+    pub fn display(&self) -> Option<&str> {
+        self.display.as_deref()
+    }
+
+    /// Unique id of the physical display behind the logical one and its refresh rate
+    fn read(&mut self, display_id: i32) -> (Option<String>, Option<u32>) {
+        if self.dumper.is_none() {
+            self.dumper = Dumpsys::new("display");
+        }
+
+        let Some(dump) = self
+            .dumper
+            .as_ref()
+            .and_then(|dumper| dumper.dump(&[]).ok())
+        else {
+            return (None, None);
+        };
+
+        // The override info of a logical display follows its base info
+        let display = format!("displayId {display_id},");
+        let info = dump
+            .lines()
+            .rev()
+            .find(|line| line.contains("DisplayInfo{") && line.contains(&display));
+        let unique_id = info.and_then(|info| {
+            let key = "uniqueId \"";
+            let info = &info[info.find(key)? + key.len()..];
+            Some(info[..info.find('"')?].to_string())
+        });
+        let rate = info
+            .and_then(mode_refresh_rate)
+            .map(|rate| rate.round() as u32)
+            .filter(|rate| *rate > 0)
+            .or_else(|| parse_refresh_rate(&dump));
+
+        (unique_id, rate)
+    }
+}
+
+impl Looper {
+    pub fn update_refresh_rate(&mut self) {
+        if !self.state.is_active() {
+            self.refresh_rate.display = None;
+            self.refresh_rate.rate = None;
+            self.refresh_rate.timer = None;
+            return;
+        }
+
+        if self
+            .refresh_rate
+            .timer
+            .is_some_and(|t| t.elapsed() < REFRESH_TIME)
+        {
+            return;
+        }
+        self.refresh_rate.timer = Some(Instant::now());
+
+        let display_id = self.windows_watcher.focused_display().unwrap_or_default();
+        let (display, rate) = self.refresh_rate.read(display_id);
+        if display.is_some() && display != self.refresh_rate.display {
+            if self.refresh_rate.display.is_some() {
+                info!("Game moved to display {display_id}");
+                self.display_changed();
+            }
+            self.refresh_rate.display = display;
+        }
+
+        let rate = rate.filter(|_| self.config.config().refresh_rate_cap);
+        if rate != self.refresh_rate.rate {
+            if let Some(rate) = rate {
+                info!("Display refresh rate: {rate}Hz");
+            }
+            self.refresh_rate.rate = rate;
+        }
+    }
+
+    /// Frametimes, vsync and the matched target belong to the old panel, so start over
+    /// like on a target change
+    fn display_changed(&mut self) {
+        let games: Vec<_> = self
+            .buffers
+            .values()
+            .map(|buffer| (buffer.pid, buffer.pkg.clone(), buffer.activity.clone()))
+            .collect();
+
+        for (pid, pkg, activity) in games {
+            let target_fps = match &activity {
+                Some(activity) => self.config.activity_target_fps(&pkg, activity),
+                None => self.config.target_fps(&pkg),
+            };
+
+            if let Some(buffer) = self.buffers.get_mut(&pid) {
+                if let Some(target_fps) = target_fps {
+                    buffer.set_target_fps_config(target_fps);
+                }
+                buffer.set_vsync(None);
+                buffer.unusable();
+            }
+        }
+
+        self.vsync = Vsync::new();
+    }
+}
+
+fn parse_refresh_rate(dump: &str) -> Option<u32> {
+    let active_mode = dump
+        .lines()
+        .find(|line| line.contains("mActiveSfDisplayMode"))
+        .and_then(|line| value_after(line, "refreshRate="));
+
+    active_mode
+        .map(|rate| rate.round() as u32)
+        .filter(|rate| *rate > 0)
+}
+
+/// Peak refresh rate of the active mode in a `DisplayInfo`. `renderFrameRate` follows the LTPO
+/// ratchet down to what is rendered, capping to it would keep the game from ever climbing back
+fn mode_refresh_rate(info: &str) -> Option<f32> {
+    let mode_id = value_after(info, "modeId ")? as u32;
+    let modes = &info[info.find("supportedModes [")?..];
+    let mode = &modes[modes.find(&format!("{{id={mode_id}, "))?..];
+    value_after(mode, "fps=")
+}
+
+fn value_after(s: &str, key: &str) -> Option<f32> {
+    let s = &s[s.find(key)? + key.len()..];
+    let end = s
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(s.len());
+    s[..end].parse().ok()
+}
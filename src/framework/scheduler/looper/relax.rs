@@ -0,0 +1,80 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::{Duration, Instant};
+
+use log::info;
+
+use super::{policy::FrameEvent, Looper};
+
+const RELAX_RATIO: f64 = 0.9;
+const DEEP_RELAX_STEP: Duration = Duration::from_millis(10);
+const DEEP_RELAX_MAX: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Copy)]
+pub enum Relax {
+    Normal,
+    Comfortable(Instant),
+    Deep { since: Instant, policy_freq: isize },
+}
+
+impl Looper {
+    pub fn relax_event(&mut self, event: FrameEvent) -> FrameEvent {
+        let relax_time = self.config.config().deep_relax_time;
+        if relax_time == 0 {
+            self.relax = Relax::Normal;
+            return event;
+        }
+
+        let comfortable = event.frame < event.target.mul_f64(RELAX_RATIO);
+
+        match self.relax {
+            Relax::Normal => {
+                if comfortable {
+                    self.relax = Relax::Comfortable(Instant::now());
+                }
+            }
+            Relax::Comfortable(since) => {
+                if !comfortable {
+                    self.relax = Relax::Normal;
+                } else if since.elapsed() >= Duration::from_secs(relax_time) {
+                    info!("Enter deep relax");
+                    self.relax = Relax::Deep {
+                        since: Instant::now(),
+                        policy_freq: self.controller.policy_freq(),
+                    };
+                }
+            }
+            Relax::Deep { since, policy_freq } => {
+                if event.frame > event.target {
+                    info!("Exit deep relax");
+                    self.controller.set_policy_freq(policy_freq);
+                    self.relax = Relax::Normal;
+                    return event;
+                }
+
+                let extra = DEEP_RELAX_STEP
+                    .mul_f64(since.elapsed().as_secs_f64())
+                    .min(DEEP_RELAX_MAX);
+
+                return FrameEvent {
+                    target: event.target + extra,
+                    ..event
+                };
+            }
+        }
+
+        event
+    }
+}
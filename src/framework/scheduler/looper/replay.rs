@@ -0,0 +1,108 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use super::buffer::{Buffer, BufferState};
+use crate::{
+    cpu_common::MemoryFs,
+    framework::{
+        config::{Config, FreqBackend, TargetFps, AUTO_TARGET_FPS},
+        error::Result,
+        node::Mode,
+        Extension,
+    },
+    Controller,
+};
+
+const CPUFREQ: &str = "/sys/devices/system/cpu/cpufreq";
+
+#[derive(Debug, Clone, Copy)]
+pub struct ReplayFrame {
+    pub timestamp: Duration,
+    pub frametime: Duration,
+    pub target_fps: Option<f64>,
+    pub policy_freq: isize,
+}
+
+/// Run the recorded frames through the real controller, on policies made up from the frequency
+/// tables in `freqs`, one per recorded policy, instead of the cpufreq nodes of this device.
+///
+/// This is the buffer and controller only, not the looper: relax, thermal and battery fps caps,
+/// the target ladder, cooldown and boosts need the device and are not applied
+pub fn replay(
+    config: &Config,
+    pkg: &str,
+    mode: Mode,
+    frames: &[(Duration, Duration)],
+    freqs: &[Vec<isize>],
+) -> Result<Vec<ReplayFrame>> {
+    // Lua extensions and their watcher need the device, replays don't call them
+    let extension = Extension::noop();
+    let mut controller = Controller::with_fs(FreqBackend::Cpufreq, Arc::new(policy_fs(freqs)))?;
+    controller.init_game(&extension);
+
+    let target_fps = config
+        .target_fps(pkg)
+        .unwrap_or_else(|| TargetFps::Array(AUTO_TARGET_FPS.to_vec()));
+    let mut buffer = Buffer::new(target_fps, 0, pkg.to_string());
+    let mut usable = false;
+    let start = Instant::now();
+
+    let mut result = Vec::with_capacity(frames.len());
+    for (timestamp, frametime) in frames.iter().copied() {
+        buffer.push_frametime_at(frametime, start + timestamp, config, &extension);
+
+        if buffer.state == BufferState::Usable {
+            usable = true;
+            if let (Some(event), Some(target_fps)) = (buffer.event(config, mode), buffer.target_fps)
+            {
+                let factor = event.factor(target_fps, config);
+                controller.fas_update_freq(0, frametime, factor);
+            }
+        } else if usable {
+            usable = false;
+            controller.init_game(&extension);
+        }
+
+        result.push(ReplayFrame {
+            timestamp,
+            frametime,
+            target_fps: buffer.target_fps,
+            policy_freq: controller.policy_freq(),
+        });
+    }
+
+    Ok(result)
+}
+
+fn policy_fs(freqs: &[Vec<isize>]) -> MemoryFs {
+    let fs = MemoryFs::default();
+
+    for (policy, freqs) in freqs.iter().enumerate() {
+        let path = format!("{CPUFREQ}/policy{policy}");
+        let table: Vec<_> = freqs.iter().map(ToString::to_string).collect();
+
+        fs.insert(format!("{path}/affected_cpus"), policy.to_string());
+        fs.insert(
+            format!("{path}/scaling_available_frequencies"),
+            table.join(" "),
+        );
+    }
+
+    fs
+}
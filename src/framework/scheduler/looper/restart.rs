@@ -0,0 +1,112 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    mem,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use libc::pid_t;
+use log::info;
+
+use super::{buffer::BufferState, Buffer, Looper};
+use crate::{
+    api::{v1::ApiV1, v2::ApiV2},
+    framework::api::ApiV0,
+};
+
+const RESTART_GRACE: Duration = Duration::from_secs(10);
+
+/// Buffer of a game whose process died while fas-rs was working on it,
+/// kept for a while so a restart of the same package can take it over
+#[derive(Debug)]
+pub struct Restarting {
+    buffer: Buffer,
+    since: Instant,
+}
+
+impl Restarting {
+    pub const fn buffer(&self) -> &Buffer {
+        &self.buffer
+    }
+}
+
+impl Looper {
+    /// Returns the buffer back if its process is still alive and should be unloaded as usual
+    pub fn park_buffer(&mut self, buffer: Buffer) -> Option<Buffer> {
+        if !self.state.is_active() || Path::new(&format!("/proc/{}", buffer.pid)).exists() {
+            return Some(buffer);
+        }
+
+        info!("[{}] died, waiting for it to restart", buffer.pkg);
+        let restarting = Restarting {
+            buffer,
+            since: Instant::now(),
+        };
+
+        self.restarting
+            .replace(restarting)
+            .map(|restarting| restarting.buffer)
+    }
+
+    pub fn expire_restarting(&mut self) -> Option<Buffer> {
+        if self
+            .restarting
+            .as_ref()
+            .is_some_and(|restarting| restarting.since.elapsed() < RESTART_GRACE)
+        {
+            return None;
+        }
+
+        let restarting = self.restarting.take()?;
+        info!("[{}] did not restart", restarting.buffer.pkg);
+        Some(restarting.buffer)
+    }
+
+    /// Moves the parked buffer over to the new pid of the same package, keeping its
+    /// frametime history and target fps instead of unloading and delaying again
+    pub fn rebind_buffer(&mut self, pid: pid_t, pkg: &str) -> Option<BufferState> {
+        if self
+            .restarting
+            .as_ref()
+            .is_none_or(|restarting| restarting.buffer.pkg != pkg)
+        {
+            return None;
+        }
+
+        let mut buffer = self.restarting.take()?.buffer;
+        let old_pid = mem::replace(&mut buffer.pid, pid);
+        info!("[{pkg}] restarted, rebind buffer from {old_pid} to {pid}");
+
+        self.extension
+            .tigger_extentions(ApiV0::UnloadFas(old_pid, pkg.to_string()));
+        self.extension
+            .tigger_extentions(ApiV1::UnloadFas(old_pid, pkg.to_string()));
+        self.extension
+            .tigger_extentions(ApiV2::UnloadFas(old_pid, pkg.to_string()));
+        self.extension
+            .tigger_extentions(ApiV0::LoadFas(pid, pkg.to_string()));
+        self.extension
+            .tigger_extentions(ApiV1::LoadFas(pid, pkg.to_string()));
+        self.extension
+            .tigger_extentions(ApiV2::LoadFas(pid, pkg.to_string()));
+
+        self.rebind_toggle(old_pid, pid);
+
+        let state = buffer.state;
+        self.buffers.insert(pid, buffer);
+        Some(state)
+    }
+}
@@ -0,0 +1,88 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::{Duration, Instant};
+
+use log::info;
+
+use super::{restart::Restarting, Looper, State};
+use crate::api::v2::ApiV2;
+
+/// Play time of the current game, only counted while fas-rs is working on it
+#[derive(Debug)]
+pub struct Session {
+    pkg: Option<String>,
+    working: Duration,
+    last_update: Instant,
+    reached: usize,
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Self {
+            pkg: None,
+            working: Duration::ZERO,
+            last_update: Instant::now(),
+            reached: 0,
+        }
+    }
+
+    pub const fn working(&self) -> Duration {
+        self.working
+    }
+}
+
+impl Looper {
+    pub fn update_session(&mut self) {
+        let elapsed = self.session.last_update.elapsed();
+        self.session.last_update = Instant::now();
+
+        // Keep counting while a crashed game restarts
+        let pkg = self
+            .target_buffer()
+            .or_else(|| self.restarting.as_ref().map(Restarting::buffer))
+            .map(|buffer| buffer.pkg.clone());
+        if pkg != self.session.pkg {
+            self.session = Session::new();
+            self.session.pkg = pkg;
+            return;
+        }
+
+        let Some(pkg) = pkg else {
+            return;
+        };
+
+        if self.state == State::Working {
+            self.session.working += elapsed;
+        }
+
+        let milestones = self.config.session_milestones();
+        let minutes = self.session.working.as_secs() / 60;
+        // Milestones added to the config below the current play time don't fire late
+        while let Some(milestone) = milestones
+            .get(self.session.reached)
+            .copied()
+            .filter(|milestone| *milestone <= minutes)
+        {
+            self.session.reached += 1;
+            if milestone < minutes {
+                continue;
+            }
+
+            info!("[{pkg}] played for {milestone} minutes");
+            self.extension
+                .tigger_extentions(ApiV2::SessionMilestone(milestone, pkg.clone()));
+        }
+    }
+}
@@ -0,0 +1,177 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    fmt::Write,
+    time::{Duration, Instant},
+};
+
+use super::{Looper, State};
+use crate::{
+    framework::api::{
+        settings,
+        telemetry::{self, Telemetry},
+    },
+    trace,
+};
+
+const REFRESH_TIME: Duration = Duration::from_secs(1);
+
+impl Looper {
+    pub fn update_status(&mut self) {
+        if self.status_timer.elapsed() < REFRESH_TIME {
+            return;
+        }
+        self.status_timer = Instant::now();
+
+        let status = self.status();
+        let _ = self.node.write_node("status", &status);
+
+        self.publish_telemetry();
+    }
+
+    /// `key=value` lines of the status node
+    pub fn status(&self) -> String {
+        let state = match self.state {
+            State::NotWorking => "not_working",
+            State::Waiting => "waiting",
+            State::Working => "working",
+            State::Cooldown => "cooldown",
+        };
+
+        let mut status = String::new();
+        let _ = writeln!(status, "mode={}", self.mode);
+        let _ = writeln!(status, "state={state}");
+        if let Some(mode) = self.toggle.mode() {
+            let _ = writeln!(status, "toggle_mode={mode}");
+        }
+        if self.toggle.paused() {
+            let _ = writeln!(status, "paused=true");
+        }
+        if let Some(cpu) = self.isolation.cpu() {
+            let _ = writeln!(status, "isolated_cpu={cpu}");
+        }
+        if self.video.playing() {
+            let _ = writeln!(status, "video=true");
+        }
+        let _ = writeln!(
+            status,
+            "write_conflicts={}",
+            self.controller.write_conflicts()
+        );
+
+        if let Some(buffer) = self.target_buffer() {
+            let _ = writeln!(status, "pkg={}", buffer.pkg);
+            if let Some(target_fps) = buffer.target_fps {
+                let _ = writeln!(status, "target_fps={target_fps}");
+            }
+            let _ = writeln!(status, "current_fps={:.1}", buffer.current_fps);
+            let _ = writeln!(status, "engine={}", buffer.engine);
+            if let Some(display) = self.refresh_rate.display() {
+                let _ = writeln!(status, "display={display}");
+            }
+            let _ = writeln!(status, "session_time={}", self.session.working().as_secs());
+            if let Some(ladder) = buffer.ladder() {
+                let _ = writeln!(status, "target_ladder={ladder}");
+            }
+        }
+
+        let freqs: Vec<_> = self
+            .controller
+            .freqs()
+            .map(|freq| freq.map_or_else(|| "-".to_string(), |freq| freq.to_string()))
+            .collect();
+        let _ = writeln!(status, "policy_freq={}", self.controller.policy_freq());
+        let _ = writeln!(status, "freqs={}", freqs.join(","));
+
+        if let Some(temp) = self.thermal.temp() {
+            let _ = writeln!(status, "temp={temp}");
+        }
+
+        if let Some(busy) = self.gpu.busy() {
+            let _ = writeln!(status, "gpu_busy={busy}");
+        }
+
+        let clamps: Vec<_> = self
+            .controller
+            .thermal_clamps()
+            .into_iter()
+            .map(|(policy, freq)| format!("policy{policy}:{freq}"))
+            .collect();
+        if !clamps.is_empty() {
+            let _ = writeln!(status, "thermal_clamps={}", clamps.join(","));
+        }
+
+        let caps: Vec<_> = self
+            .controller
+            .thermal_caps()
+            .into_iter()
+            .map(|(policy, freq)| format!("policy{policy}:{freq}"))
+            .collect();
+        if !caps.is_empty() {
+            let _ = writeln!(status, "thermal_caps={}", caps.join(","));
+        }
+
+        #[cfg(feature = "use_ebpf")]
+        {
+            let health = &self.analyzer_health;
+            let _ = writeln!(status, "analyzer_events={}", health.events);
+            let _ = writeln!(status, "analyzer_silences={}", health.silences);
+            let _ = writeln!(status, "analyzer_restarts={}", health.restarts);
+            let _ = writeln!(
+                status,
+                "analyzer_silence_ms={}",
+                health.silence().as_millis()
+            );
+        }
+
+        if let Some(e) = self.config.parse_error() {
+            let _ = writeln!(status, "config_error={e}");
+        }
+
+        status
+    }
+
+    fn publish_telemetry(&mut self) {
+        let buffer = self.target_buffer();
+        let current_fps = buffer.map(|buffer| buffer.current_fps);
+        let target_fps = buffer.and_then(|buffer| buffer.target_fps);
+
+        telemetry::publish(Telemetry {
+            current_fps,
+            target_fps,
+            temp: self.thermal.temp(),
+            battery: self.power_supply.capacity(),
+            freqs: self.controller.cur_freqs(),
+            thermal_clamps: self.controller.thermal_clamps(),
+            gpu_busy: self.gpu.busy(),
+        });
+        settings::publish(self.config.extensions());
+    }
+
+    pub fn trace_metrics(&self) {
+        if let Some(buffer) = self.target_buffer() {
+            if let Some(target_fps) = buffer.target_fps {
+                trace::counter("fas-rs target_fps", target_fps.round() as i64);
+            }
+            trace::counter("fas-rs current_fps", buffer.current_fps.round() as i64);
+        }
+
+        trace::counter("fas-rs policy_freq", self.controller.policy_freq());
+
+        if let Some(headroom) = self.thermal.headroom() {
+            trace::counter("fas-rs thermal_headroom", headroom);
+        }
+    }
+}
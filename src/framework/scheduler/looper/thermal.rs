@@ -0,0 +1,235 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    cell::OnceCell,
+    collections::HashMap,
+    fs,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use log::info;
+
+use super::Looper;
+use crate::{
+    framework::config::{PolicyThermalRule, ThermalConfig, ThermalRule},
+    init::SocProfile,
+};
+
+const THERMAL: &str = "/sys/class/thermal";
+const REFRESH_TIME: Duration = Duration::from_secs(1);
+
+#[derive(Debug)]
+pub struct Thermal {
+    zones: HashMap<String, PathBuf>,
+    default_zone: OnceCell<Option<String>>,
+    temp: Option<u32>,
+    headroom: Option<i64>,
+    timer: Option<Instant>,
+    policy_rules: HashMap<i32, PolicyThermalRule>,
+}
+
+impl Thermal {
+    pub fn new() -> Self {
+        Self {
+            zones: HashMap::new(),
+            default_zone: OnceCell::new(),
+            temp: None,
+            headroom: None,
+            timer: None,
+            policy_rules: HashMap::new(),
+        }
+    }
+
+    pub const fn temp(&self) -> Option<u32> {
+        self.temp
+    }
+
+    pub const fn headroom(&self) -> Option<i64> {
+        self.headroom
+    }
+
+    fn zone_temp(&mut self, zone: &str) -> Option<u32> {
+        if !self.zones.contains_key(zone) {
+            let path = find_zone(zone)?;
+            self.zones.insert(zone.to_string(), path);
+        }
+
+        let path = &self.zones[zone];
+        let temp: i32 = fs::read_to_string(path).ok()?.trim().parse().ok()?;
+
+        Some((temp.max(0) / 1000) as u32)
+    }
+
+    fn default_zone(&self) -> Option<String> {
+        self.default_zone
+            .get_or_init(|| {
+                let zone = SocProfile::current()
+                    .thermal_zones
+                    .iter()
+                    .find(|zone| find_zone(zone).is_some())
+                    .map(ToString::to_string);
+                info!("Default thermal zone: {zone:?}");
+                zone
+            })
+            .clone()
+    }
+}
+
+impl Looper {
+    pub fn update_thermal(&mut self) {
+        if self
+            .thermal
+            .timer
+            .is_some_and(|t| t.elapsed() < REFRESH_TIME)
+        {
+            return;
+        }
+        self.thermal.timer = Some(Instant::now());
+
+        let config = self.config.thermal();
+        let zone = config.zone.clone().or_else(|| self.thermal.default_zone());
+        let temp = match &zone {
+            Some(zone) => self.thermal.zone_temp(zone),
+            None => self.power_supply.temp(),
+        };
+        self.thermal.temp = temp;
+        self.update_policy_thermal(&config);
+
+        if config.rules.is_empty() {
+            self.thermal_rule = None;
+            self.thermal.headroom = None;
+            return;
+        }
+
+        let Some(temp) = temp else {
+            return;
+        };
+
+        self.thermal.headroom = config
+            .rules
+            .iter()
+            .map(|rule| i64::from(rule.above) - i64::from(temp))
+            .min();
+
+        let rule = select_rule(
+            &config.rules,
+            |rule: &ThermalRule| rule.above,
+            temp,
+            self.thermal_rule,
+            config.hysteresis,
+        );
+
+        if rule != self.thermal_rule {
+            if let Some(rule) = rule {
+                info!(
+                    "Temperature {temp}°C above {}°C, limit target fps to {}",
+                    rule.above, rule.target_fps
+                );
+            } else {
+                info!("Temperature {temp}°C, thermal limit released");
+            }
+
+            self.thermal_rule = rule;
+        }
+    }
+
+    /// Back off each mapped cluster by its own hottest zone, so a warm skin or GPU
+    /// doesn't hold down clusters that are still cool
+    fn update_policy_thermal(&mut self, config: &ThermalConfig) {
+        let mut rules = HashMap::new();
+
+        if !config.policy_rules.is_empty() {
+            for (key, zones) in &config.policies {
+                let Some(policy) = key.strip_prefix("policy").and_then(|n| n.parse().ok()) else {
+                    continue;
+                };
+
+                let current = self.thermal.policy_rules.get(&policy).copied();
+                let temp = zones
+                    .iter()
+                    .filter_map(|zone| self.thermal.zone_temp(zone))
+                    .max();
+                let rule = temp.map_or(current, |temp| {
+                    select_rule(
+                        &config.policy_rules,
+                        |rule: &PolicyThermalRule| rule.above,
+                        temp,
+                        current,
+                        config.hysteresis,
+                    )
+                });
+
+                if rule != current {
+                    if let Some(rule) = rule {
+                        info!(
+                            "policy{policy} at {}°C above {}°C, cap to {:.0}% of max freq",
+                            temp.unwrap_or_default(),
+                            rule.above,
+                            rule.max_freq * 100.0
+                        );
+                    } else {
+                        info!("policy{policy} thermal cap released");
+                    }
+                }
+
+                if let Some(rule) = rule {
+                    rules.insert(policy, rule);
+                }
+            }
+        }
+
+        let caps = rules
+            .iter()
+            .map(|(policy, rule)| (*policy, rule.max_freq))
+            .collect();
+        self.controller.set_thermal_caps(&caps);
+        self.thermal.policy_rules = rules;
+    }
+}
+
+/// The highest rule `temp` reached, the current one is held until `temp` drops `hysteresis` below it
+fn select_rule<R: Copy>(
+    rules: &[R],
+    above: fn(&R) -> u32,
+    temp: u32,
+    current: Option<R>,
+    hysteresis: u32,
+) -> Option<R> {
+    let rule = rules
+        .iter()
+        .filter(|rule| temp >= above(rule))
+        .max_by_key(|rule| above(rule))
+        .copied();
+
+    match current {
+        Some(current)
+            if rule.is_none_or(|rule| above(&rule) < above(&current))
+                && temp + hysteresis > above(&current) =>
+        {
+            Some(current)
+        }
+        _ => rule,
+    }
+}
+
+fn find_zone(zone: &str) -> Option<PathBuf> {
+    fs::read_dir(THERMAL)
+        .ok()?
+        .filter_map(std::result::Result::ok)
+        .map(|entry| entry.path())
+        .find(|path| fs::read_to_string(path.join("type")).is_ok_and(|kind| kind.trim() == zone))
+        .map(|path| path.join("temp"))
+}
@@ -0,0 +1,98 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::str::FromStr;
+
+use libc::pid_t;
+use log::{info, warn};
+
+use super::Looper;
+use crate::framework::node::Mode;
+
+/// Override set from the overlay socket by a QS tile or notification, kept until its buffer unloads
+#[derive(Debug, Default)]
+pub struct Toggle {
+    pid: Option<pid_t>,
+    mode: Option<Mode>,
+    paused: bool,
+}
+
+impl Toggle {
+    pub const fn mode(&self) -> Option<Mode> {
+        self.mode
+    }
+
+    pub const fn paused(&self) -> bool {
+        self.paused
+    }
+}
+
+impl Looper {
+    /// `mode` cycles the power mode, `mode <name>` sets it, `pause` / `resume` stop and
+    /// restart FAS and `reset` drops the override, all for the current game only
+    pub fn quick_toggle(&mut self, command: &str) {
+        let Some(pid) = self.target_buffer().map(|buffer| buffer.pid) else {
+            warn!("Quick toggle '{command}' ignored, no game running");
+            return;
+        };
+
+        if self.toggle.pid != Some(pid) {
+            self.toggle = Toggle {
+                pid: Some(pid),
+                ..Toggle::default()
+            };
+        }
+
+        let mut args = command.split_whitespace();
+        match (args.next(), args.next()) {
+            (Some("mode"), None) => self.toggle.mode = Some(self.mode.next()),
+            (Some("mode"), Some(mode)) => {
+                let Ok(mode) = Mode::from_str(mode) else {
+                    warn!("Quick toggle: unknown mode '{mode}'");
+                    return;
+                };
+                self.toggle.mode = Some(mode);
+            }
+            (Some("pause"), None) => self.toggle.paused = true,
+            (Some("resume"), None) => self.toggle.paused = false,
+            (Some("reset"), None) => self.toggle = Toggle::default(),
+            _ => {
+                warn!("Quick toggle: unknown command '{command}'");
+                return;
+            }
+        }
+
+        info!("Quick toggle: {command}");
+    }
+
+    /// Drop the override once the buffer it was made for is gone
+    pub fn retain_toggle(&mut self) {
+        if self.toggle.pid.is_some_and(|pid| {
+            !self.buffers.contains_key(&pid)
+                && self
+                    .restarting
+                    .as_ref()
+                    .is_none_or(|restarting| restarting.buffer().pid != pid)
+        }) {
+            info!("Quick toggle released");
+            self.toggle = Toggle::default();
+        }
+    }
+
+    pub fn rebind_toggle(&mut self, old_pid: pid_t, pid: pid_t) {
+        if self.toggle.pid == Some(old_pid) {
+            self.toggle.pid = Some(pid);
+        }
+    }
+}
@@ -12,47 +12,99 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::time::{Duration, Instant};
+use std::{
+    mem,
+    time::{Duration, Instant},
+};
 
 use log::info;
 
-use super::{super::FasData, buffer::BufferState, Buffer, Looper, State};
+use super::{
+    super::FasData, buffer::BufferState, engine::Engine, relax::Relax, Buffer, Looper, State,
+};
 use crate::{
     api::{v1::ApiV1, v2::ApiV2},
-    framework::{api::ApiV0, utils::get_process_name},
+    framework::{api::ApiV0, node::Mode, utils::get_process_name},
+    journal, trace,
 };
 
-const DELAY_TIME: Duration = Duration::from_secs(3);
-
 impl Looper {
     pub fn retain_topapp(&mut self) {
-        if let Some(buffer) = self.buffer.as_ref() {
-            if !self.windows_watcher.topapp_pids().contains(&buffer.pid) {
-                #[cfg(feature = "use_ebpf")]
-                let _ = self.analyzer.detach_app(buffer.pid);
-                let pkg = buffer.pkg.clone();
-                self.extension
-                    .tigger_extentions(ApiV0::UnloadFas(buffer.pid, pkg.clone()));
-                self.extension
-                    .tigger_extentions(ApiV1::UnloadFas(buffer.pid, pkg.clone()));
-                self.extension
-                    .tigger_extentions(ApiV2::UnloadFas(buffer.pid, pkg));
-                self.buffer = None;
+        let topapp_pids = self.windows_watcher.topapp_pids().clone();
+        // In split-screen and pop-up windows the game has to be one of the visible tasks
+        let visible_packages = self
+            .config
+            .config()
+            .multi_window
+            .then(|| self.windows_watcher.visible_packages().clone())
+            .filter(|packages| !packages.is_empty());
+        let pids: Vec<_> = self
+            .buffers
+            .iter()
+            .filter(|(pid, buffer)| {
+                !topapp_pids.contains(pid)
+                    || visible_packages
+                        .as_ref()
+                        .is_some_and(|packages| !packages.contains(&buffer.pkg))
+            })
+            .map(|(pid, _)| *pid)
+            .collect();
+
+        for pid in pids {
+            let Some(buffer) = self.buffers.remove(&pid) else {
+                continue;
+            };
+
+            #[cfg(feature = "use_ebpf")]
+            let _ = self.analyzer.detach_app(pid);
+            if let Some(buffer) = self.park_buffer(buffer) {
+                self.unload_buffer(buffer);
             }
         }
 
-        if self.buffer.is_none() {
+        if let Some(buffer) = self.expire_restarting() {
+            self.unload_buffer(buffer);
+        }
+
+        self.retain_toggle();
+
+        if (self.buffers.is_empty() && self.restarting.is_none())
+            || self.toggle.paused()
+            || self.video.playing()
+        {
             self.disable_fas();
-        } else {
+        } else if !self.buffers.is_empty() {
             self.enable_fas();
         }
     }
 
+    fn unload_buffer(&mut self, mut buffer: Buffer) {
+        let pid = buffer.pid;
+        let pkg = mem::take(&mut buffer.pkg);
+        self.buffer_pool.recycle(buffer);
+        self.extension
+            .tigger_extentions(ApiV0::UnloadFas(pid, pkg.clone()));
+        self.extension
+            .tigger_extentions(ApiV1::UnloadFas(pid, pkg.clone()));
+        self.extension.tigger_extentions(ApiV2::UnloadFas(pid, pkg));
+    }
+
     pub fn disable_fas(&mut self) {
+        self.relax = Relax::Normal;
+        self.fixed_freq = None;
+        self.devfreq_booster.restore();
+        self.end_io_boost();
+        self.end_record();
+        self.peak_refresh.restore();
+
         match self.state {
-            State::Working => {
+            State::Working | State::Cooldown => {
                 self.state = State::NotWorking;
+                // Isolation saved the cpusets as throttled, so it has to go first
+                self.isolation.restore();
                 self.cleaner.undo_cleanup();
+                self.cpuidle.restore();
+                self.irq_affinity.restore();
                 self.controller.init_default(&self.extension);
                 self.extension.tigger_extentions(ApiV0::StopFas);
                 self.extension.tigger_extentions(ApiV1::StopFas);
@@ -63,23 +115,53 @@ impl Looper {
         }
     }
 
+    pub fn shutdown(&mut self) {
+        info!("Shutting down, restoring system state");
+
+        if !self.state.is_active() {
+            self.controller.init_default(&self.extension);
+        }
+
+        self.disable_fas();
+        self.fpsgo.set_target(None);
+        journal::restore();
+    }
+
     pub fn enable_fas(&mut self) {
         match self.state {
             State::NotWorking => {
                 self.state = State::Waiting;
                 self.delay_timer = Instant::now();
+                self.valid_frames = 0;
                 self.extension.tigger_extentions(ApiV0::StartFas);
                 self.extension.tigger_extentions(ApiV1::StartFas);
                 self.extension.tigger_extentions(ApiV2::StartFas);
             }
             State::Waiting => {
-                if self.delay_timer.elapsed() > DELAY_TIME {
+                if self.ready_to_work() {
                     self.state = State::Working;
-                    self.cleaner.cleanup();
+                    if !self.config.config().dry_run {
+                        self.cleaner.cleanup(&self.config);
+                        if self.config.config().restrict_cpuidle {
+                            self.cpuidle.restrict();
+                        }
+                        self.irq_affinity.apply(&self.config.irq());
+                    }
                     self.controller.init_game(&self.extension);
                 }
             }
-            State::Working => (),
+            State::Working | State::Cooldown => (),
+        }
+    }
+
+    /// `start_frames` consecutive usable frames when set, otherwise `start_delay` in Waiting
+    fn ready_to_work(&self) -> bool {
+        let config = self.config.config();
+
+        if config.start_frames > 0 {
+            self.valid_frames >= config.start_frames
+        } else {
+            self.delay_timer.elapsed() > Duration::from_millis(config.start_delay)
         }
     }
 
@@ -90,18 +172,34 @@ impl Looper {
 
         let pid = d.pid;
         let frametime = d.frametime;
+        trace::counter("fas-rs frametime_us", frametime.as_micros());
 
-        if let Some(buffer) = self.buffer.as_mut() {
-            buffer.push_frametime(frametime, &self.extension);
+        if let Some(buffer) = self.buffers.get_mut(&pid) {
+            buffer.push_frametime(frametime, &self.config, &self.extension);
             Some(buffer.state)
         } else {
             let Ok(pkg) = get_process_name(d.pid) else {
                 return None;
             };
-            let target_fps = self.config.target_fps(&pkg)?;
+            if self.config.blacklisted(&pkg) || self.config.skips_fas(&pkg) {
+                return None;
+            }
+            if let Some(state) = self.rebind_buffer(pid, &pkg) {
+                if let Some(buffer) = self.buffers.get_mut(&pid) {
+                    buffer.push_frametime(frametime, &self.config, &self.extension);
+                }
+                return Some(state);
+            }
+            let activity = self.focused_activity(&pkg);
+            let target_fps = match &activity {
+                Some(activity) => self.config.activity_target_fps(&pkg, activity)?,
+                None => self.config.target_fps(&pkg)?,
+            };
 
             info!("New fas buffer on: [{pkg}]");
+            trace::event("fas-rs new buffer");
 
+            self.extension.set_package(Some(&pkg));
             self.extension
                 .tigger_extentions(ApiV0::LoadFas(pid, pkg.clone()));
             self.extension
@@ -109,12 +207,68 @@ impl Looper {
             self.extension
                 .tigger_extentions(ApiV2::LoadFas(pid, pkg.clone()));
 
-            let mut buffer = Buffer::new(target_fps, pid, pkg);
-            buffer.push_frametime(frametime, &self.extension);
+            let storage = self.buffer_pool.take();
+            let mut buffer = Buffer::with_storage(target_fps, pid, pkg, storage);
+            buffer.activity = activity;
+            buffer.engine = Engine::detect(pid);
+            buffer.push_frametime(frametime, &self.config, &self.extension);
 
-            self.buffer = Some(buffer);
+            self.buffers.insert(pid, buffer);
+            self.try_launch_boost(pid);
 
             Some(BufferState::Unusable)
         }
     }
+
+    pub fn update_activity(&mut self) {
+        let pkgs: Vec<_> = self.buffers.values().map(|b| b.pkg.clone()).collect();
+
+        for pkg in pkgs {
+            let Some(activity) = self.focused_activity(&pkg) else {
+                continue;
+            };
+
+            let Some(target_fps) = self.config.activity_target_fps(&pkg, &activity) else {
+                continue;
+            };
+
+            for buffer in self.buffers.values_mut().filter(|b| b.pkg == pkg) {
+                if buffer.activity.as_ref() != Some(&activity) {
+                    info!("Activity changed: [{pkg}/{activity}]");
+                    buffer.activity = Some(activity.clone());
+                    buffer.set_target_fps_config(target_fps.clone());
+                }
+            }
+        }
+    }
+
+    fn focused_activity(&mut self, pkg: &str) -> Option<String> {
+        let focused = self.windows_watcher.focused_activity()?;
+        let (focused_pkg, activity) = focused.split_once('/')?;
+
+        (focused_pkg == pkg).then(|| activity.to_string())
+    }
+
+    pub fn mode_override(&self) -> Option<Mode> {
+        self.charging_mode
+            .or_else(|| self.toggle.mode())
+            .or_else(|| self.battery_rule.and_then(|rule| rule.mode))
+            .or_else(|| self.mode_sync.mode())
+    }
+
+    pub fn fps_cap(&self) -> Option<u32> {
+        let battery = self.battery_rule.and_then(|rule| rule.target_fps);
+        let thermal = self.thermal_rule.map(|rule| rule.target_fps);
+        let refresh_rate = self.refresh_rate.rate();
+
+        battery.into_iter().chain(thermal).chain(refresh_rate).min()
+    }
+
+    pub fn update_fps_cap(&mut self) {
+        let fps_cap = self.fps_cap();
+
+        for buffer in self.buffers.values_mut() {
+            buffer.set_fps_cap(fps_cap);
+        }
+    }
 }
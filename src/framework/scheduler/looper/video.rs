@@ -0,0 +1,107 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    fs,
+    time::{Duration, Instant},
+};
+
+use libc::pid_t;
+use log::info;
+
+use super::Looper;
+
+/// Frame rates video is mastered at
+const VIDEO_FPS: [f64; 5] = [23.976, 24.0, 25.0, 29.97, 30.0];
+const VIDEO_TOLERANCE: f64 = 0.5;
+/// Thread names of `MediaCodec` and the common players built on it, truncated like `comm`
+const CODEC_THREADS: [&str; 3] = ["MediaCodec", "CodecLooper", "ExoPlayer"];
+const DETECT_TIME: Duration = Duration::from_secs(3);
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A game playing a full screen cutscene or ad holds the video frame rate no matter the
+/// frequency, so chasing its target there only burns power
+#[derive(Debug)]
+pub struct Video {
+    since: Option<Instant>,
+    playing: bool,
+    last_check: Instant,
+}
+
+impl Video {
+    pub fn new() -> Self {
+        Self {
+            since: None,
+            playing: false,
+            last_check: Instant::now(),
+        }
+    }
+
+    pub const fn playing(&self) -> bool {
+        self.playing
+    }
+}
+
+fn decoding(pid: pid_t) -> bool {
+    let Ok(tasks) = fs::read_dir(format!("/proc/{pid}/task")) else {
+        return false;
+    };
+
+    tasks.flatten().any(|task| {
+        fs::read_to_string(task.path().join("comm")).is_ok_and(|comm| {
+            CODEC_THREADS
+                .iter()
+                .any(|thread| comm.trim().starts_with(thread))
+        })
+    })
+}
+
+impl Looper {
+    pub fn update_video(&mut self) {
+        if self.video.last_check.elapsed() < CHECK_INTERVAL {
+            return;
+        }
+        self.video.last_check = Instant::now();
+
+        let video = self
+            .target_buffer()
+            .filter(|_| self.config.config().video_detect)
+            .filter(|buffer| {
+                let current_fps = buffer.current_fps;
+                buffer
+                    .target_fps
+                    .is_some_and(|target_fps| current_fps < target_fps - VIDEO_TOLERANCE)
+                    && VIDEO_FPS
+                        .iter()
+                        .any(|fps| (current_fps - fps).abs() <= VIDEO_TOLERANCE)
+                    && decoding(buffer.pid)
+            })
+            .map(|buffer| (buffer.pkg.clone(), buffer.current_fps));
+
+        let Some((pkg, current_fps)) = video else {
+            self.video.since = None;
+            if self.video.playing {
+                info!("Video playback ended");
+                self.video.playing = false;
+            }
+            return;
+        };
+
+        let since = *self.video.since.get_or_insert_with(Instant::now);
+        if !self.video.playing && since.elapsed() >= DETECT_TIME {
+            info!("[{pkg}] plays {current_fps:.2}fps video, leave frequencies to the kernel");
+            self.video.playing = true;
+        }
+    }
+}
@@ -0,0 +1,95 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::{Duration, Instant};
+
+use dumpsys_rs::Dumpsys;
+
+use super::{super::sensor::surfaceflinger_latency, Looper};
+
+const REFRESH_TIME: Duration = Duration::from_secs(2);
+
+pub struct Vsync {
+    dumper: Option<Dumpsys>,
+    timer: Option<Instant>,
+}
+
+impl Vsync {
+    pub const fn new() -> Self {
+        Self {
+            dumper: None,
+            timer: None,
+        }
+    }
+
+    /// Period and a recent vsync, from the actual present times of the layer of `pkg`,
+    /// which `SurfaceFlinger` latches on vsync
+    fn read(&mut self, pkg: &str) -> Option<(Duration, Instant)> {
+        if self.dumper.is_none() {
+            self.dumper = Dumpsys::new("SurfaceFlinger");
+        }
+
+        let latency = surfaceflinger_latency(self.dumper.as_ref()?, pkg)?;
+        let mut lines = latency.lines();
+        let period = lines.next()?.trim().parse().ok()?;
+        let present = lines
+            .filter_map(|line| line.split_whitespace().nth(1)?.parse::<i64>().ok())
+            .filter(|timestamp| *timestamp != 0 && *timestamp != i64::MAX)
+            .max()?;
+
+        let age = monotonic_ns().checked_sub(present)?;
+        let anchor = Instant::now().checked_sub(Duration::from_nanos(age.try_into().ok()?))?;
+
+        Some((Duration::from_nanos(period), anchor))
+    }
+}
+
+impl Looper {
+    pub fn update_vsync(&mut self) {
+        if !self.config.config().vsync_align || !self.state.is_active() {
+            if self.vsync.timer.take().is_some() {
+                for buffer in self.buffers.values_mut() {
+                    buffer.set_vsync(None);
+                }
+            }
+            return;
+        }
+
+        if self.vsync.timer.is_some_and(|t| t.elapsed() < REFRESH_TIME) {
+            return;
+        }
+        self.vsync.timer = Some(Instant::now());
+
+        let Some(pkg) = self.target_buffer().map(|buffer| buffer.pkg.clone()) else {
+            return;
+        };
+
+        let vsync = self.vsync.read(&pkg);
+        for buffer in self.buffers.values_mut().filter(|buffer| buffer.pkg == pkg) {
+            buffer.set_vsync(vsync);
+        }
+    }
+}
+
+fn monotonic_ns() -> i64 {
+    let mut time = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    unsafe {
+        libc::clock_gettime(libc::CLOCK_MONOTONIC, &raw mut time);
+    }
+
+    time.tv_sec * 1_000_000_000 + time.tv_nsec
+}
@@ -15,6 +15,7 @@
 #[cfg(feature = "use_binder")]
 mod binder;
 mod looper;
+mod sensor;
 mod topapp;
 
 use std::time::Duration;
@@ -32,6 +33,8 @@ use self::binder::FasServer;
 #[cfg(feature = "use_ebpf")]
 use frame_analyzer::Analyzer;
 use looper::Looper;
+pub use looper::{detected_daemons, replay, CONTROL_SOCKET};
+use sensor::Sensor;
 
 #[derive(Debug, Clone, Copy)]
 pub struct FasData {
@@ -75,18 +78,20 @@ impl Scheduler {
             .controller
             .ok_or(Error::SchedulerMissing("Controller"))?;
 
+        let sensor = Sensor::new()?;
+
         #[cfg(feature = "use_binder")]
         {
             let mut node = Node::init()?;
             let rx = FasServer::run_server(&mut node, config.clone())?;
-            Looper::new(rx, config, node, extension, controller).enter_loop()
+            Looper::new(rx, config, node, extension, sensor, controller).enter_loop()
         }
 
         #[cfg(feature = "use_ebpf")]
         {
             let node = Node::init()?;
             let analyzer = Analyzer::new()?;
-            Looper::new(analyzer, config, node, extension, controller).enter_loop()
+            Looper::new(analyzer, config, node, extension, sensor, controller).enter_loop()
         }
     }
 }
@@ -0,0 +1,161 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    sync::mpsc::{Receiver, RecvTimeoutError, Sender},
+    time::Duration,
+};
+
+use dumpsys_rs::Dumpsys;
+use libc::pid_t;
+
+use super::{super::FasData, fence::FenceTracer};
+use crate::framework::config::SensorKind;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+pub enum Command {
+    Attach(pid_t, String, SensorKind),
+    Detach,
+}
+
+pub fn thread(commands: &Receiver<Command>, sx: &Sender<FasData>) {
+    let surfaceflinger = Dumpsys::new("SurfaceFlinger");
+    let gfxinfo = Dumpsys::new("gfxinfo");
+    let mut fence: Option<FenceTracer> = None;
+
+    let mut target = None;
+    let mut last_timestamp = 0;
+
+    loop {
+        match commands.recv_timeout(POLL_INTERVAL) {
+            Ok(Command::Attach(pid, pkg, kind)) => {
+                if kind == SensorKind::Fence && fence.is_none() {
+                    fence = FenceTracer::new();
+                }
+
+                if let Some(fence) = fence.as_mut() {
+                    if kind == SensorKind::Fence {
+                        fence.attach(pid);
+                    } else {
+                        fence.detach();
+                    }
+                }
+
+                target = Some((pid, pkg, kind));
+                last_timestamp = 0;
+            }
+            Ok(Command::Detach) => {
+                if let Some(fence) = fence.as_mut() {
+                    fence.detach();
+                }
+                target = None;
+            }
+            Err(RecvTimeoutError::Disconnected) => return,
+            Err(RecvTimeoutError::Timeout) => (),
+        }
+
+        let Some((pid, pkg, kind)) = &target else {
+            continue;
+        };
+
+        let timestamps = match kind {
+            SensorKind::SurfaceFlinger => surfaceflinger
+                .as_ref()
+                .and_then(|dumper| surfaceflinger_timestamps(dumper, pkg)),
+            SensorKind::Gfxinfo => gfxinfo
+                .as_ref()
+                .and_then(|dumper| gfxinfo_timestamps(dumper, pkg)),
+            SensorKind::Fence => fence.as_mut().and_then(FenceTracer::timestamps),
+            SensorKind::Ebpf | SensorKind::Zygisk => None,
+        };
+
+        let Some(mut timestamps) = timestamps else {
+            continue;
+        };
+        timestamps.sort_unstable();
+        timestamps.retain(|timestamp| *timestamp > last_timestamp);
+
+        for timestamp in timestamps {
+            if last_timestamp > 0 {
+                let frametime = Duration::from_nanos((timestamp - last_timestamp) as u64);
+                if sx
+                    .send(FasData {
+                        pid: *pid,
+                        frametime,
+                    })
+                    .is_err()
+                {
+                    return;
+                }
+            }
+
+            last_timestamp = timestamp;
+        }
+    }
+}
+
+/// `--latency` of the layer of `pkg`: the refresh period, then one row of
+/// desired present, actual present and frame ready time per frame
+pub fn surfaceflinger_latency(dumper: &Dumpsys, pkg: &str) -> Option<String> {
+    let layers = dumper.dump(&["--list"]).ok()?;
+    let layer = layers
+        .lines()
+        .filter(|layer| layer.contains(pkg))
+        .max_by_key(|layer| layer.contains("SurfaceView"))?
+        .trim();
+
+    dumper.dump(&["--latency", layer]).ok()
+}
+
+fn surfaceflinger_timestamps(dumper: &Dumpsys, pkg: &str) -> Option<Vec<i64>> {
+    let latency = surfaceflinger_latency(dumper, pkg)?;
+
+    Some(
+        latency
+            .lines()
+            .skip(1)
+            .filter_map(|line| line.split_whitespace().nth(1)?.parse().ok())
+            .filter(|timestamp| *timestamp != 0 && *timestamp != i64::MAX)
+            .collect(),
+    )
+}
+
+fn gfxinfo_timestamps(dumper: &Dumpsys, pkg: &str) -> Option<Vec<i64>> {
+    let dump = dumper.dump(&[pkg, "framestats"]).ok()?;
+    let mut lines = dump
+        .lines()
+        .skip_while(|line| !line.starts_with("---PROFILEDATA---"))
+        .skip(1);
+
+    let header: Vec<_> = lines.next()?.split(',').collect();
+    let flags = header.iter().position(|column| *column == "Flags")?;
+    let completed = header
+        .iter()
+        .position(|column| *column == "FrameCompleted")?;
+
+    Some(
+        lines
+            .take_while(|line| !line.starts_with("---PROFILEDATA---"))
+            .filter_map(|line| {
+                let columns: Vec<_> = line.split(',').collect();
+                if columns.get(flags)?.trim() != "0" {
+                    return None;
+                }
+
+                columns.get(completed)?.trim().parse().ok()
+            })
+            .collect(),
+    )
+}
@@ -0,0 +1,227 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{self, File, OpenOptions},
+    io::{ErrorKind, Read},
+    os::unix::fs::OpenOptionsExt,
+    path::{Path, PathBuf},
+};
+
+use libc::pid_t;
+use log::{info, warn};
+
+const TRACEFS: [&str; 2] = ["/sys/kernel/tracing", "/sys/kernel/debug/tracing"];
+/// Own ftrace instance, so the global buffer and other tracers are left alone
+const INSTANCE: &str = "instances/fas_rs_fence";
+const EVENTS: [&str; 2] = [
+    "events/dma_fence/dma_fence_init/enable",
+    "events/dma_fence/dma_fence_signaled/enable",
+];
+/// Fence contexts signalling faster than this can't be presenting frames
+const MIN_INTERVAL_NS: i64 = 4_000_000;
+/// Unsignalled fences kept before old ones are dropped
+const MAX_PENDING: usize = 4096;
+
+/// Derives frame completion from GPU fences the game creates, which keeps working when the
+/// libgui uprobe misses frames of custom swapchains or inlined symbols.
+///
+/// Every fence the game process initialises is remembered by (context, seqno), their signal
+/// times are grouped per context, and the context signalling at a plausible frame rate is
+/// taken as the one presenting frames. KGSL and Mali both back their timelines with `dma_fence`
+pub struct FenceTracer {
+    instance: PathBuf,
+    pipe: Option<File>,
+    pid: Option<pid_t>,
+    pending: HashSet<(u64, u64)>,
+    context: Option<u64>,
+    buf: String,
+}
+
+impl FenceTracer {
+    pub fn new() -> Option<Self> {
+        let tracefs = TRACEFS.iter().map(Path::new).find(|path| path.exists())?;
+        let instance = tracefs.join(INSTANCE);
+
+        if !instance.exists() {
+            fs::create_dir(&instance)
+                .map_err(|e| warn!("Failed to create ftrace instance for fences: {e}"))
+                .ok()?;
+        }
+
+        if EVENTS.iter().any(|event| !instance.join(event).exists()) {
+            warn!("dma_fence tracepoints are not available, fence sensor disabled");
+            let _ = fs::remove_dir(&instance);
+            return None;
+        }
+
+        let _ = fs::write(instance.join("trace_clock"), "mono");
+        let _ = fs::write(instance.join("options/record-tgid"), "1");
+
+        Some(Self {
+            instance,
+            pipe: None,
+            pid: None,
+            pending: HashSet::new(),
+            context: None,
+            buf: String::new(),
+        })
+    }
+
+    pub fn attach(&mut self, pid: pid_t) {
+        if self.pid == Some(pid) {
+            return;
+        }
+
+        self.detach();
+        self.set_enabled(true);
+        self.pipe = OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(self.instance.join("trace_pipe"))
+            .map_err(|e| warn!("Failed to open fence trace pipe: {e}"))
+            .ok();
+        self.pid = Some(pid);
+
+        info!("Fence sensor attached to pid {pid}");
+    }
+
+    pub fn detach(&mut self) {
+        if self.pid.take().is_some() {
+            self.set_enabled(false);
+        }
+
+        self.pipe = None;
+        self.pending.clear();
+        self.context = None;
+    }
+
+    /// Signal times in nanoseconds of the frame context since the last call
+    pub fn timestamps(&mut self) -> Option<Vec<i64>> {
+        let pid = self.pid?;
+        let pipe = self.pipe.as_mut()?;
+
+        self.buf.clear();
+        let mut chunk = [0; 16 * 1024];
+        loop {
+            match pipe.read(&mut chunk) {
+                Ok(0) => break,
+                Ok(len) => self.buf.push_str(&String::from_utf8_lossy(&chunk[..len])),
+                Err(e) if e.kind() == ErrorKind::Interrupted => (),
+                Err(_) => break,
+            }
+        }
+
+        let mut signals: HashMap<u64, Vec<i64>> = HashMap::new();
+        for line in self.buf.lines() {
+            let Some(event) = Event::parse(line) else {
+                continue;
+            };
+
+            match event.name {
+                "dma_fence_init" if event.tgid == Some(pid) => {
+                    if self.pending.len() >= MAX_PENDING {
+                        self.pending.clear();
+                    }
+                    self.pending.insert((event.context, event.seqno));
+                }
+                "dma_fence_signaled" if self.pending.remove(&(event.context, event.seqno)) => {
+                    signals
+                        .entry(event.context)
+                        .or_default()
+                        .push(event.timestamp);
+                }
+                _ => (),
+            }
+        }
+
+        if !self
+            .context
+            .is_some_and(|context| signals.contains_key(&context))
+        {
+            self.context = signals
+                .iter()
+                .filter(|(_, times)| times.len() > 1 && min_interval(times) >= MIN_INTERVAL_NS)
+                .max_by_key(|(_, times)| times.len())
+                .map(|(context, _)| *context);
+        }
+
+        signals.remove(&self.context?)
+    }
+
+    fn set_enabled(&self, enabled: bool) {
+        let value = if enabled { "1" } else { "0" };
+        for event in EVENTS {
+            let _ = fs::write(self.instance.join(event), value);
+        }
+        let _ = fs::write(self.instance.join("tracing_on"), value);
+    }
+}
+
+impl Drop for FenceTracer {
+    fn drop(&mut self) {
+        self.detach();
+        let _ = fs::remove_dir(&self.instance);
+    }
+}
+
+fn min_interval(times: &[i64]) -> i64 {
+    let mut times = times.to_vec();
+    times.sort_unstable();
+    times
+        .windows(2)
+        .map(|pair| pair[1] - pair[0])
+        .min()
+        .unwrap_or(i64::MAX)
+}
+
+struct Event<'a> {
+    tgid: Option<pid_t>,
+    timestamp: i64,
+    name: &'a str,
+    context: u64,
+    seqno: u64,
+}
+
+impl<'a> Event<'a> {
+    /// `<comm>-<tid> (<tgid>) [cpu] flags <secs>.<usecs>: <event>: driver=.. timeline=.. context=.. seqno=..`
+    fn parse(line: &'a str) -> Option<Self> {
+        let (head, rest) = line.split_once(": ")?;
+        let (name, fields) = rest.split_once(": ")?;
+
+        let tgid = head
+            .split_once('(')
+            .and_then(|(_, tgid)| tgid.split_once(')'))
+            .and_then(|(tgid, _)| tgid.trim().parse().ok());
+        let (secs, usecs) = head.split_whitespace().last()?.split_once('.')?;
+        let timestamp =
+            secs.parse::<i64>().ok()? * 1_000_000_000 + usecs.parse::<i64>().ok()? * 1000;
+
+        let field = |key: &str| {
+            fields
+                .split_whitespace()
+                .find_map(|field| field.strip_prefix(key)?.strip_prefix('='))
+                .and_then(|value| value.parse().ok())
+        };
+
+        Some(Self {
+            tgid,
+            timestamp,
+            name,
+            context: field("context")?,
+            seqno: field("seqno")?,
+        })
+    }
+}
@@ -0,0 +1,160 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod dumpsys;
+mod fence;
+
+use std::{
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+    time::{Duration, Instant},
+};
+
+use libc::pid_t;
+use log::info;
+
+use super::FasData;
+use crate::framework::{
+    config::{Config, SensorKind},
+    error::Result,
+};
+pub use dumpsys::surfaceflinger_latency;
+use dumpsys::Command;
+
+const FALLBACK_TIME: Duration = Duration::from_secs(3);
+// A static menu also produces no frames, so the preferred source gets another try after a while
+const REPROBE_TIME: Duration = Duration::from_secs(30);
+
+impl SensorKind {
+    pub const fn is_available(self) -> bool {
+        match self {
+            Self::Ebpf => cfg!(feature = "use_ebpf"),
+            Self::Zygisk => cfg!(feature = "use_binder"),
+            Self::SurfaceFlinger | Self::Gfxinfo | Self::Fence => true,
+        }
+    }
+
+    pub const fn is_native(self) -> bool {
+        matches!(self, Self::Ebpf | Self::Zygisk)
+    }
+
+    const fn native() -> Self {
+        if cfg!(feature = "use_ebpf") {
+            Self::Ebpf
+        } else {
+            Self::Zygisk
+        }
+    }
+}
+
+pub struct Sensor {
+    game: Option<(pid_t, String)>,
+    candidates: Vec<SensorKind>,
+    current: usize,
+    last_frame: Instant,
+    fallback: Instant,
+    command: Sender<Command>,
+    rx: Receiver<FasData>,
+}
+
+impl Sensor {
+    pub fn new() -> Result<Self> {
+        let (command, command_rx) = mpsc::channel();
+        let (sx, rx) = mpsc::channel();
+
+        thread::Builder::new()
+            .name("SensorThread".into())
+            .spawn(move || dumpsys::thread(&command_rx, &sx))?;
+
+        Ok(Self {
+            game: None,
+            candidates: vec![SensorKind::native()],
+            current: 0,
+            last_frame: Instant::now(),
+            fallback: Instant::now(),
+            command,
+            rx,
+        })
+    }
+
+    pub fn kind(&self) -> SensorKind {
+        self.candidates
+            .get(self.current)
+            .copied()
+            .unwrap_or_else(SensorKind::native)
+    }
+
+    pub fn update(&mut self, game: Option<(pid_t, String)>, config: &Config) {
+        if self.game != game {
+            self.candidates = game.as_ref().map_or_else(Vec::new, |(_, pkg)| {
+                config
+                    .sensor_priority(pkg)
+                    .into_iter()
+                    .filter(|kind| kind.is_available())
+                    .collect()
+            });
+
+            if self.candidates.is_empty() {
+                self.candidates.push(SensorKind::native());
+            }
+
+            self.game = game;
+            self.current = 0;
+            self.last_frame = Instant::now();
+            self.attach();
+        } else if self.game.is_some()
+            && self.last_frame.elapsed() > FALLBACK_TIME
+            && self.current + 1 < self.candidates.len()
+        {
+            let last = self.kind();
+            self.current += 1;
+            self.last_frame = Instant::now();
+            self.fallback = Instant::now();
+
+            info!(
+                "Sensor {last:?} got no frame, fallback to {:?}",
+                self.kind()
+            );
+
+            self.attach();
+        } else if self.game.is_some() && self.current > 0 && self.fallback.elapsed() > REPROBE_TIME
+        {
+            self.current = 0;
+            self.last_frame = Instant::now();
+
+            info!("Probe preferred sensor {:?} again", self.kind());
+
+            self.attach();
+        }
+    }
+
+    pub fn frame_received(&mut self) {
+        self.last_frame = Instant::now();
+    }
+
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<FasData> {
+        self.rx.recv_timeout(timeout).ok()
+    }
+
+    fn attach(&self) {
+        let command = match &self.game {
+            Some((pid, pkg)) if !self.kind().is_native() => {
+                Command::Attach(*pid, pkg.clone(), self.kind())
+            }
+            _ => Command::Detach,
+        };
+
+        let _ = self.command.send(command);
+    }
+}
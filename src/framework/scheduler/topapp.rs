@@ -22,19 +22,79 @@ const REFRESH_TIME: Duration = Duration::from_secs(1);
 struct WindowsInfo {
     pub visible_freeform_window: bool,
     pub pids: Vec<i32>,
+    pub visible_packages: Vec<String>,
+    pub focused_display: Option<i32>,
+    pub focused_activity: Option<String>,
 }
 
 impl WindowsInfo {
+    /// `visible-apps` starts with the display contents, so one dump carries both the
+    /// windows and the focused app of every display
     pub fn new(dump: &str) -> Self {
         let pids = Self::parse_top_app(dump);
+        let visible_packages = Self::parse_visible_packages(dump);
         let visible_freeform_window = dump.contains("freeform");
+        let (focused_display, focused_activity) = Self::parse_focused_activity(dump).unzip();
 
         Self {
             visible_freeform_window,
             pids,
+            visible_packages,
+            focused_display: focused_display.flatten(),
+            focused_activity,
         }
     }
 
+    /// Every display has its own focused app, the one of the top focused display wins
+    fn parse_focused_activity(dump: &str) -> Option<(Option<i32>, String)> {
+        let value = |line: &str, key: &str| {
+            line.split(key)
+                .nth(1)?
+                .split_whitespace()
+                .next()?
+                .parse::<i32>()
+                .ok()
+        };
+
+        let top_focused = dump.lines().find_map(|l| value(l, "mTopFocusedDisplayId="));
+
+        let mut display = None;
+        let mut focused = Vec::new();
+        for line in dump.lines() {
+            if line.contains("mDisplayId=") {
+                display = value(line, "mDisplayId=");
+            } else if line.contains("mFocusedApp=") {
+                if let Some(activity) = line.split_whitespace().find(|s| s.contains('/')) {
+                    focused.push((display, activity.trim_end_matches('}').to_string()));
+                }
+            }
+        }
+
+        let index = focused
+            .iter()
+            .position(|(display, _)| top_focused.is_some() && *display == top_focused)
+            .unwrap_or(0);
+        if focused.is_empty() {
+            None
+        } else {
+            Some(focused.swap_remove(index))
+        }
+    }
+
+    /// Window headers look like `Window{7a3c2b1 u0 com.example.game/.MainActivity}:`
+    fn parse_visible_packages(dump: &str) -> Vec<String> {
+        let mut packages: Vec<String> = dump
+            .lines()
+            .filter_map(|l| l.split("Window{").nth(1)?.split_whitespace().nth(2))
+            .filter(|s| s.contains('/'))
+            .filter_map(|s| s.split('/').next())
+            .map(String::from)
+            .collect();
+        packages.sort_unstable();
+        packages.dedup();
+        packages
+    }
+
     fn parse_top_app(dump: &str) -> Vec<i32> {
         dump.lines()
             .filter(|l| l.contains("Session{"))
@@ -68,6 +128,18 @@ impl TimedWatcher {
         self.cache().visible_freeform_window
     }
 
+    pub fn visible_packages(&mut self) -> &Vec<String> {
+        &self.cache().visible_packages
+    }
+
+    pub fn focused_display(&mut self) -> Option<i32> {
+        self.cache().focused_display
+    }
+
+    pub fn focused_activity(&mut self) -> Option<String> {
+        self.cache().focused_activity.clone()
+    }
+
     fn cache(&mut self) -> &WindowsInfo {
         if self.last_refresh.elapsed() > REFRESH_TIME {
             let dump = self.windows_dumper.dump(&["visible-apps"]).unwrap();
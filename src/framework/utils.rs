@@ -12,9 +12,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::{fs, path::Path};
+use std::{fs, path::Path, time::Duration};
 
-use crate::framework::Result;
+use crate::framework::{error::Error, Result};
 
 pub fn get_process_name(pid: i32) -> Result<String> {
     let cmdline = Path::new("/proc").join(pid.to_string()).join("cmdline");
@@ -22,3 +22,23 @@ pub fn get_process_name(pid: i32) -> Result<String> {
     let cmdline = cmdline.split(':').next().unwrap_or_default();
     Ok(cmdline.trim_matches(['\0']).trim().to_string())
 }
+
+pub fn get_process_age(pid: i32) -> Result<Duration> {
+    let stat = fs::read_to_string(Path::new("/proc").join(pid.to_string()).join("stat"))?;
+    let start_ticks: u64 = stat
+        .rsplit_once(')')
+        .and_then(|(_, stat)| stat.split_whitespace().nth(19))
+        .and_then(|t| t.parse().ok())
+        .ok_or(Error::Other("Failed to parse process stat"))?;
+
+    let uptime: f64 = fs::read_to_string("/proc/uptime")?
+        .split_whitespace()
+        .next()
+        .and_then(|t| t.parse().ok())
+        .ok_or(Error::Other("Failed to parse uptime"))?;
+
+    let ticks_per_sec = unsafe { libc::sysconf(libc::_SC_CLK_TCK) }.max(1) as f64;
+    let started = start_ticks as f64 / ticks_per_sec;
+
+    Ok(Duration::from_secs_f64((uptime - started).max(0.0)))
+}
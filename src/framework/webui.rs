@@ -0,0 +1,274 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    fmt::Write as _,
+    fs,
+    io::{self, BufRead, BufReader, ErrorKind, Read, Write},
+    net::{Ipv4Addr, TcpListener, TcpStream},
+    os::{
+        linux::net::SocketAddrExt,
+        unix::{
+            fs::OpenOptionsExt,
+            net::{SocketAddr, UnixStream},
+        },
+    },
+    path::Path,
+    thread,
+    time::{Duration, Instant},
+};
+
+use log::{info, warn};
+use serde_json::Value;
+
+use super::{error::Result, scheduler::CONTROL_SOCKET};
+use crate::misc;
+
+/// Only root (and so the module `WebUI` through `ksu.exec`) can read the token
+const TOKEN_NODE: &str = "/dev/fas_rs/webui_token";
+const MAX_BODY: usize = 1024 * 1024;
+const MAX_LINE: u64 = 8 * 1024;
+const MAX_HEADERS: usize = 64;
+/// The server handles one connection at a time, a slow client must not hold it any longer
+const REQUEST_DEADLINE: Duration = Duration::from_secs(5);
+
+struct Response {
+    status: &'static str,
+    content_type: &'static str,
+    body: String,
+}
+
+impl Response {
+    fn new(status: &'static str, body: impl Into<String>) -> Self {
+        Self {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body: body.into(),
+        }
+    }
+
+    fn json(value: &Value) -> Self {
+        Self {
+            status: "200 OK",
+            content_type: "application/json",
+            body: value.to_string(),
+        }
+    }
+}
+
+/// Serve config read/write and live stats on `127.0.0.1:port` for the module's `WebUI` page.
+/// Every request needs `Authorization: Bearer <token>`, the token is in `/dev/fas_rs/webui_token`.
+/// Requests are passed on to the daemon over its control socket, so they go through the same
+/// uid check as every other control client
+pub fn spawn(port: u16) -> Result<()> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+    let token = generate_token()?;
+    let _ = fs::create_dir_all(Path::new(TOKEN_NODE).parent().unwrap());
+    fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(TOKEN_NODE)?
+        .write_all(token.as_bytes())?;
+
+    thread::Builder::new()
+        .name("WebUiServer".into())
+        .spawn(move || {
+            misc::set_idle_priority();
+            for stream in listener.incoming().filter_map(std::result::Result::ok) {
+                let _ = stream.set_write_timeout(Some(REQUEST_DEADLINE));
+                handle(stream, &token).unwrap_or_else(|e| warn!("WebUI request failed: {e}"));
+            }
+        })?;
+
+    info!("WebUI backend listening on 127.0.0.1:{port}");
+    Ok(())
+}
+
+fn generate_token() -> Result<String> {
+    let mut bytes = [0; 16];
+    fs::File::open("/dev/urandom")?.read_exact(&mut bytes)?;
+
+    Ok(bytes.iter().fold(String::new(), |mut token, byte| {
+        let _ = write!(token, "{byte:02x}");
+        token
+    }))
+}
+
+/// Reads of a connection time out once its deadline has passed, however the bytes trickle in
+struct DeadlineStream {
+    stream: TcpStream,
+    deadline: Instant,
+}
+
+impl Read for DeadlineStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(ErrorKind::TimedOut.into());
+        }
+
+        self.stream.set_read_timeout(Some(remaining))?;
+        self.stream.read(buf)
+    }
+}
+
+/// A line of at most `MAX_LINE` bytes, `None` if it is longer
+fn read_line(reader: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut line = String::new();
+    reader.take(MAX_LINE).read_line(&mut line)?;
+
+    if line.len() as u64 >= MAX_LINE && !line.ends_with('\n') {
+        Ok(None)
+    } else {
+        Ok(Some(line))
+    }
+}
+
+fn handle(stream: TcpStream, token: &str) -> std::io::Result<()> {
+    let mut reader = BufReader::new(DeadlineStream {
+        stream: stream.try_clone()?,
+        deadline: Instant::now() + REQUEST_DEADLINE,
+    });
+    let too_large = Response::new("431 Request Header Fields Too Large", "headers too large");
+
+    let Some(request_line) = read_line(&mut reader)? else {
+        return write_response(stream, &too_large);
+    };
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let path = parts.next().unwrap_or_default().to_string();
+
+    let mut content_length = 0;
+    let mut authorized = false;
+    for count in 0.. {
+        let Some(line) = read_line(&mut reader)? else {
+            return write_response(stream, &too_large);
+        };
+        if line.trim().is_empty() {
+            break;
+        }
+        if count >= MAX_HEADERS {
+            return write_response(stream, &too_large);
+        }
+
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match name.trim().to_ascii_lowercase().as_str() {
+            "content-length" => content_length = value.parse().unwrap_or(0),
+            "authorization" => {
+                authorized = value
+                    .strip_prefix("Bearer ")
+                    .is_some_and(|value| constant_time_eq(value.as_bytes(), token.as_bytes()));
+            }
+            _ => (),
+        }
+    }
+
+    let response = if method == "OPTIONS" {
+        Response::new("204 No Content", "")
+    } else if !authorized {
+        Response::new("401 Unauthorized", "missing or wrong token")
+    } else if content_length > MAX_BODY {
+        Response::new("413 Payload Too Large", "config too large")
+    } else {
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body)?;
+        route(&method, &path, &String::from_utf8_lossy(&body))
+    };
+
+    write_response(stream, &response)
+}
+
+/// Whether `a` and `b` are equal, in a time that only depends on their length
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+fn route(method: &str, path: &str, body: &str) -> Response {
+    let (verb, request) = match (method, path) {
+        ("GET", "/config") => ("config", "config".to_string()),
+        ("PUT" | "POST", "/config") => (
+            "config-set",
+            format!("config-set {}", Value::String(body.to_string())),
+        ),
+        ("GET", "/stats") => ("stats", "stats".to_string()),
+        _ => return Response::new("404 Not Found", "unknown endpoint"),
+    };
+
+    let reply = match control_request(verb, &request) {
+        Ok(reply) => reply,
+        Err(e) => {
+            return Response::new(
+                "503 Service Unavailable",
+                format!("control socket is not available: {e}"),
+            )
+        }
+    };
+
+    match (reply["error"].as_str(), reply["rejected"].as_str(), verb) {
+        (Some(e), _, _) => Response::new("500 Internal Server Error", e),
+        (None, Some(e), _) => Response::new("400 Bad Request", e),
+        (None, None, "config") => {
+            Response::new("200 OK", reply["config"].as_str().unwrap_or_default())
+        }
+        (None, None, "stats") => Response::json(&reply["stats"]),
+        (None, None, _) => Response::new("200 OK", "ok"),
+    }
+}
+
+/// Send `request` to the daemon and wait for its reply to `verb`. The socket also pushes overlay
+/// stats to every client, those lines are skipped
+fn control_request(verb: &str, request: &str) -> io::Result<Value> {
+    let addr = SocketAddr::from_abstract_name(CONTROL_SOCKET)?;
+    let mut stream = UnixStream::connect_addr(&addr)?;
+    stream.set_read_timeout(Some(REQUEST_DEADLINE))?;
+    stream.set_write_timeout(Some(REQUEST_DEADLINE))?;
+    writeln!(stream, "{request}")?;
+
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(ErrorKind::UnexpectedEof.into());
+        }
+
+        if let Ok(reply) = serde_json::from_str::<Value>(&line) {
+            if reply["reply"] == verb {
+                return Ok(reply);
+            }
+        }
+    }
+}
+
+fn write_response(mut stream: TcpStream, response: &Response) -> std::io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\n\
+         Content-Type: {}\r\n\
+         Content-Length: {}\r\n\
+         Access-Control-Allow-Origin: *\r\n\
+         Access-Control-Allow-Methods: GET, PUT, POST, OPTIONS\r\n\
+         Access-Control-Allow-Headers: Authorization, Content-Type\r\n\
+         Connection: close\r\n\r\n{}",
+        response.status,
+        response.content_type,
+        response.body.len(),
+        response.body
+    )
+}
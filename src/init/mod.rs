@@ -0,0 +1,271 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod probe;
+mod soc;
+
+use std::{
+    fmt::Write as _,
+    fs,
+    io::{self, prelude::*},
+    path::Path,
+};
+
+use anyhow::{anyhow, bail, Result};
+use toml::{Table, Value};
+
+use crate::framework::Format;
+use probe::DeviceInfo;
+pub use soc::SocProfile;
+
+const HELP: &str = "\
+fas-rs init [options]:
+    --output <path>     where to write the generated config
+    --std <path>        import game list from the standard config
+    --keep-std <bool>   value of config.keep_std
+    --scene <bool>      value of config.scene_game_list
+    --yes               use defaults instead of asking
+    --force             overwrite an existing config\
+";
+
+#[derive(Debug, Default)]
+struct Options {
+    output: Option<String>,
+    std_path: Option<String>,
+    keep_std: Option<bool>,
+    scene_game_list: Option<bool>,
+    assume_yes: bool,
+    force: bool,
+}
+
+impl Options {
+    fn parse(args: &[String]) -> Result<Self> {
+        let mut options = Self::default();
+        let mut args = args.iter();
+
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .cloned()
+                    .ok_or_else(|| anyhow!("Missing value of {arg}\n{HELP}"))
+            };
+
+            match arg.as_str() {
+                "--output" => options.output = Some(value()?),
+                "--std" => options.std_path = Some(value()?),
+                "--keep-std" => options.keep_std = Some(value()?.parse()?),
+                "--scene" => options.scene_game_list = Some(value()?.parse()?),
+                "--yes" | "-y" => options.assume_yes = true,
+                "--force" | "-f" => options.force = true,
+                _ => bail!("Unknown argument {arg}\n{HELP}"),
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+pub fn init<P: AsRef<Path>>(args: &[String], default_output: P) -> Result<()> {
+    let options = Options::parse(args)?;
+    let output = options
+        .output
+        .as_ref()
+        .map_or_else(|| default_output.as_ref(), Path::new);
+
+    if output.exists() && !options.force {
+        bail!("{output:?} already exists, use --force to overwrite it");
+    }
+
+    let device = DeviceInfo::probe();
+    let soc = SocProfile::detect(device.platform.as_deref());
+    print_device(&device, &soc);
+
+    let keep_std = match options.keep_std {
+        Some(keep_std) => keep_std,
+        None => ask(
+            options.assume_yes,
+            "Keep the standard profile when the module updates?",
+            true,
+        )?,
+    };
+    let scene_game_list = match options.scene_game_list {
+        Some(scene_game_list) => scene_game_list,
+        None => ask(options.assume_yes, "Use the game list of Scene?", true)?,
+    };
+
+    let game_list = match &options.std_path {
+        Some(std_path) => std_game_list(std_path, device.max_refresh_rate())?,
+        None => Table::new(),
+    };
+
+    let config = generate(&device, &soc, keep_std, scene_game_list, &game_list);
+    let config = match Format::from_path(output) {
+        Format::Toml => config,
+        // The comments of the generated config only survive in toml
+        format => format.serialize(&toml::from_str::<Table>(&config)?)?,
+    };
+
+    if let Some(parent) = output.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(output, config)?;
+
+    println!("Starter config written to {}", output.display());
+
+    Ok(())
+}
+
+fn print_device(device: &DeviceInfo, soc: &SocProfile) {
+    println!(
+        "Platform: {} ({:?})",
+        device.platform.as_deref().unwrap_or("unknown"),
+        soc.family
+    );
+
+    for cluster in &device.clusters {
+        println!(
+            "Cluster policy{}: cpus {:?}, {}-{} KHz",
+            cluster.policy, cluster.cpus, cluster.min_freq, cluster.max_freq
+        );
+    }
+
+    println!("Refresh rates: {:?}", device.refresh_rates);
+    println!("Thermal zones: {}", device.thermal_zones.len());
+}
+
+fn ask(assume_yes: bool, question: &str, default: bool) -> Result<bool> {
+    if assume_yes {
+        return Ok(default);
+    }
+
+    let hint = if default { "[Y/n]" } else { "[y/N]" };
+
+    loop {
+        print!("{question} {hint} ");
+        io::stdout().flush()?;
+
+        let mut answer = String::new();
+        if io::stdin().read_line(&mut answer)? == 0 {
+            return Ok(default);
+        }
+
+        match answer.trim().to_lowercase().as_str() {
+            "" => return Ok(default),
+            "y" | "yes" => return Ok(true),
+            "n" | "no" => return Ok(false),
+            _ => println!("Please answer y or n"),
+        }
+    }
+}
+
+fn std_game_list<P: AsRef<Path>>(std_path: P, max_refresh_rate: Option<u32>) -> Result<Table> {
+    let std_config: Table = toml::from_str(&fs::read_to_string(std_path)?)?;
+    let Some(Value::Table(game_list)) = std_config.get("game_list") else {
+        return Ok(Table::new());
+    };
+
+    let Some(max_refresh_rate) = max_refresh_rate else {
+        return Ok(game_list.clone());
+    };
+
+    Ok(game_list
+        .iter()
+        .map(|(pkg, target_fps)| (pkg.clone(), cap_target_fps(target_fps, max_refresh_rate)))
+        .collect())
+}
+
+fn cap_target_fps(target_fps: &Value, max_refresh_rate: u32) -> Value {
+    let max_refresh_rate = i64::from(max_refresh_rate);
+
+    match target_fps {
+        Value::Array(arr) => {
+            let capped: Vec<_> = arr
+                .iter()
+                .filter(|fps| fps.as_integer().is_some_and(|fps| fps <= max_refresh_rate))
+                .cloned()
+                .collect();
+
+            if capped.is_empty() {
+                Value::Integer(max_refresh_rate)
+            } else {
+                Value::Array(capped)
+            }
+        }
+        Value::Integer(fps) => Value::Integer((*fps).min(max_refresh_rate)),
+        _ => target_fps.clone(),
+    }
+}
+
+fn generate(
+    device: &DeviceInfo,
+    soc: &SocProfile,
+    keep_std: bool,
+    scene_game_list: bool,
+    game_list: &Table,
+) -> String {
+    let mut config = String::new();
+
+    let _ = writeln!(config, "# Generated by `fas-rs init`");
+    let _ = writeln!(
+        config,
+        "# Platform: {} ({:?})",
+        device.platform.as_deref().unwrap_or("unknown"),
+        soc.family
+    );
+    for cluster in &device.clusters {
+        let _ = writeln!(
+            config,
+            "# policy{}: cpus {:?}, {}-{} KHz",
+            cluster.policy, cluster.cpus, cluster.min_freq, cluster.max_freq
+        );
+    }
+    let _ = writeln!(config, "# Refresh rates: {:?}", device.refresh_rates);
+    for zone in &device.thermal_zones {
+        let _ = writeln!(config, "# thermal_zone{}: {}", zone.id, zone.kind);
+    }
+    let _ = writeln!(config);
+
+    let _ = writeln!(config, "[config]");
+    let _ = writeln!(
+        config,
+        "# Keep the standard profile and only keep the local game list when merging"
+    );
+    let _ = writeln!(config, "keep_std = {keep_std}");
+    let _ = writeln!(config, "# Also use the game list of Scene");
+    let _ = writeln!(config, "scene_game_list = {scene_game_list}");
+    let _ = writeln!(config);
+
+    let _ = writeln!(config, "[game_list]");
+    let _ = writeln!(
+        config,
+        "# \"package\" = target_fps, a single fps or an array matched at runtime"
+    );
+    if game_list.is_empty() {
+        let fps = device.max_refresh_rate().unwrap_or(60).min(120);
+        let _ = writeln!(config, "# \"com.example.game\" = [30, 60, {fps}]");
+    }
+    for (pkg, target_fps) in game_list {
+        let _ = writeln!(config, "{} = {target_fps}", Value::String(pkg.clone()));
+    }
+
+    let modes = ["powersave", "balance", "performance", "fast"];
+    for (mode, margin) in modes.iter().zip(soc.margins) {
+        let _ = writeln!(config);
+        let _ = writeln!(config, "[{mode}]");
+        let _ = writeln!(config, "# Allowed frame drop margin(ms)");
+        let _ = writeln!(config, "margin = {margin}");
+    }
+
+    config
+}
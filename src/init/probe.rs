@@ -0,0 +1,126 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fs;
+
+use dumpsys_rs::Dumpsys;
+
+use crate::{cpu_common, misc::getprop};
+
+#[derive(Debug)]
+pub struct Cluster {
+    pub policy: i32,
+    pub cpus: Vec<i32>,
+    pub min_freq: isize,
+    pub max_freq: isize,
+}
+
+#[derive(Debug)]
+pub struct ThermalZone {
+    pub id: u32,
+    pub kind: String,
+}
+
+#[derive(Debug)]
+pub struct DeviceInfo {
+    pub platform: Option<String>,
+    pub clusters: Vec<Cluster>,
+    pub refresh_rates: Vec<u32>,
+    pub thermal_zones: Vec<ThermalZone>,
+}
+
+impl DeviceInfo {
+    pub fn probe() -> Self {
+        Self {
+            platform: getprop("ro.board.platform"),
+            clusters: probe_clusters(),
+            refresh_rates: probe_refresh_rates(),
+            thermal_zones: probe_thermal_zones(),
+        }
+    }
+
+    pub fn max_refresh_rate(&self) -> Option<u32> {
+        self.refresh_rates.last().copied()
+    }
+}
+
+fn probe_clusters() -> Vec<Cluster> {
+    cpu_common::policies()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|info| Cluster {
+            policy: info.policy,
+            min_freq: info.freqs.first().copied().unwrap_or_default(),
+            max_freq: info.freqs.last().copied().unwrap_or_default(),
+            cpus: info.cpus,
+        })
+        .collect()
+}
+
+fn probe_refresh_rates() -> Vec<u32> {
+    let Some(dumper) = Dumpsys::new("display") else {
+        return Vec::new();
+    };
+
+    dumper
+        .dump(&[])
+        .map(|dump| parse_refresh_rates(&dump))
+        .unwrap_or_default()
+}
+
+fn parse_refresh_rates(dump: &str) -> Vec<u32> {
+    let mut rates: Vec<u32> = dump
+        .split("fps=")
+        .skip(1)
+        .filter_map(|s| {
+            let end = s
+                .find(|c: char| !c.is_ascii_digit() && c != '.')
+                .unwrap_or(s.len());
+            s[..end].parse::<f32>().ok()
+        })
+        .map(|fps| fps.round() as u32)
+        .filter(|fps| *fps > 0)
+        .collect();
+
+    rates.sort_unstable();
+    rates.dedup();
+    rates
+}
+
+fn probe_thermal_zones() -> Vec<ThermalZone> {
+    let Ok(entries) = fs::read_dir("/sys/class/thermal") else {
+        return Vec::new();
+    };
+
+    let mut zones: Vec<_> = entries
+        .filter_map(std::result::Result::ok)
+        .filter_map(|entry| {
+            let id = entry
+                .file_name()
+                .to_str()?
+                .strip_prefix("thermal_zone")?
+                .parse()
+                .ok()?;
+            let kind = fs::read_to_string(entry.path().join("type")).ok()?;
+
+            Some(ThermalZone {
+                id,
+                kind: kind.trim().to_string(),
+            })
+        })
+        .collect();
+
+    zones.sort_by_key(|zone| zone.id);
+    zones
+}
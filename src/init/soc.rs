@@ -0,0 +1,122 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::OnceLock;
+
+use crate::misc::getprop;
+
+static CURRENT: OnceLock<SocProfile> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SocFamily {
+    SnapdragonFlagship,
+    Snapdragon,
+    DimensityFlagship,
+    Dimensity,
+    Exynos,
+    Tensor,
+    Generic,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SocProfile {
+    pub family: SocFamily,
+    /// margins(ms) of powersave / balance / performance / fast
+    pub margins: [u64; 4],
+    /// frequency step(KHz) per unit of scale factor
+    pub base_freq: isize,
+    /// thermal zone types tried in order when `thermal.zone` is unset
+    pub thermal_zones: &'static [&'static str],
+    /// number of leading cpufreq policies made of little cores
+    pub little_clusters: usize,
+}
+
+impl SocProfile {
+    pub fn detect(platform: Option<&str>) -> Self {
+        let family = platform.map_or(SocFamily::Generic, SocFamily::from_platform);
+
+        let margins = match family {
+            SocFamily::SnapdragonFlagship | SocFamily::DimensityFlagship | SocFamily::Generic => {
+                [4, 3, 2, 1]
+            }
+            SocFamily::Snapdragon | SocFamily::Dimensity => [5, 4, 2, 1],
+            SocFamily::Exynos | SocFamily::Tensor => [6, 4, 3, 1],
+        };
+
+        let base_freq = match family {
+            SocFamily::SnapdragonFlagship | SocFamily::DimensityFlagship | SocFamily::Generic => {
+                600_000
+            }
+            SocFamily::Snapdragon | SocFamily::Dimensity => 500_000,
+            SocFamily::Exynos | SocFamily::Tensor => 400_000,
+        };
+
+        let thermal_zones: &[&str] = match family {
+            SocFamily::SnapdragonFlagship | SocFamily::Snapdragon => {
+                &["cpu-1-0-usr", "cpu-1-0", "cpuss-0"]
+            }
+            SocFamily::DimensityFlagship | SocFamily::Dimensity => {
+                &["mtktscpu", "soc_max", "cpu_big0"]
+            }
+            SocFamily::Exynos | SocFamily::Tensor => &["BIG", "big"],
+            SocFamily::Generic => &[],
+        };
+
+        // 8 Elite and Dimensity 9300 / 9400 have no little cores at all
+        let little_clusters = match platform.map(str::to_lowercase).as_deref() {
+            Some("sun" | "mt6989" | "mt6991") => 0,
+            _ => 1,
+        };
+
+        Self {
+            family,
+            margins,
+            base_freq,
+            thermal_zones,
+            little_clusters,
+        }
+    }
+
+    pub fn current() -> Self {
+        *CURRENT.get_or_init(|| {
+            let platform = getprop("ro.board.platform").or_else(|| getprop("ro.soc.model"));
+            Self::detect(platform.as_deref())
+        })
+    }
+}
+
+impl SocFamily {
+    fn from_platform(platform: &str) -> Self {
+        let platform = platform.to_lowercase();
+
+        match platform.as_str() {
+            "sun" | "pineapple" | "kalama" | "cape" | "taro" | "lahaina" | "kona" => {
+                Self::SnapdragonFlagship
+            }
+            "zuma" | "gs201" | "gs101" => Self::Tensor,
+            p if p.starts_with("mt69") => Self::DimensityFlagship,
+            p if p.starts_with("mt") => Self::Dimensity,
+            p if p.starts_with("exynos") || p.starts_with("s5e") || p.starts_with("erd") => {
+                Self::Exynos
+            }
+            p if p.starts_with("sm8") => Self::SnapdragonFlagship,
+            p if p.starts_with("sm") || p.starts_with("msm") || p.starts_with("sdm") => {
+                Self::Snapdragon
+            }
+            "crow" | "parrot" | "ukee" | "yupik" | "holi" | "bengal" | "lito" | "atoll"
+            | "trinket" => Self::Snapdragon,
+            _ => Self::Generic,
+        }
+    }
+}
@@ -0,0 +1,81 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    collections::BTreeSet,
+    fs::{self, set_permissions, OpenOptions},
+    io::Write,
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use log::{info, warn};
+
+/// Lives on tmpfs, so it survives a crash of fas-rs but not a reboot, which resets sysfs anyway
+const JOURNAL: &str = "/dev/fas_rs/journal";
+
+static RECORDED: Mutex<BTreeSet<PathBuf>> = Mutex::new(BTreeSet::new());
+
+/// Remember the current value of `path` before fas-rs first writes to it
+// Held across the append, so two writers can't both journal the same node
+#[allow(clippy::significant_drop_tightening)]
+pub fn record(path: &Path) {
+    let mut recorded = RECORDED.lock().unwrap();
+    if recorded.contains(path) {
+        return;
+    }
+
+    let Ok(value) = fs::read_to_string(path) else {
+        return;
+    };
+
+    let line = format!("{}\t{}\n", path.display(), value.trim());
+    let written = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(JOURNAL)
+        .and_then(|mut journal| journal.write_all(line.as_bytes()));
+
+    match written {
+        Ok(()) => {
+            recorded.insert(path.to_path_buf());
+        }
+        Err(e) => warn!("Failed to record {path:?} in journal: {e}"),
+    }
+}
+
+/// Put back every node recorded by a previous run that did not restore them itself
+pub fn restore() {
+    let Ok(journal) = fs::read_to_string(JOURNAL) else {
+        return;
+    };
+
+    let entries: Vec<_> = journal
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .collect();
+
+    // Twice, because limits like scaling_min_freq are rejected while the matching
+    // max is still below them
+    for _ in 0..2 {
+        for (path, value) in &entries {
+            let _ = set_permissions(path, PermissionsExt::from_mode(0o644));
+            let _ = fs::write(path, value);
+        }
+    }
+
+    info!("Restored {} nodes left by a previous run", entries.len());
+    let _ = fs::remove_file(JOURNAL);
+}
@@ -0,0 +1,123 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    io::{self, prelude::*},
+    path::Path,
+    sync::{Mutex, OnceLock},
+};
+
+use anyhow::Result;
+use flexi_logger::{
+    writers::{FileLogWriter, LogWriter},
+    Cleanup, Criterion, DeferredNow, FileSpec, LogSpecification, Logger, LoggerHandle, Naming,
+    Record,
+};
+use log::info;
+
+use crate::framework::LogLevel;
+
+const LOG_FILE_SIZE: u64 = 1024 * 1024;
+const LOG_FILE_KEEP: usize = 3;
+
+static LOGGER: OnceLock<(LoggerHandle, Mutex<LogLevel>)> = OnceLock::new();
+static LOG_FILE: OnceLock<FileLogWriter> = OnceLock::new();
+
+/// Logs to stdout, and to the rotating file once it was attached
+struct Output;
+
+impl LogWriter for Output {
+    fn write(&self, now: &mut DeferredNow, record: &Record) -> io::Result<()> {
+        let mut stdout = io::stdout().lock();
+        log_format(&mut stdout, now, record)?;
+        writeln!(stdout)?;
+
+        if let Some(file) = LOG_FILE.get() {
+            file.write(now, record)?;
+        }
+
+        Ok(())
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        io::stdout().flush()?;
+
+        if let Some(file) = LOG_FILE.get() {
+            file.flush()?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Start logging to stdout, before anything that could fail
+pub fn init(level: LogLevel) -> Result<()> {
+    let handle = Logger::with(spec(level))
+        .log_to_writer(Box::new(Output))
+        .start()?;
+
+    let _ = LOGGER.set((handle, Mutex::new(level)));
+
+    Ok(())
+}
+
+/// Also write the log to rotating files in `dir`, once the config was read
+pub fn attach_file<P: AsRef<Path>>(dir: P) -> Result<()> {
+    let file = FileLogWriter::builder(
+        FileSpec::default()
+            .directory(dir.as_ref())
+            .basename("fas-rs"),
+    )
+    .rotate(
+        Criterion::Size(LOG_FILE_SIZE),
+        Naming::Numbers,
+        Cleanup::KeepLogFiles(LOG_FILE_KEEP),
+    )
+    .format(log_format)
+    .try_build()?;
+
+    let _ = LOG_FILE.set(file);
+
+    Ok(())
+}
+
+pub fn set_level(level: LogLevel) {
+    let Some((handle, current)) = LOGGER.get() else {
+        return;
+    };
+
+    let mut current = current.lock().unwrap();
+    if *current != level {
+        info!("Switch log level: {current:?} -> {level:?}");
+        handle.set_new_spec(spec(level));
+        *current = level;
+    }
+}
+
+fn spec(level: LogLevel) -> LogSpecification {
+    match level {
+        LogLevel::Error => LogSpecification::error(),
+        LogLevel::Info => LogSpecification::info(),
+        LogLevel::Debug => LogSpecification::debug(),
+    }
+}
+
+fn log_format(
+    write: &mut dyn Write,
+    now: &mut DeferredNow,
+    record: &Record<'_>,
+) -> Result<(), io::Error> {
+    let time = now.format("%Y-%m-%d %H:%M");
+    write!(write, "[{time}] {}: {}", record.level(), record.args())
+}
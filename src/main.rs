@@ -22,41 +22,64 @@
     clippy::cast_possible_wrap
 )]
 
+mod check;
 mod cpu_common;
+mod doctor;
 mod framework;
+mod init;
+mod journal;
+mod logger;
+mod merge;
 mod misc;
+mod replay;
+mod sepolicy;
+mod top;
+mod trace;
 
 use std::{
     env, fs,
-    io::{self, prelude::*},
+    path::{Path, PathBuf},
     process,
 };
 
-use framework::prelude::*;
+use framework::{prelude::*, webui, LogLevel};
 
 use anyhow::Result;
-use flexi_logger::{DeferredNow, LogSpecification, Logger, Record};
-use log::{error, warn};
-
-#[cfg(debug_assertions)]
-use log::debug;
+use log::{debug, error, warn};
 
 use cpu_common::Controller;
+use init::SocProfile;
 use misc::setprop;
 
+#[cfg(all(feature = "use_mimalloc", feature = "use_jemalloc"))]
+compile_error!("use_mimalloc and use_jemalloc can't be enabled together");
+
+#[cfg(feature = "use_mimalloc")]
+#[global_allocator]
+static GLOBAL: mimalloc::MiMalloc = mimalloc::MiMalloc;
+
+#[cfg(feature = "use_jemalloc")]
+#[global_allocator]
+static GLOBAL: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
+
 const USER_CONFIG: &str = "/sdcard/Android/fas-rs/games.toml";
+const USER_CONFIG_EXTENSIONS: [&str; 4] = ["toml", "json", "yaml", "yml"];
 
 fn main() -> Result<()> {
     let args: Vec<_> = env::args().collect();
 
     if args[1] == "merge" {
-        let local = fs::read_to_string(USER_CONFIG)?;
-        let std = fs::read_to_string(&args[2])?;
-
-        let new = Config::merge(&local, &std).unwrap_or(std);
-        println!("{new}");
-
-        return Ok(());
+        return merge::merge(&args[2..], &user_config());
+    } else if args[1] == "check" {
+        return check::check(&args[2..], &user_config());
+    } else if args[1] == "doctor" {
+        return doctor::doctor(&args[2..], &user_config());
+    } else if args[1] == "top" {
+        return top::top(&args[2..]);
+    } else if args[1] == "init" {
+        return init::init(&args[2..], user_config());
+    } else if args[1] == "replay" || args[1] == "--replay" {
+        return replay::replay(&args[2..], user_config());
     } else if args[1] == "run" {
         setprop("fas-rs-server-started", "true");
         run(&args[2]).unwrap_or_else(|e| error!("{e:#?}"));
@@ -66,26 +89,38 @@ fn main() -> Result<()> {
 }
 
 fn run<S: AsRef<str>>(std_path: S) -> Result<()> {
-    #[cfg(not(debug_assertions))]
-    let logger_spec = LogSpecification::info();
-
-    #[cfg(debug_assertions)]
-    let logger_spec = LogSpecification::debug();
-
-    Logger::with(logger_spec)
-        .log_to_stdout()
-        .format(log_format)
-        .start()?;
+    logger::init(LogLevel::Info)?;
 
     let std_path = std_path.as_ref();
+    let _instance = misc::lock_instance()?;
+    misc::install_signal_handlers();
 
     let self_pid = process::id();
     let _ = fs::write("/dev/cpuset/background/cgroup.procs", self_pid.to_string());
+    misc::pin_to_little_cores();
+
+    let config = Config::new(user_config().as_path(), Path::new(std_path))?;
+
+    let log_dir = Path::new(std_path)
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join("logs");
+    logger::attach_file(log_dir).unwrap_or_else(|e| warn!("Failed to open log files: {e:#?}"));
+    logger::set_level(config.config().log_level);
+    sepolicy::patch();
+
+    let soc = SocProfile::current();
+    debug!("soc profile: {soc:?}");
+    cpu_common::set_base_freq(soc.base_freq);
+
+    let webui_port = config.config().webui_port;
+    if webui_port != 0 {
+        webui::spawn(webui_port).unwrap_or_else(|e| error!("{e:#?}"));
+    }
 
-    let config = Config::new(USER_CONFIG, std_path)?;
-    let cpu = Controller::new()?;
+    journal::restore();
+    let cpu = Controller::new(config.config().freq_backend)?;
 
-    #[cfg(debug_assertions)]
     debug!("{cpu:#?}");
 
     Scheduler::new()
@@ -96,11 +131,11 @@ fn run<S: AsRef<str>>(std_path: S) -> Result<()> {
     Ok(())
 }
 
-fn log_format(
-    write: &mut dyn Write,
-    now: &mut DeferredNow,
-    record: &Record<'_>,
-) -> Result<(), io::Error> {
-    let time = now.format("%Y-%m-%d %H:%M");
-    write!(write, "[{time}] {}: {}", record.level(), record.args())
+fn user_config() -> PathBuf {
+    let path = Path::new(USER_CONFIG);
+    USER_CONFIG_EXTENSIONS
+        .iter()
+        .map(|extension| path.with_extension(extension))
+        .find(|path| path.exists())
+        .unwrap_or_else(|| path.to_path_buf())
 }
@@ -0,0 +1,143 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, bail, Result};
+use toml::Table;
+
+use crate::framework::{Config, Format};
+
+const HELP: &str = "\
+fas-rs merge <std> [options]:
+    --config <path>     user config to merge into
+    --in-place          write the merged config back to the user config
+    --dry-run           only print what would change\
+";
+
+struct Options {
+    std: String,
+    config: Option<String>,
+    in_place: bool,
+    dry_run: bool,
+}
+
+impl Options {
+    fn parse(args: &[String]) -> Result<Self> {
+        let mut args = args.iter();
+        let std = args
+            .next()
+            .cloned()
+            .ok_or_else(|| anyhow!("Missing standard config\n{HELP}"))?;
+
+        let mut options = Self {
+            std,
+            config: None,
+            in_place: false,
+            dry_run: false,
+        };
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--config" => {
+                    options.config = Some(
+                        args.next()
+                            .cloned()
+                            .ok_or_else(|| anyhow!("Missing value of {arg}\n{HELP}"))?,
+                    );
+                }
+                "--in-place" => options.in_place = true,
+                "--dry-run" => options.dry_run = true,
+                _ => bail!("Unknown argument {arg}\n{HELP}"),
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+pub fn merge(args: &[String], default_config: &Path) -> Result<()> {
+    let options = Options::parse(args)?;
+    let config_path = options
+        .config
+        .as_deref()
+        .map_or_else(|| default_config.to_path_buf(), PathBuf::from);
+    let format = Format::from_path(&config_path);
+
+    let local = fs::read_to_string(&config_path)?;
+    let std = fs::read_to_string(&options.std)?;
+
+    // Only a local config that parses can be diffed, a broken one is replaced as a whole
+    let (new, diff) = match Config::merge(&local, &std, format) {
+        Ok(new) => {
+            let diff = Config::diff(&local, &new, format)?;
+            (new, Some(diff))
+        }
+        Err(e) => {
+            eprintln!("Failed to merge, reset to the standard config: {e}");
+            let std = match format {
+                Format::Toml => std.clone(),
+                // Comments of the standard config only survive in toml
+                format => format.serialize(&toml::from_str::<Table>(&std)?)?,
+            };
+            (std, None)
+        }
+    };
+
+    match &diff {
+        Some(diff) if diff.is_empty() => eprintln!("No changes"),
+        Some(diff) => {
+            for line in diff {
+                eprintln!("{line}");
+            }
+            eprintln!("{} change(s)", diff.len());
+        }
+        None => eprintln!("Replace the whole config"),
+    }
+
+    if options.dry_run {
+        return Ok(());
+    }
+
+    if options.in_place {
+        if diff.is_some_and(|diff| diff.is_empty()) {
+            return Ok(());
+        }
+
+        let backup = with_suffix(&config_path, ".bak");
+        let temp = with_suffix(&config_path, ".tmp");
+        fs::copy(&config_path, &backup)?;
+        fs::write(&temp, &new)?;
+        fs::rename(&temp, &config_path)?;
+        eprintln!(
+            "Merged into {}, backup at {}",
+            config_path.display(),
+            backup.display()
+        );
+    } else {
+        println!("{new}");
+    }
+
+    Ok(())
+}
+
+/// `games.yaml` -> `games.yaml.bak`, whatever the format of the config is
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
@@ -12,10 +12,157 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::process::Command;
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{Read, Seek, Write},
+    mem,
+    os::fd::AsRawFd,
+    process::{self, Command},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use anyhow::{bail, Result};
+use log::{debug, warn};
+
+use crate::cpu_common;
+
+const INSTANCE_LOCK: &str = "/dev/fas_rs/fas-rs.pid";
+
+static SHUTDOWN: AtomicBool = AtomicBool::new(false);
+static DUMP: AtomicBool = AtomicBool::new(false);
+static RELOAD: AtomicBool = AtomicBool::new(false);
 
 pub fn setprop<S: AsRef<str>>(k: S, v: S) {
     let key = k.as_ref();
     let value = v.as_ref();
     let _ = Command::new("setprop").args([key, value]).spawn();
 }
+
+pub fn getprop<S: AsRef<str>>(k: S) -> Option<String> {
+    let key = k.as_ref();
+    let output = Command::new("getprop").arg(key).output().ok()?;
+    let value = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+    if value.is_empty() {
+        None
+    } else {
+        Some(value)
+    }
+}
+
+/// Hold an exclusive lock on the pidfile for as long as the returned file lives,
+/// the kernel drops it when the process exits, even on a crash
+pub fn lock_instance() -> Result<File> {
+    let _ = fs::create_dir_all("/dev/fas_rs");
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(INSTANCE_LOCK)?;
+
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } != 0 {
+        let mut pid = String::new();
+        let _ = file.read_to_string(&mut pid);
+        bail!("fas-rs is already running (pid {})", pid.trim());
+    }
+
+    file.set_len(0)?;
+    file.rewind()?;
+    write!(file, "{}", process::id())?;
+
+    Ok(file)
+}
+
+/// Pid of the running daemon, found by its instance lock being held
+pub fn running_instance() -> Option<String> {
+    let mut file = File::open(INSTANCE_LOCK).ok()?;
+
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_SH | libc::LOCK_NB) } == 0 {
+        return None;
+    }
+
+    let mut pid = String::new();
+    let _ = file.read_to_string(&mut pid);
+    Some(pid.trim().to_string())
+}
+
+extern "C" fn request_shutdown(_: libc::c_int) {
+    SHUTDOWN.store(true, Ordering::Release);
+}
+
+extern "C" fn on_sigquit(_: libc::c_int) {
+    request_dump();
+}
+
+extern "C" fn on_sighup(_: libc::c_int) {
+    RELOAD.store(true, Ordering::Release);
+}
+
+/// Turn SIGTERM/SIGINT into a flag the looper polls, so it can restore everything before exiting,
+/// SIGQUIT into a state dump request and SIGHUP into a frame analyzer reload
+pub fn install_signal_handlers() {
+    for signal in [libc::SIGTERM, libc::SIGINT] {
+        unsafe {
+            libc::signal(signal, request_shutdown as *const () as libc::sighandler_t);
+        }
+    }
+
+    unsafe {
+        libc::signal(libc::SIGQUIT, on_sigquit as *const () as libc::sighandler_t);
+        libc::signal(libc::SIGHUP, on_sighup as *const () as libc::sighandler_t);
+    }
+}
+
+pub fn shutdown_requested() -> bool {
+    SHUTDOWN.load(Ordering::Acquire)
+}
+
+pub fn request_dump() {
+    DUMP.store(true, Ordering::Release);
+}
+
+/// Whether a state dump was requested since the last call
+pub fn take_dump_request() -> bool {
+    DUMP.swap(false, Ordering::AcqRel)
+}
+
+/// Whether a frame analyzer reload was requested since the last call
+#[cfg_attr(not(feature = "use_ebpf"), allow(dead_code))]
+pub fn take_reload_request() -> bool {
+    RELOAD.swap(false, Ordering::AcqRel)
+}
+
+/// Keep fas-rs on the cluster with the lowest max frequency so it never takes big core time
+/// from the game, threads spawned afterwards inherit the mask
+pub fn pin_to_little_cores() {
+    let Some(little) = cpu_common::policies().ok().and_then(|policies| {
+        policies
+            .into_iter()
+            .min_by_key(|policy| policy.freqs.last().copied())
+    }) else {
+        return;
+    };
+
+    unsafe {
+        let mut set: libc::cpu_set_t = mem::zeroed();
+        for cpu in &little.cpus {
+            libc::CPU_SET(*cpu as usize, &mut set);
+        }
+
+        if libc::sched_setaffinity(0, mem::size_of::<libc::cpu_set_t>(), &raw const set) == 0 {
+            debug!("Pinned to cpus {:?}", little.cpus);
+        } else {
+            warn!("Failed to pin to cpus {:?}", little.cpus);
+        }
+    }
+}
+
+/// Run the calling thread only when a cpu would otherwise idle, for threads whose latency
+/// doesn't matter
+pub fn set_idle_priority() {
+    let param = libc::sched_param { sched_priority: 0 };
+    unsafe {
+        libc::sched_setscheduler(0, libc::SCHED_IDLE, &raw const param);
+    }
+}
@@ -0,0 +1,159 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{collections::BTreeSet, fs, path::Path, time::Duration};
+
+use anyhow::{anyhow, bail, Result};
+
+use crate::framework::{replay as replay_frames, Config, Mode};
+
+const HELP: &str = "\
+fas-rs replay <record.csv> [options]:
+    --config <path>     config used for the replay
+    --pkg <package>     package used to look up target fps
+    --mode <mode>       powersave / balance / performance / fast
+
+Only the frame buffer and the frequency controller are replayed. Relax, thermal and
+battery fps caps, the target ladder, cooldown, boosts and extensions are skipped.\
+";
+
+/// Frequency table used when the record has no usable frequency column
+const DEFAULT_FREQS: [isize; 2] = [300_000, 3_000_000];
+
+struct Options {
+    record: String,
+    config: Option<String>,
+    pkg: Option<String>,
+    mode: Mode,
+}
+
+impl Options {
+    fn parse(args: &[String]) -> Result<Self> {
+        let mut args = args.iter();
+        let record = args
+            .next()
+            .cloned()
+            .ok_or_else(|| anyhow!("Missing record file\n{HELP}"))?;
+
+        let mut options = Self {
+            record,
+            config: None,
+            pkg: None,
+            mode: Mode::Balance,
+        };
+
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .cloned()
+                    .ok_or_else(|| anyhow!("Missing value of {arg}\n{HELP}"))
+            };
+
+            match arg.as_str() {
+                "--config" => options.config = Some(value()?),
+                "--pkg" => options.pkg = Some(value()?),
+                "--mode" => {
+                    options.mode = value()?
+                        .parse()
+                        .map_err(|_| anyhow!("Unknown mode\n{HELP}"))?;
+                }
+                _ => bail!("Unknown argument {arg}\n{HELP}"),
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+pub fn replay<P: AsRef<Path>>(args: &[String], default_config: P) -> Result<()> {
+    let options = Options::parse(args)?;
+    let config_path = options
+        .config
+        .as_deref()
+        .map_or_else(|| default_config.as_ref().to_path_buf(), Into::into);
+    let config = Config::new(&config_path, &config_path)?;
+
+    let pkg = options.pkg.clone().unwrap_or_else(|| {
+        Path::new(&options.record)
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.rsplit_once('_'))
+            .map(|(pkg, _)| pkg.to_string())
+            .unwrap_or_default()
+    });
+
+    let record = fs::read_to_string(&options.record)?;
+    let (frames, freqs) = parse_record(&record);
+
+    let result = replay_frames(&config, &pkg, options.mode, &frames, &freqs)?;
+
+    println!("timestamp_ms,frametime_us,target_fps,policy_freq");
+    for frame in result {
+        println!(
+            "{},{},{},{}",
+            frame.timestamp.as_millis(),
+            frame.frametime.as_micros(),
+            frame.target_fps.unwrap_or_default(),
+            frame.policy_freq
+        );
+    }
+
+    Ok(())
+}
+
+/// Frames as (timestamp, frametime) and the frequencies seen for each recorded policy
+fn parse_record(record: &str) -> (Vec<(Duration, Duration)>, Vec<Vec<isize>>) {
+    let mut frames = Vec::new();
+    let mut freqs: Vec<BTreeSet<isize>> = Vec::new();
+
+    for line in record.lines().skip(1) {
+        let mut columns = line.split(',');
+        let (Some(Ok(timestamp)), Some(Ok(frametime))) = (
+            columns.next().map(str::parse),
+            columns.next().map(str::parse),
+        ) else {
+            continue;
+        };
+
+        // Offline policies are recorded as 0 and the last column is the temperature
+        for (policy, freq) in columns.enumerate() {
+            let Ok(freq) = freq.parse::<isize>() else {
+                continue;
+            };
+
+            if freq >= 100_000 {
+                if freqs.len() <= policy {
+                    freqs.resize_with(policy + 1, BTreeSet::new);
+                }
+                freqs[policy].insert(freq);
+            }
+        }
+
+        frames.push((
+            Duration::from_millis(timestamp),
+            Duration::from_micros(frametime),
+        ));
+    }
+
+    let mut freqs: Vec<Vec<isize>> = freqs
+        .into_iter()
+        .filter(|freqs| !freqs.is_empty())
+        .map(|freqs| freqs.into_iter().collect())
+        .collect();
+    if freqs.is_empty() {
+        freqs.push(DEFAULT_FREQS.to_vec());
+    }
+
+    (frames, freqs)
+}
@@ -0,0 +1,152 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    fs::{self, OpenOptions},
+    io::ErrorKind,
+    path::Path,
+    process::Command,
+};
+
+use log::{info, warn};
+
+const SELINUX_ENFORCE: &str = "/sys/fs/selinux/enforce";
+const CPUFREQ_PROBE: &str = "/sys/devices/system/cpu/cpufreq/policy0/scaling_max_freq";
+
+/// A tool able to patch the live policy
+struct Patcher {
+    path: &'static str,
+    args: &'static [&'static str],
+    /// Takes every rule in one argument separated by `;` instead of one argument per rule
+    joined: bool,
+}
+
+const PATCHERS: &[Patcher] = &[
+    Patcher {
+        path: "/data/adb/ksud",
+        args: &["sepolicy", "patch"],
+        joined: true,
+    },
+    Patcher {
+        path: "/data/adb/ap/bin/magiskpolicy",
+        args: &["--live"],
+        joined: false,
+    },
+    Patcher {
+        path: "/data/adb/magisk/magiskpolicy",
+        args: &["--live"],
+        joined: false,
+    },
+    Patcher {
+        path: "/debug_ramdisk/magiskpolicy",
+        args: &["--live"],
+        joined: false,
+    },
+    Patcher {
+        path: "/system/bin/magiskpolicy",
+        args: &["--live"],
+        joined: false,
+    },
+];
+
+/// Make sure the nodes fas-rs needs are not denied by selinux, patching the live policy
+/// for our own domain if any of them is
+pub fn patch() {
+    if fs::read_to_string(SELINUX_ENFORCE).map_or(true, |mode| mode.trim() != "1") {
+        return;
+    }
+
+    let denied: Vec<_> = probes()
+        .into_iter()
+        .filter(|(_, denied)| *denied)
+        .map(|(node, _)| node)
+        .collect();
+    if denied.is_empty() {
+        return;
+    }
+
+    let Some(domain) = domain() else {
+        warn!("Access to {denied:?} denied, but the selinux domain of fas-rs is unknown");
+        return;
+    };
+    info!("Access to {denied:?} denied in domain {domain}, patching sepolicy");
+
+    let rules = rules(&domain);
+    let Some(patcher) = PATCHERS
+        .iter()
+        .find(|patcher| Path::new(patcher.path).exists())
+    else {
+        warn!("No sepolicy patcher found, nodes denied by selinux stay unusable");
+        return;
+    };
+
+    let mut command = Command::new(patcher.path);
+    command.args(patcher.args);
+    if patcher.joined {
+        command.arg(rules.join("; "));
+    } else {
+        command.args(&rules);
+    }
+
+    match command.status() {
+        Ok(status) if status.success() => info!("Applied {} sepolicy rules", rules.len()),
+        Ok(status) => warn!("{} exited with {status}", patcher.path),
+        Err(e) => warn!("Failed to run {}: {e}", patcher.path),
+    }
+
+    for (node, denied) in probes() {
+        if denied {
+            warn!("Access to {node} is still denied after patching sepolicy");
+        }
+    }
+}
+
+fn denied<T>(result: std::io::Result<T>) -> bool {
+    result.is_err_and(|e| e.kind() == ErrorKind::PermissionDenied)
+}
+
+fn probes() -> [(&'static str, bool); 3] {
+    [
+        (
+            "cpufreq",
+            denied(OpenOptions::new().write(true).open(CPUFREQ_PROBE)),
+        ),
+        ("bpf", denied(fs::read_dir("/sys/fs/bpf"))),
+        ("input", denied(fs::read_dir("/dev/input"))),
+    ]
+}
+
+/// Type of the current process context, e.g. `magisk` for `u:r:magisk:s0`
+fn domain() -> Option<String> {
+    let context = fs::read_to_string("/proc/self/attr/current").ok()?;
+    let domain = context.trim_end_matches('\0').trim().split(':').nth(2)?;
+    Some(domain.to_string())
+}
+
+fn rules(domain: &str) -> Vec<String> {
+    [
+        "allow {} sysfs_devices_system_cpu file { read write open getattr setattr }",
+        "allow {} sysfs_devices_system_cpu dir { read open search getattr }",
+        "allow {} sysfs file { read write open getattr setattr }",
+        "allow {} self capability2 { bpf perfmon }",
+        "allow {} self bpf { map_create map_read map_write prog_load prog_run }",
+        "allow {} fs_bpf dir { read open search write add_name create getattr }",
+        "allow {} fs_bpf file { read write open create getattr }",
+        "allow {} input_device dir { read open search getattr }",
+        "allow {} input_device chr_file { read open ioctl getattr }",
+    ]
+    .iter()
+    .map(|rule| rule.replacen("{}", domain, 1))
+    .collect()
+}
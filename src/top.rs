@@ -0,0 +1,181 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Write as _,
+    fs,
+    io::{self, Write},
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Result};
+
+const HELP: &str = "\
+fas-rs top [options]:
+    --once              print the status once and exit\
+";
+
+const STATUS_NODE: &str = "/dev/fas_rs/status";
+const REFRESH_TIME: Duration = Duration::from_secs(1);
+const MAX_TRANSITIONS: usize = 8;
+const BAR_WIDTH: usize = 30;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Snapshot {
+    state: String,
+    mode: String,
+    pkg: String,
+    target_fps: String,
+}
+
+pub fn top(args: &[String]) -> Result<()> {
+    let mut once = false;
+    for arg in args {
+        match arg.as_str() {
+            "--once" => once = true,
+            _ => bail!("Unknown argument {arg}\n{HELP}"),
+        }
+    }
+
+    let start = Instant::now();
+    let mut last: Option<Snapshot> = None;
+    let mut transitions = VecDeque::with_capacity(MAX_TRANSITIONS);
+
+    loop {
+        let Ok(status) = fs::read_to_string(STATUS_NODE) else {
+            bail!("Failed to read {STATUS_NODE}, is fas-rs running?");
+        };
+        let status = parse_status(&status);
+
+        let snapshot = Snapshot {
+            state: field(&status, "state").to_string(),
+            mode: field(&status, "mode").to_string(),
+            pkg: field(&status, "pkg").to_string(),
+            target_fps: field(&status, "target_fps").to_string(),
+        };
+
+        if let Some(last) = last.as_ref().filter(|last| **last != snapshot) {
+            if transitions.len() == MAX_TRANSITIONS {
+                transitions.pop_front();
+            }
+            transitions.push_back(format!(
+                "+{:>5}s  {} [{}] -> {} [{}] {}",
+                start.elapsed().as_secs(),
+                last.state,
+                last.pkg,
+                snapshot.state,
+                snapshot.pkg,
+                describe_change(last, &snapshot),
+            ));
+        }
+        last = Some(snapshot);
+
+        let screen = render(&status, &transitions);
+        let mut stdout = io::stdout().lock();
+        if once {
+            write!(stdout, "{screen}")?;
+            return Ok(());
+        }
+
+        write!(stdout, "\x1b[2J\x1b[H{screen}")?;
+        stdout.flush()?;
+        drop(stdout);
+
+        thread::sleep(REFRESH_TIME);
+    }
+}
+
+fn parse_status(status: &str) -> HashMap<&str, &str> {
+    status
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim(), value.trim()))
+        .collect()
+}
+
+fn field<'a>(status: &HashMap<&str, &'a str>, key: &str) -> &'a str {
+    status.get(key).copied().unwrap_or("-")
+}
+
+fn describe_change(last: &Snapshot, now: &Snapshot) -> String {
+    let mut change = String::new();
+
+    if last.mode != now.mode {
+        let _ = write!(change, "mode {} -> {} ", last.mode, now.mode);
+    }
+
+    if last.target_fps != now.target_fps {
+        let _ = write!(change, "target {} -> {}", last.target_fps, now.target_fps);
+    }
+
+    change
+}
+
+fn render(status: &HashMap<&str, &str>, transitions: &VecDeque<String>) -> String {
+    let mut screen = String::new();
+
+    let _ = writeln!(screen, "fas-rs top (Ctrl-C to quit)\n");
+    let _ = writeln!(
+        screen,
+        "state: {:<12} mode: {:<12} write conflicts: {}",
+        field(status, "state"),
+        field(status, "mode"),
+        field(status, "write_conflicts"),
+    );
+    let _ = writeln!(screen, "game:  {}", field(status, "pkg"));
+
+    let current_fps: f64 = field(status, "current_fps").parse().unwrap_or_default();
+    let target_fps: f64 = field(status, "target_fps").parse().unwrap_or_default();
+    let _ = writeln!(
+        screen,
+        "fps:   {current_fps:>6.1} / {:<6} {}",
+        field(status, "target_fps"),
+        bar(current_fps, target_fps),
+    );
+
+    let temp = field(status, "temp");
+    if temp != "-" {
+        let _ = writeln!(screen, "temp:  {temp}°C");
+    }
+
+    let _ = writeln!(
+        screen,
+        "\npolicy freq: {} KHz",
+        field(status, "policy_freq")
+    );
+    for (cluster, freq) in field(status, "freqs").split(',').enumerate() {
+        let _ = writeln!(screen, "  cluster{cluster}: {freq:>8} KHz");
+    }
+
+    let _ = writeln!(screen, "\ntransitions:");
+    if transitions.is_empty() {
+        let _ = writeln!(screen, "  (none yet)");
+    }
+    for transition in transitions.iter().rev() {
+        let _ = writeln!(screen, "  {transition}");
+    }
+
+    screen
+}
+
+fn bar(value: f64, max: f64) -> String {
+    if max <= 0.0 {
+        return String::new();
+    }
+
+    let filled = ((value / max).clamp(0.0, 1.0) * BAR_WIDTH as f64).round() as usize;
+    format!("[{}{}]", "#".repeat(filled), " ".repeat(BAR_WIDTH - filled))
+}
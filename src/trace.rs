@@ -0,0 +1,68 @@
+// Copyright 2023 shadow3aaa@gitbub.com
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    fmt::Display,
+    fs::{File, OpenOptions},
+    io::prelude::*,
+    process,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
+
+const TRACE_MARKERS: [&str; 2] = [
+    "/sys/kernel/tracing/trace_marker",
+    "/sys/kernel/debug/tracing/trace_marker",
+];
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static MARKER: Mutex<Option<File>> = Mutex::new(None);
+
+pub fn set_enabled(enabled: bool) {
+    if ENABLED.swap(enabled, Ordering::AcqRel) == enabled {
+        return;
+    }
+
+    let mut marker = MARKER.lock().unwrap();
+    *marker = if enabled {
+        TRACE_MARKERS
+            .iter()
+            .find_map(|path| OpenOptions::new().write(true).open(path).ok())
+    } else {
+        None
+    };
+}
+
+pub fn counter<N: Display, V: Display>(name: N, value: V) {
+    write_marker(format_args!("C|{}|{name}|{value}", process::id()));
+}
+
+pub fn event(name: &str) {
+    let pid = process::id();
+    write_marker(format_args!("B|{pid}|{name}"));
+    write_marker(format_args!("E|{pid}"));
+}
+
+fn write_marker(args: std::fmt::Arguments<'_>) {
+    if !ENABLED.load(Ordering::Acquire) {
+        return;
+    }
+
+    if let Some(marker) = MARKER.lock().unwrap().as_mut() {
+        // Each write to trace_marker is a separate event, so the marker must go out in one piece
+        let _ = marker.write_all(args.to_string().as_bytes());
+    }
+}